@@ -0,0 +1,65 @@
+use std::collections::hash_map::DefaultHasher;
+use std::env;
+use std::fs;
+use std::hash::Hasher;
+use std::path::{Path, PathBuf};
+
+/// Generate the list of builtin/*.sk to embed them into the compiler
+/// (see `runner::load_builtin`) and the fingerprint of the compiler
+/// (see `runner::load_corelib_exports`)
+fn main() {
+    println!("cargo:rerun-if-changed=builtin");
+    println!("cargo:rerun-if-changed=src");
+    let mut paths = fs::read_dir("builtin")
+        .expect("./builtin not found")
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().map_or(false, |ext| ext == "sk"))
+        .collect::<Vec<_>>();
+    // Sort to make the load order deterministic
+    paths.sort();
+
+    let mut code = "pub static BUILTIN_FILES: &[(&str, &str)] = &[\n".to_string();
+    for path in &paths {
+        let abs_path = fs::canonicalize(&path).unwrap();
+        code += &format!(
+            "    ({:?}, include_str!({:?})),\n",
+            path.to_str().unwrap(),
+            abs_path.to_str().unwrap()
+        );
+    }
+    code += "];\n";
+
+    // The precompiled corelib must be rebuilt when builtin/*.sk or the
+    // compiler is changed
+    let mut hasher = DefaultHasher::new();
+    hasher.write(env::var("CARGO_PKG_VERSION").unwrap().as_bytes());
+    let mut sources = paths.clone();
+    collect_rs_files(Path::new("src"), &mut sources);
+    for path in sources {
+        hasher.write(path.to_str().unwrap().as_bytes());
+        hasher.write(&fs::read(&path).unwrap());
+    }
+    code += &format!(
+        "pub static CORELIB_FINGERPRINT: &str = \"{:016x}\";\n",
+        hasher.finish()
+    );
+
+    let out_path = Path::new(&env::var("OUT_DIR").unwrap()).join("builtin.rs");
+    fs::write(out_path, code).unwrap();
+}
+
+/// Append the paths of src/**/*.rs to `paths` (sorted)
+fn collect_rs_files(dir: &Path, paths: &mut Vec<PathBuf>) {
+    let mut entries = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect::<Vec<_>>();
+    entries.sort();
+    for path in entries {
+        if path.is_dir() {
+            collect_rs_files(&path, paths);
+        } else if path.extension().map_or(false, |ext| ext == "rs") {
+            paths.push(path);
+        }
+    }
+}
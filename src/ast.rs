@@ -1,9 +1,23 @@
+use crate::location::LocationSpan;
 use crate::names::*;
 use crate::parser::token::Token;
+use std::collections::HashSet;
 
 #[derive(Debug, PartialEq)]
 pub struct Program {
     pub toplevel_items: Vec<TopLevelItem>,
+    /// `require`d files
+    pub requires: Vec<Require>,
+    /// Arities of the functions used in the program (eg. 2 for
+    /// `Fn2<Int, Int, Int>` or `fn(x: Int, y: Int){ ... }`)
+    pub fn_arities: HashSet<usize>,
+}
+
+/// `require "foo"`
+#[derive(Debug, PartialEq)]
+pub struct Require {
+    pub path: String,
+    pub locs: LocationSpan,
 }
 
 #[derive(Debug, PartialEq)]
@@ -12,31 +26,95 @@ pub enum TopLevelItem {
     Expr(AstExpression),
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum Definition {
     ClassDefinition {
         name: ClassFirstname,
         typarams: Vec<String>,
         super_name: ClassFullname,
         defs: Vec<Definition>,
+        locs: LocationSpan,
+    },
+    ModuleDefinition {
+        name: ClassFirstname,
+        defs: Vec<Definition>,
+        locs: LocationSpan,
+    },
+    /// `enum Foo; case Bar(x: Int); end`
+    EnumDefinition {
+        name: ClassFirstname,
+        cases: Vec<EnumCase>,
+        defs: Vec<Definition>,
+        locs: LocationSpan,
+    },
+    /// `include Foo` in a class
+    IncludeDefinition {
+        module_name: ClassFullname,
+        locs: LocationSpan,
+    },
+    /// `extend Foo` in a class
+    ExtendDefinition {
+        module_name: ClassFullname,
+        locs: LocationSpan,
     },
     InstanceMethodDefinition {
         sig: AstMethodSignature,
         body_exprs: Vec<AstExpression>,
+        locs: LocationSpan,
     },
     ClassMethodDefinition {
         sig: AstMethodSignature,
         body_exprs: Vec<AstExpression>,
+        locs: LocationSpan,
     },
     ConstDefinition {
         name: ConstFirstname,
         expr: AstExpression,
+        locs: LocationSpan,
     },
 }
 
-#[derive(Debug, PartialEq)]
+/// A case of an enum (eg. `case Circle(r: Float)`)
+#[derive(Debug, PartialEq, Clone)]
+pub struct EnumCase {
+    pub name: ClassFirstname,
+    pub params: Vec<Param>,
+    pub locs: LocationSpan,
+}
+
+impl EnumCase {
+    /// Definitions in the class of this case (an initializer which sets
+    /// the params to the ivars of the same name)
+    pub fn defs(&self) -> Vec<Definition> {
+        if self.params.is_empty() {
+            return vec![];
+        }
+        let body_exprs = self
+            .params
+            .iter()
+            .map(|param| assignment(ivar_ref(param.name.clone()), bare_name(&param.name)))
+            .collect();
+        vec![Definition::InstanceMethodDefinition {
+            sig: AstMethodSignature {
+                name: method_firstname("initialize"),
+                typarams: vec![],
+                params: self.params.clone(),
+                ret_typ: Typ {
+                    name: "Void".to_string(),
+                    typ_args: vec![],
+                },
+            },
+            body_exprs,
+            locs: self.locs.clone(),
+        }]
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
 pub struct AstMethodSignature {
     pub name: MethodFirstname,
+    /// Type parameters of a generic method (eg. `U` of `def map<U>`)
+    pub typarams: Vec<String>,
     pub params: Vec<Param>,
     pub ret_typ: Typ,
 }
@@ -47,16 +125,32 @@ pub struct Param {
     pub typ: Typ,
 }
 
+/// Parameter of a lambda or a block. The type may be omitted for a block
+/// parameter (eg. `|x|`) and is inferred from the method signature
+#[derive(Debug, PartialEq, Clone)]
+pub struct BlockParam {
+    pub name: String,
+    pub opt_typ: Option<Typ>,
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct Typ {
     pub name: String,
     pub typ_args: Vec<Typ>,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, Clone)]
 pub struct AstExpression {
     pub body: AstExpressionBody,
     pub primary: bool,
+    pub locs: LocationSpan,
+}
+
+// Ignore `locs` so that the parser tests need not to write the positions
+impl PartialEq for AstExpression {
+    fn eq(&self, other: &Self) -> bool {
+        self.body == other.body && self.primary == other.primary
+    }
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -77,16 +171,55 @@ pub enum AstExpressionBody {
         then_exprs: Vec<AstExpression>,
         else_exprs: Option<Vec<AstExpression>>,
     },
+    /// `if let x = maybe then ... else ... end`
+    IfLet {
+        name: String,
+        expr: Box<AstExpression>,
+        then_exprs: Vec<AstExpression>,
+        else_exprs: Option<Vec<AstExpression>>,
+    },
     While {
         cond_expr: Box<AstExpression>,
         body_exprs: Vec<AstExpression>,
+        label: Option<String>,
+    },
+    /// `loop do ... end`
+    Loop {
+        body_exprs: Vec<AstExpression>,
+        label: Option<String>,
+    },
+    /// `break` (`arg` is the value of the `loop`, if any)
+    Break {
+        label: Option<String>,
+        arg: Option<Box<AstExpression>>,
+    },
+    Next {
+        label: Option<String>,
+    },
+    /// `begin ... rescue => e ... ensure ... end`
+    Begin {
+        body_exprs: Vec<AstExpression>,
+        /// Name of the variable bound to the exception (`e` of `rescue => e`)
+        rescue_var: Option<String>,
+        rescue_exprs: Option<Vec<AstExpression>>,
+        ensure_exprs: Option<Vec<AstExpression>>,
+    },
+    /// `match expr when pattern ... end`
+    Match {
+        cond_expr: Box<AstExpression>,
+        clauses: Vec<AstMatchClause>,
+    },
+    /// `return` (`arg` is None if no value is given)
+    Return {
+        arg: Option<Box<AstExpression>>,
     },
-    Break,
     LVarAssign {
         name: String,
         rhs: Box<AstExpression>,
         /// Whether declared with `var` (TODO: rename to `readonly`?)
         is_var: bool,
+        /// Type annotation (eg. `Int` of `x: Int = 1`)
+        typ: Option<Typ>,
     },
     IVarAssign {
         name: String,
@@ -98,15 +231,38 @@ pub enum AstExpressionBody {
         names: Vec<String>,
         rhs: Box<AstExpression>,
     },
+    /// `a.b op= c` or `a[b] op= c`. The receiver and the arguments of `lhs`
+    /// are evaluated only once. (`op=` on variables are desugared by the parser)
+    OpAssign {
+        lhs: Box<AstExpression>,
+        op: String,
+        rhs: Box<AstExpression>,
+    },
     MethodCall {
         receiver_expr: Option<Box<AstExpression>>, // Box is needed to aboid E0072
         method_name: MethodFirstname,
+        /// Explicit type arguments for a generic method (eg. `Int` of `foo<Int>(x)`)
+        type_args: Vec<Typ>,
         arg_exprs: Vec<AstExpression>,
         may_have_paren_wo_args: bool,
     },
+    /// `maybe?.foo(args)`
+    SafeMethodCall {
+        receiver_expr: Box<AstExpression>,
+        method_name: MethodFirstname,
+        type_args: Vec<Typ>,
+        arg_exprs: Vec<AstExpression>,
+    },
+    /// `super(args)`. `arg_exprs` is None for `super` without parentheses,
+    /// which passes the arguments of the current method
+    SuperCall {
+        arg_exprs: Option<Vec<AstExpression>>,
+    },
     LambdaExpr {
-        params: Vec<Param>,
+        params: Vec<BlockParam>,
         exprs: Vec<AstExpression>,
+        /// false if this is a block (`do ... end` or `{ ... }`)
+        is_fn: bool,
     },
     // Local variable reference or method call with implicit receiver(self)
     BareName(String),
@@ -117,12 +273,42 @@ pub enum AstExpressionBody {
     FloatLiteral {
         value: f64,
     },
+    /// The range is checked in HIR because it depends on where the literal
+    /// is (eg. `-9223372036854775808`, `18446744073709551615.to_u64`)
     DecimalLiteral {
-        value: i32,
+        value: u64,
     },
     StringLiteral {
         content: String,
     },
+    /// `"...#{expr}..."`. `parts` are the string literals and the
+    /// embedded expressions in order
+    StringWithInterpolation {
+        parts: Vec<AstExpression>,
+    },
+}
+
+/// `when pattern if guard then body` (or `else body`) of `match`
+#[derive(Debug, PartialEq, Clone)]
+pub struct AstMatchClause {
+    pub pattern: AstPattern,
+    pub guard: Option<AstExpression>,
+    pub body_exprs: Vec<AstExpression>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum AstPattern {
+    /// `Foo::Bar(a, b)` (matches a case of an enum)
+    ExtractorPattern {
+        names: Vec<String>,
+        params: Vec<AstPattern>,
+    },
+    /// `a` (matches anything and binds it to `a`)
+    VariablePattern(String),
+    /// `_` (matches anything)
+    WildcardPattern,
+    /// `1`, `true`, etc. (compared with `==`)
+    LiteralPattern(Box<AstExpression>),
 }
 
 impl Definition {
@@ -132,9 +318,28 @@ impl Definition {
             _ => false,
         }
     }
+
+    pub fn locs(&self) -> &LocationSpan {
+        match self {
+            Definition::ClassDefinition { locs, .. } => locs,
+            Definition::ModuleDefinition { locs, .. } => locs,
+            Definition::EnumDefinition { locs, .. } => locs,
+            Definition::IncludeDefinition { locs, .. } => locs,
+            Definition::ExtendDefinition { locs, .. } => locs,
+            Definition::InstanceMethodDefinition { locs, .. } => locs,
+            Definition::ClassMethodDefinition { locs, .. } => locs,
+            Definition::ConstDefinition { locs, .. } => locs,
+        }
+    }
 }
 
 impl AstExpression {
+    /// Set the location of this expression
+    pub fn with_locs(mut self, locs: LocationSpan) -> AstExpression {
+        self.locs = locs;
+        self
+    }
+
     pub fn may_have_paren_wo_args(&self) -> bool {
         match self.body {
             AstExpressionBody::MethodCall {
@@ -146,6 +351,24 @@ impl AstExpression {
         }
     }
 
+    /// True if a block (`do ... end` or `{ ... }`) can follow this
+    pub fn can_take_block(&self) -> bool {
+        match self.body {
+            AstExpressionBody::MethodCall { .. }
+            | AstExpressionBody::SafeMethodCall { .. }
+            | AstExpressionBody::BareName(_) => true,
+            _ => false,
+        }
+    }
+
+    /// True if this is a lambda or a block
+    pub fn is_lambda(&self) -> bool {
+        match &self.body {
+            AstExpressionBody::LambdaExpr { .. } => true,
+            _ => false,
+        }
+    }
+
     /// True if this can be the left hand side of an assignment
     pub fn is_lhs(&self) -> bool {
         if self.may_have_paren_wo_args() {
@@ -154,7 +377,9 @@ impl AstExpression {
         match self.body {
             AstExpressionBody::IVarRef(_) => true,
             AstExpressionBody::ConstRef(_) => true,
-            // TODO: a[b]
+            AstExpressionBody::MethodCall {
+                ref method_name, ..
+            } => method_name.0 == "[]",
             _ => false,
         }
     }
@@ -192,15 +417,82 @@ pub fn if_expr(
     })
 }
 
+pub fn if_let_expr(
+    name: &str,
+    expr: AstExpression,
+    then_exprs: Vec<AstExpression>,
+    else_exprs: Option<Vec<AstExpression>>,
+) -> AstExpression {
+    non_primary_expression(AstExpressionBody::IfLet {
+        name: name.to_string(),
+        expr: Box::new(expr),
+        then_exprs,
+        else_exprs,
+    })
+}
+
 pub fn while_expr(cond_expr: AstExpression, body_exprs: Vec<AstExpression>) -> AstExpression {
     non_primary_expression(AstExpressionBody::While {
         cond_expr: Box::new(cond_expr),
         body_exprs,
+        label: None,
+    })
+}
+
+pub fn loop_expr(body_exprs: Vec<AstExpression>) -> AstExpression {
+    non_primary_expression(AstExpressionBody::Loop {
+        body_exprs,
+        label: None,
+    })
+}
+
+/// Set the label to a loop
+pub fn labeled_loop(mut expr: AstExpression, name: String) -> AstExpression {
+    match &mut expr.body {
+        AstExpressionBody::While { label, .. } | AstExpressionBody::Loop { label, .. } => {
+            *label = Some(name)
+        }
+        _ => panic!("[BUG] labeled_loop: not a loop"),
+    }
+    expr
+}
+
+pub fn break_expr(label: Option<String>, arg: Option<AstExpression>) -> AstExpression {
+    non_primary_expression(AstExpressionBody::Break {
+        label,
+        arg: arg.map(Box::new),
     })
 }
 
-pub fn break_expr() -> AstExpression {
-    non_primary_expression(AstExpressionBody::Break {})
+pub fn next_expr(label: Option<String>) -> AstExpression {
+    non_primary_expression(AstExpressionBody::Next { label })
+}
+
+pub fn match_expr(cond_expr: AstExpression, clauses: Vec<AstMatchClause>) -> AstExpression {
+    non_primary_expression(AstExpressionBody::Match {
+        cond_expr: Box::new(cond_expr),
+        clauses,
+    })
+}
+
+pub fn begin_expr(
+    body_exprs: Vec<AstExpression>,
+    rescue_var: Option<String>,
+    rescue_exprs: Option<Vec<AstExpression>>,
+    ensure_exprs: Option<Vec<AstExpression>>,
+) -> AstExpression {
+    non_primary_expression(AstExpressionBody::Begin {
+        body_exprs,
+        rescue_var,
+        rescue_exprs,
+        ensure_exprs,
+    })
+}
+
+pub fn return_expr(arg: Option<AstExpression>) -> AstExpression {
+    non_primary_expression(AstExpressionBody::Return {
+        arg: arg.map(Box::new),
+    })
 }
 
 /// Create an expression for an assigment
@@ -210,6 +502,7 @@ pub fn assignment(lhs: AstExpression, rhs: AstExpression) -> AstExpression {
             name: s,
             rhs: Box::new(rhs),
             is_var: false,
+            typ: None,
         },
         AstExpressionBody::IVarRef(name) => AstExpressionBody::IVarAssign {
             name,
@@ -220,26 +513,60 @@ pub fn assignment(lhs: AstExpression, rhs: AstExpression) -> AstExpression {
             names,
             rhs: Box::new(rhs),
         },
+        // `a.b = c` is `a.b=(c)` and `a[b] = c` is `a.[]=(b, c)`
         AstExpressionBody::MethodCall {
             receiver_expr,
             method_name,
+            mut arg_exprs,
             ..
-        } => AstExpressionBody::MethodCall {
-            receiver_expr,
-            method_name: method_name.append("="),
-            arg_exprs: vec![rhs],
-            may_have_paren_wo_args: false,
-        },
+        } => {
+            arg_exprs.push(rhs);
+            AstExpressionBody::MethodCall {
+                receiver_expr,
+                method_name: method_name.append("="),
+                type_args: vec![],
+                arg_exprs,
+                may_have_paren_wo_args: false,
+            }
+        }
         _ => panic!("[BUG] unexpectd lhs: {:?}", lhs.body),
     };
     non_primary_expression(body)
 }
 
+/// Create an expression for a compound assignment (`lhs op= rhs`)
+pub fn op_assignment(lhs: AstExpression, op: &str, rhs: AstExpression) -> AstExpression {
+    match lhs.body {
+        AstExpressionBody::MethodCall { .. } => {
+            non_primary_expression(AstExpressionBody::OpAssign {
+                lhs: Box::new(lhs),
+                op: op.to_string(),
+                rhs: Box::new(rhs),
+            })
+        }
+        // `a ||= b` is `a || (a = b)` (`b` is not evaluated if `a` is true)
+        _ if op == "&&" || op == "||" => binary_expr(lhs.clone(), op, assignment(lhs, rhs)),
+        // `a op= b` is `a = a op b`
+        _ => assignment(lhs.clone(), binary_expr(lhs, op, rhs)),
+    }
+}
+
 pub fn lvar_decl(name: String, rhs: AstExpression) -> AstExpression {
+    typed_lvar_decl(name, None, rhs, true)
+}
+
+/// `x: Int = 1` or `var x: Int = 1`
+pub fn typed_lvar_decl(
+    name: String,
+    typ: Option<Typ>,
+    rhs: AstExpression,
+    is_var: bool,
+) -> AstExpression {
     non_primary_expression(AstExpressionBody::LVarAssign {
         name,
         rhs: Box::new(rhs),
-        is_var: true,
+        is_var,
+        typ,
     })
 }
 
@@ -257,18 +584,53 @@ pub fn method_call(
     arg_exprs: Vec<AstExpression>,
     primary: bool,
     may_have_paren_wo_args: bool,
+) -> AstExpression {
+    generic_method_call(
+        receiver_expr,
+        method_name,
+        vec![],
+        arg_exprs,
+        primary,
+        may_have_paren_wo_args,
+    )
+}
+
+/// Method call with explicit type arguments (eg. `foo<Int>(x)`)
+pub fn generic_method_call(
+    receiver_expr: Option<AstExpression>,
+    method_name: &str,
+    type_args: Vec<Typ>,
+    arg_exprs: Vec<AstExpression>,
+    primary: bool,
+    may_have_paren_wo_args: bool,
 ) -> AstExpression {
     AstExpression {
         primary,
+        locs: LocationSpan::default(),
         body: AstExpressionBody::MethodCall {
             receiver_expr: receiver_expr.map(Box::new),
             method_name: method_firstname(method_name),
+            type_args,
             arg_exprs,
             may_have_paren_wo_args,
         },
     }
 }
 
+pub fn safe_method_call(
+    receiver_expr: AstExpression,
+    method_name: &str,
+    type_args: Vec<Typ>,
+    arg_exprs: Vec<AstExpression>,
+) -> AstExpression {
+    primary_expression(AstExpressionBody::SafeMethodCall {
+        receiver_expr: Box::new(receiver_expr),
+        method_name: method_firstname(method_name),
+        type_args,
+        arg_exprs,
+    })
+}
+
 pub fn bare_name(name: &str) -> AstExpression {
     primary_expression(AstExpressionBody::BareName(name.to_string()))
 }
@@ -285,22 +647,45 @@ pub fn unary_expr(expr: AstExpression, op: &str) -> AstExpression {
     primary_expression(AstExpressionBody::MethodCall {
         receiver_expr: Some(Box::new(expr)),
         method_name: method_firstname(op),
+        type_args: vec![],
         arg_exprs: vec![],
         may_have_paren_wo_args: false,
     })
 }
 
+/// Same as `bin_op_expr` but `&&` and `||` are the logical operators
+pub fn binary_expr(left: AstExpression, op: &str, right: AstExpression) -> AstExpression {
+    match op {
+        "&&" => logical_and(left, right),
+        "||" => logical_or(left, right),
+        _ => bin_op_expr(left, op, right),
+    }
+}
+
 pub fn bin_op_expr(left: AstExpression, op: &str, right: AstExpression) -> AstExpression {
     non_primary_expression(AstExpressionBody::MethodCall {
         receiver_expr: Some(Box::new(left)),
         method_name: method_firstname(op),
+        type_args: vec![],
         arg_exprs: vec![right],
         may_have_paren_wo_args: false,
     })
 }
 
-pub fn lambda_expr(params: Vec<Param>, exprs: Vec<AstExpression>) -> AstExpression {
-    primary_expression(AstExpressionBody::LambdaExpr { params, exprs })
+pub fn lambda_expr(
+    params: Vec<BlockParam>,
+    exprs: Vec<AstExpression>,
+    is_fn: bool,
+) -> AstExpression {
+    primary_expression(AstExpressionBody::LambdaExpr {
+        params,
+        exprs,
+        is_fn,
+    })
+}
+
+pub fn super_call(arg_exprs: Option<Vec<AstExpression>>) -> AstExpression {
+    primary_expression(AstExpressionBody::SuperCall { arg_exprs })
 }
 
 pub fn pseudo_variable(token: Token) -> AstExpression {
@@ -315,7 +700,7 @@ pub fn float_literal(value: f64) -> AstExpression {
     primary_expression(AstExpressionBody::FloatLiteral { value })
 }
 
-pub fn decimal_literal(value: i32) -> AstExpression {
+pub fn decimal_literal(value: u64) -> AstExpression {
     primary_expression(AstExpressionBody::DecimalLiteral { value })
 }
 
@@ -323,10 +708,15 @@ pub fn string_literal(content: String) -> AstExpression {
     primary_expression(AstExpressionBody::StringLiteral { content })
 }
 
+pub fn string_with_interpolation(parts: Vec<AstExpression>) -> AstExpression {
+    primary_expression(AstExpressionBody::StringWithInterpolation { parts })
+}
+
 pub fn primary_expression(body: AstExpressionBody) -> AstExpression {
     AstExpression {
         primary: true,
         body,
+        locs: LocationSpan::default(),
     }
 }
 
@@ -334,6 +724,7 @@ pub fn non_primary_expression(body: AstExpressionBody) -> AstExpression {
     AstExpression {
         primary: false,
         body,
+        locs: LocationSpan::default(),
     }
 }
 
@@ -344,6 +735,7 @@ pub fn set_method_call_args(expr: AstExpression, args: Vec<AstExpression>) -> As
         AstExpressionBody::MethodCall {
             receiver_expr,
             method_name,
+            type_args,
             arg_exprs,
             ..
         } => {
@@ -359,9 +751,11 @@ pub fn set_method_call_args(expr: AstExpression, args: Vec<AstExpression>) -> As
                 body: AstExpressionBody::MethodCall {
                     receiver_expr,
                     method_name,
+                    type_args,
                     arg_exprs: args,
                     may_have_paren_wo_args: false,
                 },
+                locs: expr.locs,
             }
         }
         AstExpressionBody::BareName(s) => AstExpression {
@@ -369,10 +763,61 @@ pub fn set_method_call_args(expr: AstExpression, args: Vec<AstExpression>) -> As
             body: AstExpressionBody::MethodCall {
                 receiver_expr: None,
                 method_name: method_firstname(&s),
+                type_args: vec![],
                 arg_exprs: args,
                 may_have_paren_wo_args: false,
             },
+            locs: expr.locs,
         },
         b => panic!("[BUG] `extend' takes a MethodCall but got {:?}", b),
     }
 }
+
+/// Append a block to the arguments of a method call
+pub fn add_block(expr: AstExpression, block: AstExpression) -> AstExpression {
+    let body = match expr.body {
+        AstExpressionBody::MethodCall {
+            receiver_expr,
+            method_name,
+            type_args,
+            mut arg_exprs,
+            ..
+        } => {
+            arg_exprs.push(block);
+            AstExpressionBody::MethodCall {
+                receiver_expr,
+                method_name,
+                type_args,
+                arg_exprs,
+                may_have_paren_wo_args: false,
+            }
+        }
+        AstExpressionBody::SafeMethodCall {
+            receiver_expr,
+            method_name,
+            type_args,
+            mut arg_exprs,
+        } => {
+            arg_exprs.push(block);
+            AstExpressionBody::SafeMethodCall {
+                receiver_expr,
+                method_name,
+                type_args,
+                arg_exprs,
+            }
+        }
+        AstExpressionBody::BareName(s) => AstExpressionBody::MethodCall {
+            receiver_expr: None,
+            method_name: method_firstname(&s),
+            type_args: vec![],
+            arg_exprs: vec![block],
+            may_have_paren_wo_args: false,
+        },
+        b => panic!("[BUG] `add_block' takes a MethodCall but got {:?}", b),
+    };
+    AstExpression {
+        primary: expr.primary,
+        body,
+        locs: expr.locs,
+    }
+}
@@ -33,6 +33,18 @@ impl<'hir, 'run, 'ictx> CodeGen<'hir, 'run, 'ictx> {
         self.build_ivar_load(sk_int, 0, "int").into_int_value()
     }
 
+    /// Convert LLVM int into Shiika `Int8`, `UInt32`, etc.
+    /// (Use `unbox_int` for the other way)
+    pub fn box_sized_int(
+        &self,
+        class_name: &str,
+        int: &inkwell::values::IntValue,
+    ) -> inkwell::values::BasicValueEnum {
+        let sk_int = self.allocate_sk_obj(&class_fullname(class_name), "sized_int");
+        self.build_ivar_store(&sk_int, 0, int.as_basic_value_enum(), "int");
+        sk_int
+    }
+
     /// Convert LLVM float into Shiika Float
     pub fn box_float(
         &self,
@@ -43,6 +55,17 @@ impl<'hir, 'run, 'ictx> CodeGen<'hir, 'run, 'ictx> {
         sk_float
     }
 
+    /// Convert LLVM float into Shiika Float32
+    /// (Use `unbox_float` for the other way)
+    pub fn box_float32(
+        &self,
+        float: &inkwell::values::FloatValue,
+    ) -> inkwell::values::BasicValueEnum {
+        let sk_float = self.allocate_sk_obj(&class_fullname("Float32"), "float32");
+        self.build_ivar_store(&sk_float, 0, float.as_basic_value_enum(), "float");
+        sk_float
+    }
+
     /// Convert Shiika Float into LLVM float
     pub fn unbox_float<'a>(
         &'a self,
@@ -2,7 +2,6 @@ use crate::hir::*;
 use crate::ty::*;
 use std::collections::HashMap;
 use std::collections::VecDeque;
-use std::rc::Rc;
 
 #[derive(Debug)]
 pub struct CodeGenContext<'hir: 'run, 'run> {
@@ -16,11 +15,37 @@ pub struct CodeGenContext<'hir: 'run, 'run> {
     pub function_params: Option<&'hir [MethodParam]>,
     /// Ptr of local variables
     pub lvars: HashMap<String, inkwell::values::PointerValue<'run>>,
-    pub current_loop_end: Option<Rc<inkwell::basic_block::BasicBlock<'run>>>,
+    /// Loops which contain the current expression (innermost last)
+    pub loops: Vec<CodeGenLoop<'hir, 'run>>,
+    /// `begin` expressions which contain the current expression (innermost last)
+    pub begins: Vec<CodeGenBegin<'hir, 'run>>,
     /// Lambdas to be compiled
     pub lambdas: VecDeque<CodeGenLambda<'hir>>,
 }
 
+/// A loop (`while` or `loop`) being compiled
+#[derive(Debug)]
+pub struct CodeGenLoop<'hir, 'run> {
+    pub label: Option<String>,
+    /// Where `next` jumps to
+    pub begin_block: inkwell::basic_block::BasicBlock<'run>,
+    /// Where `break` jumps to
+    pub end_block: inkwell::basic_block::BasicBlock<'run>,
+    /// Type of the value of the loop and where to store it. None for `while`
+    /// and the loops without a value (Void or Never)
+    pub result: Option<(&'hir TermTy, inkwell::values::PointerValue<'run>)>,
+    /// Number of the `begin`s which contain this loop
+    pub n_begins: usize,
+}
+
+/// The body of a `begin` expression being compiled
+#[derive(Debug, Clone, Copy)]
+pub struct CodeGenBegin<'hir, 'run> {
+    /// The exception handler to restore when leaving the body
+    pub prev_handler: inkwell::values::BasicValueEnum<'run>,
+    pub ensure_exprs: Option<&'hir HirExpressions>,
+}
+
 #[derive(Debug)]
 pub enum FunctionOrigin {
     Method,
@@ -46,8 +71,21 @@ impl<'hir, 'run> CodeGenContext<'hir, 'run> {
             function_origin,
             function_params,
             lvars: HashMap::new(),
-            current_loop_end: None,
+            loops: vec![],
+            begins: vec![],
             lambdas: VecDeque::new(),
         }
     }
+
+    /// Return the loop of the label (or the innermost one), if any
+    pub fn find_loop(&self, label: &Option<String>) -> Option<&CodeGenLoop<'hir, 'run>> {
+        match label {
+            Some(name) => self
+                .loops
+                .iter()
+                .rev()
+                .find(|l| l.label.as_ref() == Some(name)),
+            None => self.loops.last(),
+        }
+    }
 }
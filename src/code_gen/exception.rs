@@ -0,0 +1,278 @@
+//! Exceptions
+//!
+//! Exceptions are implemented with setjmp/longjmp. `begin` saves the
+//! execution context into a jmp_buf and sets it to the global
+//! `shiika_exception_handler`; `raise` stores the exception to
+//! `shiika_current_exception` and longjmps to the handler.
+use crate::code_gen::code_gen_context::*;
+use crate::code_gen::*;
+use crate::error::Error;
+use crate::ty;
+use inkwell::attributes::{Attribute, AttributeLoc};
+
+/// Name of the llvm global which holds the jmp_buf of the innermost `begin`
+/// (null if there is none)
+const HANDLER_GLOBAL: &str = "shiika_exception_handler";
+/// Name of the llvm global which holds the exception being raised
+const EXCEPTION_GLOBAL: &str = "shiika_current_exception";
+/// Size of jmp_buf in i64 (larger than any platform needs)
+const JMP_BUF_SIZE: u32 = 64;
+
+impl<'hir: 'ictx, 'run, 'ictx: 'run> CodeGen<'hir, 'run, 'ictx> {
+    /// Declare setjmp and longjmp
+    pub(super) fn gen_exception_declares(&self) {
+        let fn_type = self.i32_type.fn_type(&[self.i8ptr_type.into()], false);
+        let setjmp = self.module.add_function("_setjmp", fn_type, None);
+        let returns_twice = Attribute::get_named_enum_kind_id("returns_twice");
+        setjmp.add_attribute(
+            AttributeLoc::Function,
+            self.context.create_enum_attribute(returns_twice, 0),
+        );
+        let fn_type = self
+            .void_type
+            .fn_type(&[self.i8ptr_type.into(), self.i32_type.into()], false);
+        self.module.add_function("longjmp", fn_type, None);
+    }
+
+    /// Create the globals for exceptions (only declare them if they are
+    /// defined in the library)
+    pub(super) fn gen_exception_globals(&self, imported: bool) {
+        for name in &[HANDLER_GLOBAL, EXCEPTION_GLOBAL] {
+            let global = self.module.add_global(self.i8ptr_type, None, name);
+            if !imported {
+                global.set_initializer(&self.i8ptr_type.const_null());
+            }
+        }
+    }
+
+    /// Generate IR to raise `exception` (used by `Object#raise`)
+    pub fn gen_raise(
+        &self,
+        function: &inkwell::values::FunctionValue,
+        exception: inkwell::values::BasicValueEnum,
+    ) {
+        let handler = self
+            .builder
+            .build_load(self.global_ptr(HANDLER_GLOBAL), "handler");
+        let uncaught_block = self.context.append_basic_block(*function, "Uncaught");
+        let caught_block = self.context.append_basic_block(*function, "Caught");
+        let is_uncaught = self
+            .builder
+            .build_is_null(handler.into_pointer_value(), "isUncaught");
+        self.builder
+            .build_conditional_branch(is_uncaught, uncaught_block, caught_block);
+
+        // Uncaught:
+        self.builder.position_at_end(uncaught_block);
+        let sk_exception =
+            self.builder
+                .build_bitcast(exception, self.llvm_type(&ty::raw("Exception")), "");
+        let message = self.build_ivar_load(sk_exception, 0, "@message");
+        // `@message` is null if the initializer of a subclass did not call `super`
+        let no_message_block = self.context.append_basic_block(*function, "NoMessage");
+        let has_message_block = self.context.append_basic_block(*function, "HasMessage");
+        let no_message = self
+            .builder
+            .build_is_null(message.into_pointer_value(), "noMessage");
+        self.builder
+            .build_conditional_branch(no_message, no_message_block, has_message_block);
+        // NoMessage:
+        self.builder.position_at_end(no_message_block);
+        self.gen_uncaught_exit("Uncaught exception\n", &[]);
+        // HasMessage:
+        self.builder.position_at_end(has_message_block);
+        let cstr = self.build_ivar_load(message, 0, "@ptr");
+        self.gen_uncaught_exit("Uncaught exception: %s\n", &[cstr]);
+
+        // Caught:
+        self.builder.position_at_end(caught_block);
+        let e = self.builder.build_bitcast(exception, self.i8ptr_type, "");
+        self.builder
+            .build_store(self.global_ptr(EXCEPTION_GLOBAL), e);
+        let longjmp = self.module.get_function("longjmp").unwrap();
+        self.builder.build_call(
+            longjmp,
+            &[handler, self.i32_type.const_int(1, false).into()],
+            "",
+        );
+        self.builder.build_unreachable();
+    }
+
+    /// Print the message of an uncaught exception and exit
+    fn gen_uncaught_exit(&self, tmpl: &str, args: &[inkwell::values::BasicValueEnum]) {
+        let tmpl = self.builder.build_global_string_ptr(tmpl, "uncaught_tmpl");
+        let mut printf_args = vec![tmpl.as_pointer_value().into()];
+        printf_args.extend_from_slice(args);
+        let printf = self.module.get_function("printf").unwrap();
+        self.builder.build_call(printf, &printf_args, "");
+        let exit = self.module.get_function("exit").unwrap();
+        self.builder
+            .build_call(exit, &[self.i32_type.const_int(1, false).into()], "");
+        self.builder.build_unreachable();
+    }
+
+    /// Generate IR for `begin ... rescue => e ... ensure ... end`
+    pub(super) fn gen_begin_expr(
+        &self,
+        ctx: &mut CodeGenContext<'hir, 'run>,
+        ty: &'hir TermTy,
+        body_exprs: &'hir HirExpressions,
+        rescue_var: &Option<String>,
+        rescue_exprs: &'hir Option<HirExpressions>,
+        ensure_exprs: &'hir Option<HirExpressions>,
+    ) -> Result<inkwell::values::BasicValueEnum, Error> {
+        // The value of the `begin` is stored here
+        let result_ptr = self.build_result_alloca(ctx, ty, "beginResult");
+        match ensure_exprs {
+            None => {
+                self.gen_rescue_clause(ctx, result_ptr, body_exprs, rescue_var, rescue_exprs)?
+            }
+            // Compiled like `begin (begin ... rescue ... end) ensure ... end`
+            Some(ensure_exprs) => {
+                let end_block = self.context.append_basic_block(ctx.function, "BeginEnd");
+                let (prev_handler, catch_block) = self.gen_setjmp(ctx);
+                ctx.begins.push(CodeGenBegin {
+                    prev_handler,
+                    ensure_exprs: Some(ensure_exprs),
+                });
+                self.gen_rescue_clause(ctx, result_ptr, body_exprs, rescue_var, rescue_exprs)?;
+                ctx.begins.pop();
+                self.set_exception_handler(prev_handler);
+                self.gen_exprs(ctx, ensure_exprs)?;
+                self.builder.build_unconditional_branch(end_block);
+
+                // Run `ensure` and re-raise the exception
+                self.builder.position_at_end(catch_block);
+                self.set_exception_handler(prev_handler);
+                self.gen_exprs(ctx, ensure_exprs)?;
+                let exception = self
+                    .builder
+                    .build_load(self.global_ptr(EXCEPTION_GLOBAL), "exception");
+                self.gen_raise(&ctx.function, exception);
+
+                // BeginEnd:
+                self.builder.position_at_end(end_block);
+            }
+        }
+        match result_ptr {
+            Some((_, ptr)) => Ok(self.builder.build_load(ptr, "beginResult")),
+            None => Ok(self.gen_const_ref(&const_fullname("::Void"))),
+        }
+    }
+
+    /// Generate IR for `begin ... rescue => e ... end` (without `ensure`)
+    fn gen_rescue_clause(
+        &self,
+        ctx: &mut CodeGenContext<'hir, 'run>,
+        result_ptr: Option<(&'hir TermTy, inkwell::values::PointerValue<'run>)>,
+        body_exprs: &'hir HirExpressions,
+        rescue_var: &Option<String>,
+        rescue_exprs: &'hir Option<HirExpressions>,
+    ) -> Result<(), Error> {
+        let rescue_exprs = match rescue_exprs {
+            Some(exprs) => exprs,
+            None => {
+                let value = self.gen_exprs(ctx, body_exprs)?;
+                self.store_result(result_ptr, &body_exprs.ty, value);
+                return Ok(());
+            }
+        };
+        let end_block = self.context.append_basic_block(ctx.function, "RescueEnd");
+        let (prev_handler, catch_block) = self.gen_setjmp(ctx);
+        ctx.begins.push(CodeGenBegin {
+            prev_handler,
+            ensure_exprs: None,
+        });
+        let value = self.gen_exprs(ctx, body_exprs)?;
+        ctx.begins.pop();
+        self.set_exception_handler(prev_handler);
+        self.store_result(result_ptr, &body_exprs.ty, value);
+        self.builder.build_unconditional_branch(end_block);
+
+        // Rescue:
+        self.builder.position_at_end(catch_block);
+        self.set_exception_handler(prev_handler);
+        if let Some(name) = rescue_var {
+            let exception = self
+                .builder
+                .build_load(self.global_ptr(EXCEPTION_GLOBAL), "exception");
+            let exception_ty = self.llvm_type(&ty::raw("Exception"));
+            let value = self.builder.build_bitcast(exception, exception_ty, name);
+            self.store_lvar(ctx, name, exception_ty, value);
+        }
+        let value = self.gen_exprs(ctx, rescue_exprs)?;
+        self.store_result(result_ptr, &rescue_exprs.ty, value);
+        self.builder.build_unconditional_branch(end_block);
+
+        // RescueEnd:
+        self.builder.position_at_end(end_block);
+        Ok(())
+    }
+
+    /// Set a new exception handler and call setjmp. Returns the previous
+    /// handler and the block to go when an exception is raised
+    fn gen_setjmp(
+        &self,
+        ctx: &mut CodeGenContext<'hir, 'run>,
+    ) -> (
+        inkwell::values::BasicValueEnum<'run>,
+        inkwell::basic_block::BasicBlock<'run>,
+    ) {
+        let prev_handler = self
+            .builder
+            .build_load(self.global_ptr(HANDLER_GLOBAL), "prevHandler");
+        let jmp_buf_type = self.i64_type.array_type(JMP_BUF_SIZE);
+        let jmp_buf = self.build_entry_alloca(ctx, jmp_buf_type, "jmpBuf");
+        let jmp_buf = self.builder.build_bitcast(jmp_buf, self.i8ptr_type, "");
+        self.set_exception_handler(jmp_buf);
+        let setjmp = self.module.get_function("_setjmp").unwrap();
+        let result = self
+            .builder
+            .build_call(setjmp, &[jmp_buf], "setjmp")
+            .try_as_basic_value()
+            .left()
+            .unwrap();
+        let is_raised = self.builder.build_int_compare(
+            inkwell::IntPredicate::NE,
+            result.into_int_value(),
+            self.i32_type.const_int(0, false),
+            "isRaised",
+        );
+        let try_block = self.context.append_basic_block(ctx.function, "Try");
+        let catch_block = self.context.append_basic_block(ctx.function, "Catch");
+        self.builder
+            .build_conditional_branch(is_raised, catch_block, try_block);
+        self.builder.position_at_end(try_block);
+        (prev_handler, catch_block)
+    }
+
+    /// Restore the exception handlers and run the `ensure` clauses of the
+    /// `begin`s being left (by `return`, `break` or `next`)
+    pub(super) fn gen_leave_begins(
+        &self,
+        ctx: &mut CodeGenContext<'hir, 'run>,
+        n_begins: usize,
+    ) -> Result<(), Error> {
+        let begins = ctx.begins.split_off(n_begins);
+        for (i, begin) in begins.iter().enumerate().rev() {
+            // `ensure` runs outside of its own `begin`
+            ctx.begins.extend_from_slice(&begins[..i]);
+            self.set_exception_handler(begin.prev_handler);
+            if let Some(exprs) = begin.ensure_exprs {
+                self.gen_exprs(ctx, exprs)?;
+            }
+            ctx.begins.truncate(n_begins);
+        }
+        ctx.begins.extend(begins);
+        Ok(())
+    }
+
+    fn set_exception_handler(&self, handler: inkwell::values::BasicValueEnum) {
+        self.builder
+            .build_store(self.global_ptr(HANDLER_GLOBAL), handler);
+    }
+
+    fn global_ptr(&self, name: &str) -> inkwell::values::PointerValue<'ictx> {
+        self.module.get_global(name).unwrap().as_pointer_value()
+    }
+}
@@ -8,7 +8,7 @@ use crate::names::*;
 use crate::ty;
 use crate::ty::*;
 use inkwell::values::*;
-use std::rc::Rc;
+use inkwell::AddressSpace;
 
 impl<'hir, 'run, 'ictx> CodeGen<'hir, 'run, 'ictx> {
     pub fn gen_exprs(
@@ -42,8 +42,31 @@ impl<'hir, 'run, 'ictx> CodeGen<'hir, 'run, 'ictx> {
             HirWhileExpression {
                 cond_expr,
                 body_exprs,
-            } => self.gen_while_expr(ctx, &cond_expr, &body_exprs),
-            HirBreakExpression => self.gen_break_expr(ctx),
+                label,
+            } => self.gen_while_expr(ctx, &cond_expr, &body_exprs, label),
+            HirLoopExpression { body_exprs, label } => {
+                self.gen_loop_expr(ctx, &expr.ty, &body_exprs, label)
+            }
+            HirBreakExpression { label, arg } => self.gen_break_expr(ctx, label, arg),
+            HirNextExpression { label } => self.gen_next_expr(ctx, label),
+            HirBeginExpression {
+                body_exprs,
+                rescue_var,
+                rescue_exprs,
+                ensure_exprs,
+            } => self.gen_begin_expr(
+                ctx,
+                &expr.ty,
+                &body_exprs,
+                rescue_var,
+                &rescue_exprs,
+                &ensure_exprs,
+            ),
+            HirMatchExpression {
+                cond_assign_expr,
+                clauses,
+            } => self.gen_match_expr(ctx, &expr.ty, &cond_assign_expr, clauses),
+            HirReturnExpression { arg } => self.gen_return_expr(ctx, arg),
             HirLVarAssign { name, rhs } => self.gen_lvar_assign(ctx, name, rhs),
             HirIVarAssign { name, idx, rhs, .. } => self.gen_ivar_assign(ctx, name, idx, rhs),
             HirConstAssign { fullname, rhs } => self.gen_const_assign(ctx, fullname, rhs),
@@ -52,6 +75,11 @@ impl<'hir, 'run, 'ictx> CodeGen<'hir, 'run, 'ictx> {
                 method_fullname,
                 arg_exprs,
             } => self.gen_method_call(ctx, method_fullname, receiver_expr, arg_exprs),
+            HirSuperCall {
+                receiver_expr,
+                method_fullname,
+                arg_exprs,
+            } => self.gen_super_call(ctx, method_fullname, receiver_expr, arg_exprs),
             HirArgRef { idx } => self.gen_arg_ref(ctx, idx),
             HirLVarRef { name } => self.gen_lvar_ref(ctx, name),
             HirIVarRef { name, idx } => self.gen_ivar_ref(ctx, name, idx),
@@ -64,6 +92,7 @@ impl<'hir, 'run, 'ictx> CodeGen<'hir, 'run, 'ictx> {
             } => self.gen_lambda_expr(ctx, name, params, exprs, captures_ary),
             HirSelfExpression => self.gen_self_expression(ctx),
             HirArrayLiteral { exprs } => self.gen_array_literal(ctx, exprs),
+            HirParenthesizedExpr { exprs } => self.gen_exprs(ctx, exprs),
             HirFloatLiteral { value } => Ok(self.gen_float_literal(*value)),
             HirDecimalLiteral { value } => Ok(self.gen_decimal_literal(*value)),
             HirStringLiteral { idx } => Ok(self.gen_string_literal(idx)),
@@ -71,10 +100,12 @@ impl<'hir, 'run, 'ictx> CodeGen<'hir, 'run, 'ictx> {
 
             HirLambdaCaptureRef { idx } => self.gen_lambda_capture_ref(ctx, idx, &expr.ty),
             HirBitCast { expr: target } => self.gen_bitcast(ctx, target, &expr.ty),
+            HirIsInstanceOf { expr, classname } => self.gen_is_instance_of(ctx, expr, classname),
             HirClassLiteral {
                 fullname,
                 str_literal_idx,
             } => Ok(self.gen_class_literal(fullname, str_literal_idx)),
+            HirErrorPlaceholder => panic!("[BUG] HirErrorPlaceholder must not be compiled"),
         }
     }
 
@@ -178,13 +209,12 @@ impl<'hir, 'run, 'ictx> CodeGen<'hir, 'run, 'ictx> {
                 self.gen_conditional_branch(cond_value, then_block, else_block);
                 // IfThen:
                 self.builder.position_at_end(then_block);
-                let then_value: &dyn inkwell::values::BasicValue =
-                    &self.gen_exprs(ctx, then_exprs)?;
+                let then_value = self.gen_branch_value(ctx, ty, then_exprs)?;
                 self.builder.build_unconditional_branch(merge_block);
                 let then_block_end = self.builder.get_insert_block().unwrap();
                 // IfElse:
                 self.builder.position_at_end(else_block);
-                let else_value = self.gen_exprs(ctx, else_exprs)?;
+                let else_value = self.gen_branch_value(ctx, ty, else_exprs)?;
                 self.builder.build_unconditional_branch(merge_block);
                 let else_block_end = self.builder.get_insert_block().unwrap();
                 // IfEnd:
@@ -192,7 +222,7 @@ impl<'hir, 'run, 'ictx> CodeGen<'hir, 'run, 'ictx> {
 
                 let phi_node = self.builder.build_phi(self.llvm_type(ty), "ifResult");
                 phi_node
-                    .add_incoming(&[(then_value, then_block_end), (&else_value, else_block_end)]);
+                    .add_incoming(&[(&then_value, then_block_end), (&else_value, else_block_end)]);
                 Ok(phi_node.as_basic_value())
             }
             None => {
@@ -211,11 +241,29 @@ impl<'hir, 'run, 'ictx> CodeGen<'hir, 'run, 'ictx> {
         }
     }
 
+    /// Generate IR for a branch of `if` and return its value casted to the
+    /// type of the `if`. If the branch does not return (eg. ends with
+    /// `return`), the value is a dummy
+    fn gen_branch_value(
+        &self,
+        ctx: &mut CodeGenContext<'hir, 'run>,
+        ty: &TermTy,
+        exprs: &'hir HirExpressions,
+    ) -> Result<inkwell::values::BasicValueEnum, Error> {
+        let value = self.gen_exprs(ctx, exprs)?;
+        if exprs.ty != *ty && !ty.is_never_type() {
+            Ok(self.builder.build_bitcast(value, self.llvm_type(ty), ""))
+        } else {
+            Ok(value)
+        }
+    }
+
     fn gen_while_expr(
         &self,
         ctx: &mut CodeGenContext<'hir, 'run>,
         cond_expr: &'hir HirExpression,
         body_exprs: &'hir HirExpressions,
+        label: &Option<String>,
     ) -> Result<inkwell::values::BasicValueEnum, Error> {
         let begin_block = self.context.append_basic_block(ctx.function, "WhileBegin");
         self.builder.build_unconditional_branch(begin_block);
@@ -227,31 +275,200 @@ impl<'hir, 'run, 'ictx> CodeGen<'hir, 'run, 'ictx> {
         self.gen_conditional_branch(cond_value, body_block, end_block);
         // WhileBody:
         self.builder.position_at_end(body_block);
-        let rc1 = Rc::new(end_block);
-        let rc2 = Rc::clone(&rc1);
-        ctx.current_loop_end = Some(rc1);
+        ctx.loops.push(CodeGenLoop {
+            label: label.clone(),
+            begin_block,
+            end_block,
+            result: None,
+            n_begins: ctx.begins.len(),
+        });
         self.gen_exprs(ctx, body_exprs)?;
-        ctx.current_loop_end = None;
+        ctx.loops.pop();
         self.builder.build_unconditional_branch(begin_block);
 
         // WhileEnd:
-        self.builder.position_at_end(*rc2);
+        self.builder.position_at_end(end_block);
         Ok(self.i32_type.const_int(0, false).as_basic_value_enum()) // return Void
     }
 
+    fn gen_loop_expr(
+        &self,
+        ctx: &mut CodeGenContext<'hir, 'run>,
+        ty: &'hir TermTy,
+        body_exprs: &'hir HirExpressions,
+        label: &Option<String>,
+    ) -> Result<inkwell::values::BasicValueEnum, Error> {
+        // The value given to `break` is stored here
+        let result_ptr = self.build_result_alloca(ctx, ty, "loopResult");
+        let begin_block = self.context.append_basic_block(ctx.function, "LoopBegin");
+        let end_block = self.context.append_basic_block(ctx.function, "LoopEnd");
+        self.builder.build_unconditional_branch(begin_block);
+        // LoopBegin:
+        self.builder.position_at_end(begin_block);
+        ctx.loops.push(CodeGenLoop {
+            label: label.clone(),
+            begin_block,
+            end_block,
+            result: result_ptr,
+            n_begins: ctx.begins.len(),
+        });
+        self.gen_exprs(ctx, body_exprs)?;
+        ctx.loops.pop();
+        self.builder.build_unconditional_branch(begin_block);
+
+        // LoopEnd:
+        self.builder.position_at_end(end_block);
+        match result_ptr {
+            Some((_, ptr)) => Ok(self.builder.build_load(ptr, "loopResult")),
+            None => Ok(self.gen_const_ref(&const_fullname("::Void"))),
+        }
+    }
+
     fn gen_break_expr(
         &self,
         ctx: &mut CodeGenContext<'hir, 'run>,
+        label: &Option<String>,
+        arg: &'hir Option<Box<HirExpression>>,
+    ) -> Result<inkwell::values::BasicValueEnum, Error> {
+        let value = match arg {
+            Some(expr) => Some(self.gen_expr(ctx, expr)?),
+            None => None,
+        };
+        let (end_block, result, n_begins) = match ctx.find_loop(label) {
+            Some(l) => (l.end_block, l.result, l.n_begins),
+            None => return Err(error::program_error("break outside of a loop")),
+        };
+        if let (Some(v), Some((ty, ptr))) = (value, result) {
+            // The value may be a subtype of the type of the loop
+            let v = self.builder.build_bitcast(v, self.llvm_type(ty), "");
+            self.builder.build_store(ptr, v);
+        }
+        self.gen_leave_begins(ctx, n_begins)?;
+        self.builder.build_unconditional_branch(end_block);
+        self.start_unreachable_block(ctx, "AfterBreak");
+        Ok(self.gen_const_ref(&const_fullname("::Void")))
+    }
+
+    fn gen_next_expr(
+        &self,
+        ctx: &mut CodeGenContext<'hir, 'run>,
+        label: &Option<String>,
     ) -> Result<inkwell::values::BasicValueEnum, Error> {
-        match &ctx.current_loop_end {
-            Some(b) => {
-                self.builder.build_unconditional_branch(*Rc::clone(b));
-                Ok(self.i32_type.const_int(0, false).as_basic_value_enum()) // return Void
+        let (begin_block, n_begins) = match ctx.find_loop(label) {
+            Some(l) => (l.begin_block, l.n_begins),
+            None => return Err(error::program_error("next outside of a loop")),
+        };
+        self.gen_leave_begins(ctx, n_begins)?;
+        self.builder.build_unconditional_branch(begin_block);
+        self.start_unreachable_block(ctx, "AfterNext");
+        Ok(self.gen_const_ref(&const_fullname("::Void")))
+    }
+
+    /// Generate IR for `match`
+    fn gen_match_expr(
+        &self,
+        ctx: &mut CodeGenContext<'hir, 'run>,
+        ty: &'hir TermTy,
+        cond_assign_expr: &'hir HirExpression,
+        clauses: &'hir [MatchClause],
+    ) -> Result<inkwell::values::BasicValueEnum, Error> {
+        self.gen_expr(ctx, cond_assign_expr)?;
+        let result_ptr = self.build_result_alloca(ctx, ty, "matchResult");
+        let end_block = self.context.append_basic_block(ctx.function, "MatchEnd");
+        for clause in clauses {
+            let next_block = self.context.append_basic_block(ctx.function, "MatchNext");
+            for component in &clause.components {
+                match component {
+                    MatchComponent::Test(expr) => {
+                        let cond_value = self.gen_expr(ctx, expr)?;
+                        let ok_block = self.context.append_basic_block(ctx.function, "MatchOk");
+                        self.gen_conditional_branch(cond_value, ok_block, next_block);
+                        self.builder.position_at_end(ok_block);
+                    }
+                    MatchComponent::Bind(name, expr) => {
+                        let value = self.gen_expr(ctx, expr)?;
+                        self.store_lvar(ctx, name, self.llvm_type(&expr.ty), value);
+                    }
+                }
             }
-            None => Err(error::program_error("break outside of a loop")),
+            let value = self.gen_exprs(ctx, &clause.body_hir)?;
+            self.store_result(result_ptr, &clause.body_hir.ty, value);
+            self.builder.build_unconditional_branch(end_block);
+            self.builder.position_at_end(next_block);
+        }
+        // No clause matched (cannot happen because `match` is exhaustive)
+        self.builder.build_unreachable();
+
+        // MatchEnd:
+        self.builder.position_at_end(end_block);
+        match result_ptr {
+            Some((_, ptr)) => Ok(self.builder.build_load(ptr, "matchResult")),
+            None => Ok(self.gen_const_ref(&const_fullname("::Void"))),
         }
     }
 
+    /// Allocate the memory to store the value of an expression which has
+    /// branches (None if the expression has no value)
+    pub(super) fn build_result_alloca(
+        &self,
+        ctx: &CodeGenContext<'hir, 'run>,
+        ty: &'hir TermTy,
+        name: &str,
+    ) -> Option<(&'hir TermTy, inkwell::values::PointerValue<'run>)> {
+        if ty.is_void_type() || ty.is_never_type() {
+            None
+        } else {
+            Some((ty, self.build_entry_alloca(ctx, self.llvm_type(ty), name)))
+        }
+    }
+
+    /// Store the value of a branch to the memory allocated by
+    /// `build_result_alloca`
+    pub(super) fn store_result(
+        &self,
+        result_ptr: Option<(&'hir TermTy, inkwell::values::PointerValue<'run>)>,
+        value_ty: &TermTy,
+        value: inkwell::values::BasicValueEnum,
+    ) {
+        if value_ty.is_never_type() {
+            return;
+        }
+        if let Some((ty, ptr)) = result_ptr {
+            // The value may be a subtype of the type of the whole expression
+            let v = self.builder.build_bitcast(value, self.llvm_type(ty), "");
+            self.builder.build_store(ptr, v);
+        }
+    }
+
+    /// Create a new basic block and move to it. Used after a jump (eg. `break`)
+    /// because LLVM does not allow instructions after a terminator
+    fn start_unreachable_block(&self, ctx: &mut CodeGenContext<'hir, 'run>, name: &str) {
+        let block = self.context.append_basic_block(ctx.function, name);
+        self.builder.position_at_end(block);
+    }
+
+    /// Generate IR for `return`.
+    fn gen_return_expr(
+        &self,
+        ctx: &mut CodeGenContext<'hir, 'run>,
+        arg: &'hir HirExpression,
+    ) -> Result<inkwell::values::BasicValueEnum, Error> {
+        let value = self.gen_expr(ctx, arg)?;
+        self.gen_leave_begins(ctx, 0)?;
+        match ctx.function.get_type().get_return_type() {
+            Some(llvm_type) => {
+                // The value may be a subtype of the return type
+                let v = self.builder.build_bitcast(value, llvm_type, "");
+                self.builder.build_return(Some(&v));
+            }
+            None => {
+                self.builder.build_return(None);
+            }
+        }
+        self.start_unreachable_block(ctx, "AfterReturn");
+        Ok(self.gen_const_ref(&const_fullname("::Void")))
+    }
+
     fn gen_lvar_assign(
         &self,
         ctx: &mut CodeGenContext<'hir, 'run>,
@@ -259,18 +476,46 @@ impl<'hir, 'run, 'ictx> CodeGen<'hir, 'run, 'ictx> {
         rhs: &'hir HirExpression,
     ) -> Result<inkwell::values::BasicValueEnum, Error> {
         let value = self.gen_expr(ctx, rhs)?;
+        self.store_lvar(ctx, name, self.llvm_type(&rhs.ty), value);
+        Ok(value)
+    }
+
+    /// Store the value to the lvar (allocate it if not yet)
+    pub(super) fn store_lvar(
+        &self,
+        ctx: &mut CodeGenContext<'hir, 'run>,
+        name: &str,
+        llvm_type: inkwell::types::BasicTypeEnum<'ictx>,
+        value: inkwell::values::BasicValueEnum,
+    ) {
         match ctx.lvars.get(name) {
             Some(ptr) => {
                 // Reassigning; Just store to it
                 self.builder.build_store(*ptr, value);
             }
             None => {
-                let ptr = self.builder.build_alloca(self.llvm_type(&rhs.ty), name);
+                let ptr = self.build_entry_alloca(ctx, llvm_type, name);
                 self.builder.build_store(ptr, value);
                 ctx.lvars.insert(name.to_string(), ptr);
             }
         }
-        Ok(value)
+    }
+
+    /// Create an alloca at the beginning of the function, so that it
+    /// dominates all the uses and is not repeated in a loop
+    pub(super) fn build_entry_alloca<T: inkwell::types::BasicType<'ictx>>(
+        &self,
+        ctx: &CodeGenContext<'hir, 'run>,
+        ty: T,
+        name: &str,
+    ) -> inkwell::values::PointerValue<'run> {
+        let builder = self.context.create_builder();
+        let entry = ctx.function.get_first_basic_block().unwrap();
+        match entry.get_first_instruction() {
+            Some(inst) => builder.position_before(&inst),
+            None => builder.position_at_end(entry),
+        }
+        builder.build_alloca(ty, name)
     }
 
     fn gen_ivar_assign(
@@ -309,6 +554,25 @@ impl<'hir, 'run, 'ictx> CodeGen<'hir, 'run, 'ictx> {
         method_fullname: &MethodFullname,
         receiver_expr: &'hir HirExpression,
         arg_exprs: &'hir [HirExpression],
+    ) -> Result<inkwell::values::BasicValueEnum, Error> {
+        let receiver_value = self.gen_expr(ctx, receiver_expr)?;
+        let arg_values = arg_exprs
+            .iter()
+            .map(|arg_expr| self.gen_expr(ctx, arg_expr))
+            .collect::<Result<Vec<_>, _>>()?;
+        match self.vtable_index(&receiver_expr.ty, method_fullname) {
+            Some(idx) => self.gen_virtual_call(method_fullname, idx, receiver_value, arg_values),
+            None => self.gen_llvm_func_call(&method_fullname.full_name, receiver_value, arg_values),
+        }
+    }
+
+    /// Generate IR for `super` (call the llvm function of the superclass directly)
+    fn gen_super_call(
+        &self,
+        ctx: &mut CodeGenContext<'hir, 'run>,
+        method_fullname: &MethodFullname,
+        receiver_expr: &'hir HirExpression,
+        arg_exprs: &'hir [HirExpression],
     ) -> Result<inkwell::values::BasicValueEnum, Error> {
         let receiver_value = self.gen_expr(ctx, receiver_expr)?;
         let arg_values = arg_exprs
@@ -318,6 +582,65 @@ impl<'hir, 'run, 'ictx> CodeGen<'hir, 'run, 'ictx> {
         self.gen_llvm_func_call(&method_fullname.full_name, receiver_value, arg_values)
     }
 
+    /// Return the index of the method in the vtable of the receiver.
+    /// None if the method should be called directly
+    fn vtable_index(
+        &self,
+        receiver_ty: &TermTy,
+        method_fullname: &MethodFullname,
+    ) -> Option<usize> {
+        let classname = match &receiver_ty.body {
+            // Not a Shiika object (has no vtable)
+            TyBody::TyRaw if receiver_ty.fullname.0 == "Shiika::Internal::Ptr" => return None,
+            TyBody::TySpe { base_name, .. } => class_fullname(base_name),
+            TyBody::TySpeMeta { base_name, .. } | TyBody::TyGenMeta { base_name, .. } => {
+                metaclass_fullname(base_name)
+            }
+            TyBody::TyParamRef { .. } => class_fullname("Object"), // its upper bound
+            _ => receiver_ty.fullname.clone(),
+        };
+        self.vtables
+            .get(&classname)?
+            .get(&method_fullname.first_name)
+    }
+
+    /// Generate method call via the vtable of the receiver
+    fn gen_virtual_call<'a>(
+        &'a self,
+        method_fullname: &MethodFullname,
+        idx: usize,
+        receiver_value: inkwell::values::BasicValueEnum<'a>,
+        mut arg_values: Vec<inkwell::values::BasicValueEnum<'a>>,
+    ) -> Result<inkwell::values::BasicValueEnum, Error> {
+        let vtable = self.build_vtable_load(receiver_value);
+        let addr = unsafe {
+            self.builder.build_gep(
+                vtable,
+                &[self.i64_type.const_int(idx as u64, false)],
+                "addr_method",
+            )
+        };
+        let fnptr = self.builder.build_load(addr, "method");
+        // Overriding methods have the same llvm type except for `self`
+        let func_type = self.get_llvm_func(&method_fullname.full_name).get_type();
+        let func = self
+            .builder
+            .build_bitcast(fnptr, func_type.ptr_type(AddressSpace::Generic), "")
+            .into_pointer_value();
+
+        let mut llvm_args = vec![receiver_value];
+        llvm_args.append(&mut arg_values);
+        match self
+            .builder
+            .build_call(func, &llvm_args, "result")
+            .try_as_basic_value()
+            .left()
+        {
+            Some(result_value) => Ok(result_value),
+            None => Ok(self.gen_const_ref(&const_fullname("::Void"))),
+        }
+    }
+
     /// Generate llvm function call
     fn gen_llvm_func_call<'a>(
         &'a self,
@@ -384,7 +707,10 @@ impl<'hir, 'run, 'ictx> CodeGen<'hir, 'run, 'ictx> {
         Ok(self.build_ivar_load(object, *idx, name))
     }
 
-    fn gen_const_ref(&self, fullname: &ConstFullname) -> inkwell::values::BasicValueEnum {
+    pub(super) fn gen_const_ref(
+        &self,
+        fullname: &ConstFullname,
+    ) -> inkwell::values::BasicValueEnum {
         let ptr = self
             .module
             .get_global(&fullname.0)
@@ -406,10 +732,15 @@ impl<'hir, 'run, 'ictx> CodeGen<'hir, 'run, 'ictx> {
         arg_types.push(&captures_type);
         let ret_ty = &exprs.ty;
         let func_type = self.llvm_func_type(None, &arg_types, &ret_ty);
-        self.module.add_function(&func_name, func_type, None);
+        // Internal because lambda names are not unique across the library and the program
+        self.module.add_function(
+            &func_name,
+            func_type,
+            Some(inkwell::module::Linkage::Internal),
+        );
 
         // Fn1.new(fnptr, captures)
-        let cls_name = format!("Fn{}", params.len() - 1); // -1 for the last `captures` ary
+        let cls_name = fn_class_fullname(params.len() - 1); // -1 for the last `captures` ary
         let const_name = format!("::{}", cls_name);
         let meta = self.gen_const_ref(&const_fullname(&const_name));
         let fnptr = self
@@ -460,8 +791,8 @@ impl<'hir, 'run, 'ictx> CodeGen<'hir, 'run, 'ictx> {
         self.box_float(&self.f64_type.const_float(value))
     }
 
-    fn gen_decimal_literal(&self, value: i32) -> inkwell::values::BasicValueEnum {
-        self.box_int(&self.i32_type.const_int(value as u64, false))
+    fn gen_decimal_literal(&self, value: i64) -> inkwell::values::BasicValueEnum {
+        self.box_int(&self.i64_type.const_int(value as u64, true))
     }
 
     fn gen_string_literal(&self, idx: &usize) -> inkwell::values::BasicValueEnum {
@@ -480,7 +811,7 @@ impl<'hir, 'run, 'ictx> CodeGen<'hir, 'run, 'ictx> {
 
         // Store bytesize
         let bytesize = self
-            .i32_type
+            .i64_type
             .const_int(self.str_literals[*idx].len() as u64, false);
         let sk_int = self.box_int(&bytesize);
         self.build_ivar_store(&sk_str, 1, sk_int, "@bytesize");
@@ -518,9 +849,9 @@ impl<'hir, 'run, 'ictx> CodeGen<'hir, 'run, 'ictx> {
         let idx_of_captures = ctx.function_params.unwrap().len() - 1;
         let captures = self.gen_arg_ref(ctx, &idx_of_captures)?;
         let obj = self.gen_llvm_func_call(
-            "Array#nth",
+            "Array#[]",
             captures,
-            vec![self.gen_decimal_literal(*idx_in_captures as i32)],
+            vec![self.gen_decimal_literal(*idx_in_captures as i64)],
         )?;
         Ok(self.builder.build_bitcast(obj, self.llvm_type(ty), ""))
     }
@@ -535,6 +866,34 @@ impl<'hir, 'run, 'ictx> CodeGen<'hir, 'run, 'ictx> {
         Ok(self.builder.build_bitcast(obj, self.llvm_type(ty), "as"))
     }
 
+    /// Compare the vtable of the object to that of the class
+    fn gen_is_instance_of(
+        &self,
+        ctx: &mut CodeGenContext<'hir, 'run>,
+        expr: &'hir HirExpression,
+        classname: &ClassFullname,
+    ) -> Result<inkwell::values::BasicValueEnum, Error> {
+        let obj = self.gen_expr(ctx, expr)?;
+        let ptr = self
+            .builder
+            .build_struct_gep(obj.into_pointer_value(), 0, "addr_vtable")
+            .unwrap();
+        let vtable = self.builder.build_load(ptr, "vtable").into_pointer_value();
+        let class_vtable = self
+            .module
+            .get_global(&llvm_vtable_name(classname))
+            .unwrap_or_else(|| panic!("[BUG] vtable of `{}' not found", classname))
+            .as_pointer_value();
+        let eq = self.builder.build_int_compare(
+            inkwell::IntPredicate::EQ,
+            self.builder.build_ptr_to_int(vtable, self.i64_type, ""),
+            self.builder
+                .build_ptr_to_int(class_vtable, self.i64_type, ""),
+            "isInstance",
+        );
+        Ok(self.box_bool(eq))
+    }
+
     fn gen_class_literal(
         &self,
         fullname: &ClassFullname,
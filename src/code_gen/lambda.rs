@@ -58,11 +58,49 @@ impl<'hir: 'ictx, 'run, 'ictx: 'run> CodeGen<'hir, 'run, 'ictx> {
             HirWhileExpression {
                 cond_expr,
                 body_exprs,
+                ..
             } => {
                 self.gen_lambda_funcs_in_expr(cond_expr)?;
                 self.gen_lambda_funcs_in_exprs(body_exprs)?;
             }
-            HirBreakExpression => (),
+            HirLoopExpression { body_exprs, .. } => self.gen_lambda_funcs_in_exprs(body_exprs)?,
+            HirBreakExpression { arg, .. } => {
+                if let Some(expr) = arg {
+                    self.gen_lambda_funcs_in_expr(expr)?;
+                }
+            }
+            HirNextExpression { .. } => (),
+            HirBeginExpression {
+                body_exprs,
+                rescue_exprs,
+                ensure_exprs,
+                ..
+            } => {
+                self.gen_lambda_funcs_in_exprs(body_exprs)?;
+                if let Some(exprs) = rescue_exprs.as_ref() {
+                    self.gen_lambda_funcs_in_exprs(exprs)?;
+                }
+                if let Some(exprs) = ensure_exprs.as_ref() {
+                    self.gen_lambda_funcs_in_exprs(exprs)?;
+                }
+            }
+            HirMatchExpression {
+                cond_assign_expr,
+                clauses,
+            } => {
+                self.gen_lambda_funcs_in_expr(cond_assign_expr)?;
+                for clause in clauses {
+                    for component in &clause.components {
+                        match component {
+                            MatchComponent::Test(expr) | MatchComponent::Bind(_, expr) => {
+                                self.gen_lambda_funcs_in_expr(expr)?
+                            }
+                        }
+                    }
+                    self.gen_lambda_funcs_in_exprs(&clause.body_hir)?;
+                }
+            }
+            HirReturnExpression { arg } => self.gen_lambda_funcs_in_expr(arg)?,
             HirLVarAssign { rhs, .. } => self.gen_lambda_funcs_in_expr(rhs)?,
             HirIVarAssign { rhs, .. } => self.gen_lambda_funcs_in_expr(rhs)?,
             HirConstAssign { rhs, .. } => self.gen_lambda_funcs_in_expr(rhs)?,
@@ -70,6 +108,11 @@ impl<'hir: 'ictx, 'run, 'ictx: 'run> CodeGen<'hir, 'run, 'ictx> {
                 receiver_expr,
                 arg_exprs,
                 ..
+            }
+            | HirSuperCall {
+                receiver_expr,
+                arg_exprs,
+                ..
             } => {
                 self.gen_lambda_funcs_in_expr(receiver_expr)?;
                 for expr in arg_exprs {
@@ -91,6 +134,7 @@ impl<'hir: 'ictx, 'run, 'ictx: 'run> CodeGen<'hir, 'run, 'ictx> {
             }
             HirSelfExpression => (),
             HirArrayLiteral { exprs } => self.gen_lambda_funcs_in_exprs(exprs)?,
+            HirParenthesizedExpr { exprs } => self.gen_lambda_funcs_in_exprs(exprs)?,
             HirFloatLiteral { .. } => (),
             HirDecimalLiteral { .. } => (),
             HirStringLiteral { .. } => (),
@@ -98,7 +142,9 @@ impl<'hir: 'ictx, 'run, 'ictx: 'run> CodeGen<'hir, 'run, 'ictx> {
 
             HirLambdaCaptureRef { .. } => (),
             HirBitCast { expr } => self.gen_lambda_funcs_in_expr(expr)?,
+            HirIsInstanceOf { expr, .. } => self.gen_lambda_funcs_in_expr(expr)?,
             HirClassLiteral { .. } => (),
+            HirErrorPlaceholder => (),
         }
         Ok(())
     }
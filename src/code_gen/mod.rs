@@ -1,11 +1,17 @@
 mod boxing;
 mod code_gen_context;
+mod exception;
 mod gen_exprs;
 mod lambda;
 mod utils;
+mod vtable;
 use crate::code_gen::code_gen_context::*;
+use crate::code_gen::utils::llvm_func_name;
+use crate::code_gen::vtable::*;
+use crate::corelib::sized_int_bits;
 use crate::error::Error;
 use crate::hir::*;
+use crate::library::LibraryExports;
 use crate::names::*;
 use crate::ty::*;
 use either::*;
@@ -13,6 +19,10 @@ use inkwell::types::*;
 use inkwell::values::*;
 use inkwell::AddressSpace;
 use std::collections::HashMap;
+use std::path::Path;
+
+/// Name of the llvm function to initialize the constants of the precompiled library
+const LIBRARY_INIT_FUNC: &str = "init_library_constants";
 
 /// CodeGen
 ///
@@ -28,11 +38,14 @@ pub struct CodeGen<'hir: 'ictx, 'run, 'ictx: 'run> {
     pub i1_type: inkwell::types::IntType<'ictx>,
     pub i8_type: inkwell::types::IntType<'ictx>,
     pub i8ptr_type: inkwell::types::PointerType<'ictx>,
+    pub i16_type: inkwell::types::IntType<'ictx>,
     pub i32_type: inkwell::types::IntType<'ictx>,
     pub i64_type: inkwell::types::IntType<'ictx>,
+    pub f32_type: inkwell::types::FloatType<'ictx>,
     pub f64_type: inkwell::types::FloatType<'ictx>,
     pub void_type: inkwell::types::VoidType<'ictx>,
     pub llvm_struct_types: HashMap<ClassFullname, inkwell::types::StructType<'ictx>>,
+    vtables: VTables,
     str_literals: &'hir Vec<String>,
     /// Toplevel `self`
     the_main: Option<inkwell::values::BasicValueEnum<'ictx>>,
@@ -49,6 +62,20 @@ pub fn run(hir: &Hir, outpath: &str) -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+/// Compile hir of a library (which has no `main`) and write the bitcode to `outpath`
+pub fn run_library(hir: &Hir, outpath: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let context = inkwell::context::Context::create();
+    let module = context.create_module("library");
+    let builder = context.create_builder();
+    let mut code_gen = CodeGen::new(&hir, &context, &module, &builder);
+    code_gen.gen_library(&hir)?;
+    if !code_gen.module.write_bitcode_to_path(outpath) {
+        let msg = format!("failed to write {}", outpath.display());
+        return Err(Box::new(crate::error::plain_runner_error(msg)));
+    }
+    Ok(())
+}
+
 impl<'hir: 'ictx, 'run, 'ictx: 'run> CodeGen<'hir, 'run, 'ictx> {
     pub fn new(
         hir: &'hir Hir,
@@ -56,6 +83,14 @@ impl<'hir: 'ictx, 'run, 'ictx: 'run> CodeGen<'hir, 'run, 'ictx> {
         module: &'run inkwell::module::Module<'ictx>,
         builder: &'run inkwell::builder::Builder<'ictx>,
     ) -> CodeGen<'hir, 'run, 'ictx> {
+        let implemented = hir
+            .sk_methods
+            .values()
+            .flatten()
+            .map(|method| &method.signature)
+            .chain(hir.imports.sk_methods.values().flatten())
+            .map(|sig| sig.fullname.full_name.clone())
+            .collect();
         CodeGen {
             context,
             module,
@@ -63,27 +98,55 @@ impl<'hir: 'ictx, 'run, 'ictx: 'run> CodeGen<'hir, 'run, 'ictx> {
             i1_type: context.bool_type(),
             i8_type: context.i8_type(),
             i8ptr_type: context.i8_type().ptr_type(AddressSpace::Generic),
+            i16_type: context.i16_type(),
             i32_type: context.i32_type(),
             i64_type: context.i64_type(),
+            f32_type: context.f32_type(),
             f64_type: context.f64_type(),
             void_type: context.void_type(),
             llvm_struct_types: HashMap::new(),
+            vtables: VTables::build(&hir.sk_classes, &implemented),
             str_literals: &hir.str_literals,
             the_main: None,
         }
     }
 
     pub fn gen_program(&mut self, hir: &'hir Hir) -> Result<(), Error> {
+        self.gen_items(hir, "init_constants", inkwell::module::Linkage::Internal)?;
+        self.gen_user_main(&hir.main_exprs)?;
+        self.gen_lambda_funcs(&hir)?;
+        self.gen_main(!hir.imports.is_empty())?;
+        Ok(())
+    }
+
+    /// Generate a library (i.e. a program without `main`)
+    pub fn gen_library(&mut self, hir: &'hir Hir) -> Result<(), Error> {
+        // The globals are referred from the programs
+        self.gen_items(hir, LIBRARY_INIT_FUNC, inkwell::module::Linkage::External)?;
+        self.gen_lambda_funcs(&hir)?;
+        Ok(())
+    }
+
+    /// Generate classes, methods and constants
+    /// (`linkage` is the linkage of the vtables and the constants)
+    fn gen_items(
+        &mut self,
+        hir: &'hir Hir,
+        init_func_name: &str,
+        linkage: inkwell::module::Linkage,
+    ) -> Result<(), Error> {
         self.gen_declares();
-        self.gen_class_structs(&hir.sk_classes);
+        self.gen_exception_declares();
+        self.gen_exception_globals(!hir.imports.is_empty());
+        self.gen_class_structs(&hir.sk_classes, &hir.imports, linkage);
         self.gen_string_literals(&hir.str_literals);
-        self.gen_constant_ptrs(&hir.constants);
+        self.gen_imported_constants(&hir.imports.constants);
+        self.gen_constant_ptrs(&hir.constants, linkage);
+        self.gen_imported_method_funcs(&hir.imports.sk_methods);
         self.gen_method_funcs(&hir.sk_methods);
+        self.gen_vtables(&hir.imports);
         self.gen_methods(&hir.sk_methods)?;
-        self.gen_const_inits(&hir.const_inits)?;
-        self.gen_user_main(&hir.main_exprs)?;
-        self.gen_lambda_funcs(&hir)?;
-        self.gen_main()?;
+        self.gen_const_inits(&hir.const_inits, &hir.constants, init_func_name)?;
         Ok(())
     }
 
@@ -117,6 +180,15 @@ impl<'hir: 'ictx, 'run, 'ictx: 'run> CodeGen<'hir, 'run, 'ictx> {
         );
         self.module
             .add_function("llvm.memcpy.p0i8.p0i8.i64", fn_type, None);
+        let fn_type = self.i32_type.fn_type(
+            &[
+                self.i8ptr_type.into(),
+                self.i8ptr_type.into(),
+                self.i64_type.into(),
+            ],
+            false,
+        );
+        self.module.add_function("memcmp", fn_type, None);
 
         let fn_type = self.f64_type.fn_type(&[self.f64_type.into()], false);
         self.module.add_function("sin", fn_type, None);
@@ -129,11 +201,13 @@ impl<'hir: 'ictx, 'run, 'ictx: 'run> CodeGen<'hir, 'run, 'ictx> {
         let fn_type = self.f64_type.fn_type(&[self.f64_type.into()], false);
         self.module.add_function("floor", fn_type, None);
 
-        let str_type = self.i8_type.array_type(3);
+        let str_type = self.i8_type.array_type(5);
         let global = self.module.add_global(str_type, None, "putd_tmpl");
         global.set_linkage(inkwell::module::Linkage::Internal);
         global.set_initializer(&self.i8_type.const_array(&[
             self.i8_type.const_int(37, false),  // %
+            self.i8_type.const_int(108, false), // l
+            self.i8_type.const_int(108, false), // l
             self.i8_type.const_int(100, false), // d
             self.i8_type.const_int(0, false),
         ]));
@@ -171,7 +245,7 @@ impl<'hir: 'ictx, 'run, 'ictx: 'run> CodeGen<'hir, 'run, 'ictx> {
         Ok(())
     }
 
-    fn gen_main(&mut self) -> Result<(), Error> {
+    fn gen_main(&mut self, with_library: bool) -> Result<(), Error> {
         // define i32 @main() {
         let main_type = self.i32_type.fn_type(&[], false);
         let function = self.module.add_function("main", main_type, None);
@@ -182,7 +256,12 @@ impl<'hir: 'ictx, 'run, 'ictx: 'run> CodeGen<'hir, 'run, 'ictx> {
         let func = self.get_llvm_func("GC_init");
         self.builder.build_call(func, &[], "");
 
-        // Call init_constants, user_main
+        // Call init_library_constants, init_constants, user_main
+        if with_library {
+            let fn_type = self.void_type.fn_type(&[], false);
+            let func = self.module.add_function(LIBRARY_INIT_FUNC, fn_type, None);
+            self.builder.build_call(func, &[], "");
+        }
         let func = self.get_llvm_func("init_constants");
         self.builder.build_call(func, &[], "");
         let func = self.get_llvm_func("user_main");
@@ -195,7 +274,12 @@ impl<'hir: 'ictx, 'run, 'ictx: 'run> CodeGen<'hir, 'run, 'ictx> {
     }
 
     /// Create llvm struct types for Shiika objects
-    fn gen_class_structs(&mut self, classes: &HashMap<ClassFullname, SkClass>) {
+    fn gen_class_structs(
+        &mut self,
+        classes: &HashMap<ClassFullname, SkClass>,
+        imports: &LibraryExports,
+        linkage: inkwell::module::Linkage,
+    ) {
         // 1. Create struct type for each class
         for name in classes.keys() {
             self.llvm_struct_types
@@ -205,15 +289,72 @@ impl<'hir: 'ictx, 'run, 'ictx: 'run> CodeGen<'hir, 'run, 'ictx> {
         // 2. Set ivars
         for (name, sk_class) in classes {
             let struct_type = self.llvm_struct_types.get(&name).unwrap();
+            // The first field is the object header (pointer to the vtable)
+            let mut fields = vec![self.i8ptr_type.into()];
             if name.0 == "Int" {
-                struct_type.set_body(&[self.i32_type.into()], false);
+                fields.push(self.i64_type.into());
+            } else if let Some(bits) = sized_int_bits(&name.0) {
+                fields.push(self.sized_int_type(bits).into());
             } else if name.0 == "Float" {
-                struct_type.set_body(&[self.f64_type.into()], false);
+                fields.push(self.f64_type.into());
+            } else if name.0 == "Float32" {
+                fields.push(self.f32_type.into());
             } else if name.0 == "Bool" {
-                struct_type.set_body(&[self.i1_type.into()], false);
+                fields.push(self.i1_type.into());
             } else {
-                struct_type.set_body(&self.llvm_field_types(&sk_class.ivars), false);
+                fields.append(&mut self.llvm_field_types(&sk_class.ivars));
             }
+            struct_type.set_body(&fields, false);
+        }
+
+        // 3. Declare vtables (initialized in gen_vtables)
+        for (name, vtable) in self.vtables.iter() {
+            let ary_type = self.i8ptr_type.array_type(vtable.size() as u32);
+            let global = self
+                .module
+                .add_global(ary_type, None, &llvm_vtable_name(name));
+            // Vtables of the library classes are defined in the library
+            if !imports.sk_classes.contains_key(name) {
+                global.set_linkage(linkage);
+            }
+            global.set_constant(true);
+        }
+    }
+
+    /// Set the contents of the vtables
+    /// (must be called after the llvm functions are created)
+    fn gen_vtables(&self, imports: &LibraryExports) {
+        for (name, vtable) in self.vtables.iter() {
+            // Defined in the library
+            if imports.sk_classes.contains_key(name) {
+                continue;
+            }
+            let func_ptrs = vtable
+                .fullnames()
+                .iter()
+                .map(|method_name| {
+                    self.get_llvm_func(&method_name.full_name)
+                        .as_global_value()
+                        .as_pointer_value()
+                        .const_cast(self.i8ptr_type)
+                })
+                .collect::<Vec<_>>();
+            let global = self
+                .module
+                .get_global(&llvm_vtable_name(name))
+                .unwrap_or_else(|| panic!("[BUG] vtable of `{}' not declared", name));
+            global.set_initializer(&self.i8ptr_type.const_array(&func_ptrs));
+        }
+    }
+
+    /// LLVM type of the value of `Int8`, `UInt16`, etc.
+    pub fn sized_int_type(&self, bits: u32) -> inkwell::types::IntType<'ictx> {
+        match bits {
+            8 => self.i8_type,
+            16 => self.i16_type,
+            32 => self.i32_type,
+            64 => self.i64_type,
+            _ => panic!("[BUG] unsupported int width: {}", bits),
         }
     }
 
@@ -249,11 +390,23 @@ impl<'hir: 'ictx, 'run, 'ictx: 'run> CodeGen<'hir, 'run, 'ictx> {
         })
     }
 
-    fn gen_constant_ptrs(&self, constants: &HashMap<ConstFullname, TermTy>) {
+    /// Declare the constants defined in the library
+    fn gen_imported_constants(&self, constants: &HashMap<ConstFullname, TermTy>) {
+        for (fullname, ty) in constants {
+            self.module
+                .add_global(self.llvm_type(&ty), None, &fullname.0);
+        }
+    }
+
+    fn gen_constant_ptrs(
+        &self,
+        constants: &HashMap<ConstFullname, TermTy>,
+        linkage: inkwell::module::Linkage,
+    ) {
         for (fullname, ty) in constants {
             let name = &fullname.0;
             let global = self.module.add_global(self.llvm_type(&ty), None, name);
-            global.set_linkage(inkwell::module::Linkage::Internal);
+            global.set_linkage(linkage);
             let null = self.i32_type.ptr_type(AddressSpace::Generic).const_null();
             match self.llvm_zero_value(ty) {
                 Some(zero) => global.set_initializer(&zero),
@@ -262,7 +415,12 @@ impl<'hir: 'ictx, 'run, 'ictx: 'run> CodeGen<'hir, 'run, 'ictx> {
         }
     }
 
-    fn gen_const_inits(&self, const_inits: &'hir [HirExpression]) -> Result<(), Error> {
+    fn gen_const_inits(
+        &self,
+        const_inits: &'hir [HirExpression],
+        constants: &HashMap<ConstFullname, TermTy>,
+        init_func_name: &str,
+    ) -> Result<(), Error> {
         // define void @"init_::XX"
         for expr in const_inits {
             match &expr.node {
@@ -281,7 +439,7 @@ impl<'hir: 'ictx, 'run, 'ictx: 'run> CodeGen<'hir, 'run, 'ictx> {
 
         // define void @init_constants()
         let fn_type = self.void_type.fn_type(&[], false);
-        let function = self.module.add_function("init_constants", fn_type, None);
+        let function = self.module.add_function(init_func_name, fn_type, None);
         let basic_block = self.context.append_basic_block(function, "");
         self.builder.position_at_end(basic_block);
 
@@ -296,27 +454,40 @@ impl<'hir: 'ictx, 'run, 'ictx: 'run> CodeGen<'hir, 'run, 'ictx> {
             }
         }
 
-        // Generate ::Void
-        let ptr = self
-            .module
-            .get_global(&"::Void")
-            .unwrap()
-            .as_pointer_value();
-        let value = self.allocate_sk_obj(&class_fullname("Void"), "void_obj");
-        self.builder.build_store(ptr, value);
+        // Generate ::Void (unless defined in the library)
+        if constants.contains_key(&const_fullname("::Void")) {
+            let ptr = self
+                .module
+                .get_global(&"::Void")
+                .unwrap()
+                .as_pointer_value();
+            let value = self.allocate_sk_obj(&class_fullname("Void"), "void_obj");
+            self.builder.build_store(ptr, value);
+        }
 
         self.builder.build_return(None);
         Ok(())
     }
 
+    /// Declare the methods defined in the library
+    fn gen_imported_method_funcs(&self, sigs: &HashMap<ClassFullname, Vec<MethodSignature>>) {
+        for (cname, sigs) in sigs {
+            for sig in sigs {
+                let func_type = self.method_llvm_func_type(&cname.to_ty(), sig);
+                let func_name = llvm_func_name(&sig.fullname.full_name);
+                self.module.add_function(&func_name, func_type, None);
+            }
+        }
+    }
+
     /// Create inkwell functions
     fn gen_method_funcs(&self, methods: &HashMap<ClassFullname, Vec<SkMethod>>) {
         methods.iter().for_each(|(cname, sk_methods)| {
             sk_methods.iter().for_each(|method| {
                 let self_ty = cname.to_ty();
                 let func_type = self.method_llvm_func_type(&self_ty, &method.signature);
-                self.module
-                    .add_function(&method.signature.fullname.full_name, func_type, None);
+                let func_name = llvm_func_name(&method.signature.fullname.full_name);
+                self.module.add_function(&func_name, func_type, None);
             })
         })
     }
@@ -1,9 +1,13 @@
 /// Provides utility functions used by code_gen/*.rs
 /// (some are also used by corelib/*.rs)
+use crate::code_gen::vtable::llvm_vtable_name;
 use crate::code_gen::*;
 use inkwell::types::*;
 use inkwell::AddressSpace;
 
+/// Number of the fields before ivars (currently, just the vtable)
+const OBJ_HEADER_SIZE: usize = 1;
+
 impl<'hir, 'run, 'ictx> CodeGen<'hir, 'run, 'ictx> {
     pub fn build_ivar_load<'a>(
         &'a self,
@@ -15,7 +19,7 @@ impl<'hir, 'run, 'ictx> CodeGen<'hir, 'run, 'ictx> {
             .builder
             .build_struct_gep(
                 object.into_pointer_value(),
-                idx as u32,
+                (OBJ_HEADER_SIZE + idx) as u32,
                 &format!("addr_{}", name),
             )
             .unwrap();
@@ -33,7 +37,7 @@ impl<'hir, 'run, 'ictx> CodeGen<'hir, 'run, 'ictx> {
             .builder
             .build_struct_gep(
                 object.into_pointer_value(),
-                idx as u32,
+                (OBJ_HEADER_SIZE + idx) as u32,
                 &format!("addr_{}", name),
             )
             .unwrap();
@@ -62,7 +66,41 @@ impl<'hir, 'run, 'ictx> CodeGen<'hir, 'run, 'ictx> {
             .unwrap();
 
         // %foo = bitcast i8* %mem to %#{t}*",
-        self.builder.build_bitcast(raw_addr, obj_ptr_type, reg_name)
+        let obj = self.builder.build_bitcast(raw_addr, obj_ptr_type, reg_name);
+
+        // Store the vtable to the header
+        let vtable = self
+            .module
+            .get_global(&llvm_vtable_name(class_fullname))
+            .unwrap_or_else(|| panic!("[BUG] vtable of `{}' not found", class_fullname))
+            .as_pointer_value();
+        let vtable_i8 = self.builder.build_bitcast(vtable, self.i8ptr_type, "");
+        let ptr = self
+            .builder
+            .build_struct_gep(obj.into_pointer_value(), 0, "addr_vtable")
+            .unwrap();
+        self.builder.build_store(ptr, vtable_i8);
+
+        obj
+    }
+
+    /// Load the vtable of a Shiika object as `i8**`
+    pub fn build_vtable_load<'a>(
+        &'a self,
+        object: inkwell::values::BasicValueEnum<'a>,
+    ) -> inkwell::values::PointerValue<'a> {
+        let ptr = self
+            .builder
+            .build_struct_gep(object.into_pointer_value(), 0, "addr_vtable")
+            .unwrap();
+        let vtable = self.builder.build_load(ptr, "vtable");
+        self.builder
+            .build_bitcast(
+                vtable,
+                self.i8ptr_type.ptr_type(AddressSpace::Generic),
+                "vtable_ary",
+            )
+            .into_pointer_value()
     }
 
     pub fn llvm_type(&self, ty: &TermTy) -> inkwell::types::BasicTypeEnum<'ictx> {
@@ -99,7 +137,24 @@ impl<'hir, 'run, 'ictx> CodeGen<'hir, 'run, 'ictx> {
     /// Panic if not found
     pub(super) fn get_llvm_func(&self, name: &str) -> inkwell::values::FunctionValue<'ictx> {
         self.module
-            .get_function(name)
+            .get_function(&llvm_func_name(name))
             .unwrap_or_else(|| panic!("[BUG] get_llvm_func: `{:?}' not found", name))
     }
 }
+
+/// Name of the llvm function for the method `name` (eg. `Int#even?`).
+/// `?` and `!` at the end of a method name are replaced with `.q` and `.b`
+/// (`.` cannot be a part of a method name, so the names do not collide)
+pub fn llvm_func_name(name: &str) -> String {
+    let mangled = if name.ends_with('?') {
+        Some(".q")
+    } else if name.ends_with('!') {
+        Some(".b")
+    } else {
+        None
+    };
+    match mangled {
+        Some(suffix) => format!("{}{}", &name[..name.len() - 1], suffix),
+        None => name.to_string(),
+    }
+}
@@ -0,0 +1,158 @@
+use crate::hir::*;
+use crate::names::*;
+use crate::ty::MethodSignature;
+use std::collections::{HashMap, HashSet};
+
+/// Virtual method table of a class
+///
+/// Methods inherited from the superclass keep the same index as in the
+/// vtable of the superclass, so an index looked up on a class is also
+/// valid for the instances of its subclasses.
+///
+/// Methods which have no llvm function (eg. `Meta:Int#new`) are not
+/// included.
+#[derive(Debug, Clone)]
+pub struct VTable {
+    /// Method for each slot
+    fullnames: Vec<MethodFullname>,
+    /// Index of each method in `fullnames`
+    index: HashMap<MethodFirstname, usize>,
+}
+
+impl VTable {
+    fn new(
+        super_vtable: Option<&VTable>,
+        sk_class: &SkClass,
+        sk_classes: &HashMap<ClassFullname, SkClass>,
+        implemented: &HashSet<String>,
+    ) -> VTable {
+        let mut vtable = match super_vtable {
+            Some(v) => v.clone(),
+            None => VTable {
+                fullnames: vec![],
+                index: HashMap::new(),
+            },
+        };
+        // Sort to make the layout stable
+        let mixin_sigs = mixin_sigs(sk_classes, sk_class);
+        let mut sigs = sk_class
+            .method_sigs
+            .values()
+            .chain(mixin_sigs.iter())
+            .filter(|sig| implemented.contains(&sig.fullname.full_name))
+            .collect::<Vec<_>>();
+        sigs.sort_by_key(|sig| &sig.fullname.full_name);
+        for sig in sigs {
+            let firstname = &sig.fullname.first_name;
+            match vtable.index.get(firstname) {
+                Some(i) => vtable.fullnames[*i] = sig.fullname.clone(),
+                None => {
+                    vtable
+                        .index
+                        .insert(firstname.clone(), vtable.fullnames.len());
+                    vtable.fullnames.push(sig.fullname.clone());
+                }
+            }
+        }
+        vtable
+    }
+
+    /// Number of the slots
+    pub fn size(&self) -> usize {
+        self.fullnames.len()
+    }
+
+    /// Return the index of the method, if any
+    pub fn get(&self, name: &MethodFirstname) -> Option<usize> {
+        self.index.get(name).copied()
+    }
+
+    /// Return the methods in slot order
+    pub fn fullnames(&self) -> &[MethodFullname] {
+        &self.fullnames
+    }
+}
+
+/// Vtables of all the classes
+#[derive(Debug)]
+pub struct VTables {
+    vtables: HashMap<ClassFullname, VTable>,
+}
+
+impl VTables {
+    /// `implemented` is the full names of the methods which have llvm
+    /// functions
+    pub fn build(
+        sk_classes: &HashMap<ClassFullname, SkClass>,
+        implemented: &HashSet<String>,
+    ) -> VTables {
+        let mut vtables = HashMap::new();
+        for name in sk_classes.keys() {
+            build_vtable(sk_classes, implemented, &mut vtables, name);
+        }
+        VTables { vtables }
+    }
+
+    /// Return the vtable of the class, if any
+    pub fn get(&self, classname: &ClassFullname) -> Option<&VTable> {
+        self.vtables.get(classname)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&ClassFullname, &VTable)> {
+        self.vtables.iter()
+    }
+}
+
+/// Create the vtable of the class (and its ancestors) unless created
+fn build_vtable(
+    sk_classes: &HashMap<ClassFullname, SkClass>,
+    implemented: &HashSet<String>,
+    vtables: &mut HashMap<ClassFullname, VTable>,
+    name: &ClassFullname,
+) {
+    if vtables.contains_key(name) {
+        return;
+    }
+    let sk_class = sk_classes
+        .get(name)
+        .unwrap_or_else(|| panic!("[BUG] build_vtable: class `{}' not found", name));
+    if let Some(super_name) = &sk_class.superclass_fullname {
+        build_vtable(sk_classes, implemented, vtables, super_name);
+    }
+    let vtable = VTable::new(
+        sk_class
+            .superclass_fullname
+            .as_ref()
+            .and_then(|super_name| vtables.get(super_name)),
+        sk_class,
+        sk_classes,
+        implemented,
+    );
+    vtables.insert(name.clone(), vtable);
+}
+
+/// Signatures of the methods which the class gets from the included modules
+/// (except the ones overridden by the class)
+fn mixin_sigs(
+    sk_classes: &HashMap<ClassFullname, SkClass>,
+    sk_class: &SkClass,
+) -> Vec<MethodSignature> {
+    let mut sigs = HashMap::new();
+    // The module included last wins
+    for module_name in &sk_class.includes {
+        let module = sk_classes
+            .get(module_name)
+            .unwrap_or_else(|| panic!("[BUG] mixin_sigs: module `{}' not found", module_name));
+        for (name, sig) in &module.method_sigs {
+            if name.0 != "initialize" && !sk_class.method_sigs.contains_key(name) {
+                sigs.insert(name, sig.with_owner(&sk_class.fullname));
+            }
+        }
+    }
+    sigs.into_iter().map(|(_, sig)| sig).collect()
+}
+
+/// Name of the llvm constant which holds the vtable of the class
+pub fn llvm_vtable_name(classname: &ClassFullname) -> String {
+    format!("vtable_{}", classname.0)
+}
@@ -199,11 +199,21 @@ pub fn create_methods() -> Vec<SkMethod> {
             let float = code_gen.unbox_float(this);
             let int = code_gen
                 .builder
-                .build_float_to_signed_int(float, code_gen.i32_type, "int");
+                .build_float_to_signed_int(float, code_gen.i64_type, "int");
             let sk_int = code_gen.box_int(&int);
             code_gen.builder.build_return(Some(&sk_int));
             Ok(())
         }),
+        create_method("Float", "to_f32() -> Float32", |code_gen, function| {
+            let this = function.get_params()[0];
+            let float = code_gen.unbox_float(this);
+            let float32 = code_gen
+                .builder
+                .build_float_trunc(float, code_gen.f32_type, "float32");
+            let sk_float32 = code_gen.box_float32(&float32);
+            code_gen.builder.build_return(Some(&sk_float32));
+            Ok(())
+        }),
         create_method("Float", "-@ -> Float", |code_gen, function| {
             let this = function.get_params()[0];
             let float = code_gen.unbox_float(this);
@@ -1,5 +1,6 @@
 use crate::code_gen::CodeGen;
 use crate::corelib::create_method;
+use crate::corelib::sized_num;
 use crate::hir::*;
 use inkwell::values::IntValue;
 
@@ -44,7 +45,7 @@ macro_rules! create_arithmetic_method {
 }
 
 pub fn create_methods() -> Vec<SkMethod> {
-    vec![
+    let mut methods = vec![
         create_comparison_method!(
             "==",
             fn f<'a>(
@@ -232,11 +233,13 @@ pub fn create_methods() -> Vec<SkMethod> {
         create_method("Int", "-@ -> Int", |code_gen, function| {
             let sk_int = function.get_params()[0];
             let this = code_gen.unbox_int(sk_int);
-            let zero = code_gen.i32_type.const_int(0, false);
+            let zero = code_gen.i64_type.const_int(0, false);
             let result = code_gen.builder.build_int_sub(zero, this, "result");
             let sk_result = code_gen.box_int(&result);
             code_gen.builder.build_return(Some(&sk_result));
             Ok(())
         }),
-    ]
+    ];
+    methods.append(&mut sized_num::int_conversion_methods());
+    methods
 }
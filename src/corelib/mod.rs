@@ -7,12 +7,14 @@ mod never;
 mod object;
 mod shiika_internal_memory;
 mod shiika_internal_ptr;
+mod sized_num;
 mod string;
 mod void;
 use crate::hir::*;
 use crate::names::*;
 use crate::parser;
 use crate::ty;
+pub use sized_num::sized_int_bits;
 use std::collections::HashMap;
 
 pub struct Corelib {
@@ -37,6 +39,21 @@ impl Corelib {
             sk_methods,
         }
     }
+
+    /// Create the classes `FnN` for each of `arities`
+    pub fn fn_classes(arities: &[usize]) -> Corelib {
+        let items = arities.iter().map(|&i| fn_x::fn_item(i)).collect();
+        let (sk_classes, sk_methods) = make_classes(items);
+        Corelib {
+            sk_classes,
+            sk_methods,
+        }
+    }
+}
+
+/// Source of the Shiika part of the class `FnN`
+pub fn fn_class_src(arity: usize) -> String {
+    fn_x::fn_class_src(arity)
 }
 
 type ClassItem = (
@@ -131,6 +148,7 @@ fn rust_body_items() -> Vec<ClassItem> {
             vec![],
         ),
     ];
+    ret.append(&mut sized_num::sized_num_items());
     ret.append(&mut fn_x::fn_items());
     ret
 }
@@ -164,7 +182,10 @@ fn make_classes(
                     .iter()
                     .map(|x| (x.signature.first_name().clone(), x.signature.clone()))
                     .collect(),
+                is_module: false,
+                includes: vec![],
                 const_is_obj: (name == "Void"),
+                enum_cases: None,
             },
         );
 
@@ -190,7 +211,10 @@ fn make_classes(
                     .iter()
                     .map(|x| (x.signature.first_name().clone(), x.signature.clone()))
                     .collect(),
+                is_module: false,
+                includes: vec![],
                 const_is_obj: false,
+                enum_cases: None,
             },
         );
 
@@ -210,17 +234,31 @@ fn create_method_generic(
     gen: GenMethodBody,
     typaram_names: &[String],
 ) -> SkMethod {
-    let mut parser = parser::Parser::new_with_state(sig_str, parser::lexer::LexerState::MethodName);
-    let (ast_sig, _) = parser.parse_method_signature().unwrap();
-    parser.expect_eof().unwrap();
-    let sig = crate::hir::signature::create_signature(
-        &class_fullname(class_name),
-        &ast_sig,
-        typaram_names,
-    );
-
     SkMethod {
-        signature: sig,
+        signature: parse_signature(class_name, sig_str, typaram_names),
         body: SkMethodBody::RustMethodBody { gen },
     }
 }
+
+fn create_closure_method_generic(
+    class_name: &str,
+    sig_str: &str,
+    boxed_gen: Box<ClosureMethodBody>,
+    typaram_names: &[String],
+) -> SkMethod {
+    SkMethod {
+        signature: parse_signature(class_name, sig_str, typaram_names),
+        body: SkMethodBody::RustClosureMethodBody { boxed_gen },
+    }
+}
+
+fn parse_signature(
+    class_name: &str,
+    sig_str: &str,
+    typaram_names: &[String],
+) -> ty::MethodSignature {
+    let mut parser = parser::Parser::new_with_state(sig_str, parser::lexer::LexerState::MethodName);
+    let (ast_sig, _) = parser.parse_method_signature().unwrap();
+    parser.expect_eof().unwrap();
+    crate::hir::signature::create_signature(&class_fullname(class_name), &ast_sig, typaram_names)
+}
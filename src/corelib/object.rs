@@ -1,5 +1,6 @@
 use crate::corelib::create_method;
 use crate::hir::*;
+use crate::ty;
 use inkwell::values::*;
 
 pub fn create_methods() -> Vec<SkMethod> {
@@ -14,10 +15,13 @@ pub fn create_methods() -> Vec<SkMethod> {
             |code_gen, function| {
                 let sk_int = function.get_params()[1];
                 let n = code_gen.unbox_int(sk_int);
+                let n_32 = code_gen
+                    .builder
+                    .build_int_truncate(n, code_gen.i32_type, "n_32");
                 let func = code_gen.module.get_function("putchar").unwrap();
                 code_gen
                     .builder
-                    .build_call(func, &[n.as_basic_value_enum()], "");
+                    .build_call(func, &[n_32.as_basic_value_enum()], "");
                 code_gen.builder.build_return(None);
                 Ok(())
             },
@@ -65,23 +69,52 @@ pub fn create_methods() -> Vec<SkMethod> {
             Ok(())
         }),
         create_method("Object", "puts(s: String) -> Void", |code_gen, function| {
-            let s = function.get_params()[1].into_pointer_value();
-            let pptr = code_gen.builder.build_struct_gep(s, 0, "").unwrap();
-            let ptr = code_gen.builder.build_load(pptr, "");
+            let sk_str = function.get_params()[1];
+            let ptr = code_gen.build_ivar_load(sk_str, 0, "@ptr");
             let func = code_gen.module.get_function("puts").unwrap();
             code_gen.builder.build_call(func, &[ptr], "");
             code_gen.builder.build_return(None);
             Ok(())
         }),
+        create_method("Object", "raise(e: Exception) -> Never", |code_gen, function| {
+            let e = function.get_params()[1];
+            code_gen.gen_raise(function, e);
+            Ok(())
+        }),
         create_method("Object", "exit(status: Int) -> Void", |code_gen, function| {
             let sk_int = function.get_params()[1];
             let status = code_gen.unbox_int(sk_int);
+            let status_32 = code_gen
+                .builder
+                .build_int_truncate(status, code_gen.i32_type, "status_32");
             let func = code_gen.module.get_function("exit").unwrap();
             code_gen
                 .builder
-                .build_call(func, &[status.as_basic_value_enum()], "");
+                .build_call(func, &[status_32.as_basic_value_enum()], "");
             code_gen.builder.build_return(None);
             Ok(())
         }),
+        create_method("Object", "Some<T>(value: T) -> Maybe<T>", |code_gen, function| {
+            // Call `Maybe.new(value, true)` (which is hidden from the programs)
+            let cls_ptr = code_gen.module.get_global("::Maybe").unwrap();
+            let cls = code_gen
+                .builder
+                .build_load(cls_ptr.as_pointer_value(), "Maybe");
+            let value = code_gen.builder.build_bitcast(
+                function.get_params()[1],
+                code_gen.llvm_type(&ty::raw("Object")),
+                "value",
+            );
+            let is_some = code_gen.box_bool(code_gen.i1_type.const_int(1, false));
+            let func = code_gen.module.get_function("Meta:Maybe#new").unwrap();
+            let maybe = code_gen
+                .builder
+                .build_call(func, &[cls, value, is_some], "maybe")
+                .try_as_basic_value()
+                .left()
+                .unwrap();
+            code_gen.builder.build_return(Some(&maybe));
+            Ok(())
+        }),
     ]
 }
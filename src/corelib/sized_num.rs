@@ -0,0 +1,324 @@
+//! Fixed-size numeric classes (`Int8` ~ `Int64`, `UInt8` ~ `UInt64` and
+//! `Float32`.) They have no literals; use the conversion methods like
+//! `Int#to_u8` or `Float#to_f32` to create them.
+use crate::code_gen::CodeGen;
+use crate::corelib::*;
+use inkwell::values::*;
+use inkwell::{FloatPredicate, IntPredicate};
+
+/// (Class name, bit width, is signed, name of the conversion method of `Int`)
+const SIZED_INTS: [(&str, u32, bool, &str); 8] = [
+    ("Int8", 8, true, "to_i8"),
+    ("Int16", 16, true, "to_i16"),
+    ("Int32", 32, true, "to_i32"),
+    ("Int64", 64, true, "to_i64"),
+    ("UInt8", 8, false, "to_u8"),
+    ("UInt16", 16, false, "to_u16"),
+    ("UInt32", 32, false, "to_u32"),
+    ("UInt64", 64, false, "to_u64"),
+];
+
+const COMPARISON_OPS: [&str; 6] = ["==", "!=", "<", ">", "<=", ">="];
+const INT_ARITHMETIC_OPS: [&str; 10] = ["+", "-", "*", "/", "reminder", "&", "|", "^", "<<", ">>"];
+const FLOAT_ARITHMETIC_OPS: [&str; 4] = ["+", "-", "*", "/"];
+
+/// Returns the bit width if `name` is one of the sized int classes
+pub fn sized_int_bits(name: &str) -> Option<u32> {
+    SIZED_INTS
+        .iter()
+        .find(|(class_name, ..)| *class_name == name)
+        .map(|(_, bits, ..)| *bits)
+}
+
+/// The classes `Int8`, ..., `UInt64` and `Float32`
+pub fn sized_num_items() -> Vec<ClassItem> {
+    let mut items = SIZED_INTS
+        .iter()
+        .map(|&(name, bits, signed, _)| {
+            (
+                name.to_string(),
+                sized_int_methods(name, bits, signed),
+                vec![],
+                HashMap::new(),
+                vec![],
+            )
+        })
+        .collect::<Vec<_>>();
+    items.push((
+        "Float32".to_string(),
+        float32_methods(),
+        vec![],
+        HashMap::new(),
+        vec![],
+    ));
+    items
+}
+
+/// `Int#to_i8`, ..., `Int#to_u64` (The upper bits are truncated)
+pub fn int_conversion_methods() -> Vec<SkMethod> {
+    SIZED_INTS
+        .iter()
+        .map(|&(name, bits, _, method_name)| {
+            create_closure_method_generic(
+                "Int",
+                &format!("{}() -> {}", method_name, name),
+                Box::new(move |code_gen, function| {
+                    let int = code_gen.unbox_int(function.get_params()[0]);
+                    let result = if bits == 64 {
+                        int
+                    } else {
+                        code_gen.builder.build_int_truncate(
+                            int,
+                            code_gen.sized_int_type(bits),
+                            "result",
+                        )
+                    };
+                    let sk_result = code_gen.box_sized_int(name, &result);
+                    code_gen.builder.build_return(Some(&sk_result));
+                    Ok(())
+                }),
+                &[],
+            )
+        })
+        .collect()
+}
+
+fn sized_int_methods(name: &'static str, bits: u32, signed: bool) -> Vec<SkMethod> {
+    let mut methods = vec![];
+    for &op in &COMPARISON_OPS {
+        methods.push(create_closure_method_generic(
+            name,
+            &format!("{}(other: {}) -> Bool", op, name),
+            Box::new(move |code_gen, function| {
+                let val1 = code_gen.unbox_int(function.get_params()[0]);
+                let val2 = code_gen.unbox_int(function.get_params()[1]);
+                let result = code_gen.builder.build_int_compare(
+                    int_predicate(op, signed),
+                    val1,
+                    val2,
+                    "result",
+                );
+                let sk_result = code_gen.box_bool(result);
+                code_gen.builder.build_return(Some(&sk_result));
+                Ok(())
+            }),
+            &[],
+        ));
+    }
+    for &op in &INT_ARITHMETIC_OPS {
+        methods.push(create_closure_method_generic(
+            name,
+            &format!("{}(other: {}) -> {}", op, name, name),
+            Box::new(move |code_gen, function| {
+                let val1 = code_gen.unbox_int(function.get_params()[0]);
+                let val2 = code_gen.unbox_int(function.get_params()[1]);
+                let result = build_int_op(code_gen, op, signed, val1, val2);
+                let sk_result = code_gen.box_sized_int(name, &result);
+                code_gen.builder.build_return(Some(&sk_result));
+                Ok(())
+            }),
+            &[],
+        ));
+    }
+    if signed {
+        methods.push(create_closure_method_generic(
+            name,
+            &format!("-@ -> {}", name),
+            Box::new(move |code_gen, function| {
+                let int = code_gen.unbox_int(function.get_params()[0]);
+                let result = code_gen.builder.build_int_neg(int, "result");
+                let sk_result = code_gen.box_sized_int(name, &result);
+                code_gen.builder.build_return(Some(&sk_result));
+                Ok(())
+            }),
+            &[],
+        ));
+    }
+    methods.push(create_closure_method_generic(
+        name,
+        "to_i() -> Int",
+        Box::new(move |code_gen, function| {
+            let int = code_gen.unbox_int(function.get_params()[0]);
+            let result = if bits == 64 {
+                int
+            } else if signed {
+                code_gen
+                    .builder
+                    .build_int_s_extend(int, code_gen.i64_type, "result")
+            } else {
+                code_gen
+                    .builder
+                    .build_int_z_extend(int, code_gen.i64_type, "result")
+            };
+            let sk_result = code_gen.box_int(&result);
+            code_gen.builder.build_return(Some(&sk_result));
+            Ok(())
+        }),
+        &[],
+    ));
+    methods.push(create_closure_method_generic(
+        name,
+        "to_f() -> Float",
+        Box::new(move |code_gen, function| {
+            let int = code_gen.unbox_int(function.get_params()[0]);
+            let float = if signed {
+                code_gen
+                    .builder
+                    .build_signed_int_to_float(int, code_gen.f64_type, "float")
+            } else {
+                code_gen
+                    .builder
+                    .build_unsigned_int_to_float(int, code_gen.f64_type, "float")
+            };
+            let sk_result = code_gen.box_float(&float);
+            code_gen.builder.build_return(Some(&sk_result));
+            Ok(())
+        }),
+        &[],
+    ));
+    methods
+}
+
+fn int_predicate(op: &str, signed: bool) -> IntPredicate {
+    match (op, signed) {
+        ("==", _) => IntPredicate::EQ,
+        ("!=", _) => IntPredicate::NE,
+        ("<", true) => IntPredicate::SLT,
+        ("<", false) => IntPredicate::ULT,
+        (">", true) => IntPredicate::SGT,
+        (">", false) => IntPredicate::UGT,
+        ("<=", true) => IntPredicate::SLE,
+        ("<=", false) => IntPredicate::ULE,
+        (">=", true) => IntPredicate::SGE,
+        (">=", false) => IntPredicate::UGE,
+        _ => panic!("[BUG] unknown comparison operator: {}", op),
+    }
+}
+
+fn build_int_op<'a>(
+    code_gen: &'a CodeGen,
+    op: &str,
+    signed: bool,
+    val1: IntValue<'a>,
+    val2: IntValue<'a>,
+) -> IntValue<'a> {
+    let builder = &code_gen.builder;
+    match op {
+        "+" => builder.build_int_add(val1, val2, "add"),
+        "-" => builder.build_int_sub(val1, val2, "sub"),
+        "*" => builder.build_int_mul(val1, val2, "mul"),
+        "/" if signed => builder.build_int_signed_div(val1, val2, "div"),
+        "/" => builder.build_int_unsigned_div(val1, val2, "div"),
+        "reminder" if signed => builder.build_int_signed_rem(val1, val2, "rem"),
+        "reminder" => builder.build_int_unsigned_rem(val1, val2, "rem"),
+        "&" => builder.build_and(val1, val2, "and"),
+        "|" => builder.build_or(val1, val2, "or"),
+        "^" => builder.build_xor(val1, val2, "xor"),
+        "<<" => builder.build_left_shift(val1, val2, "lshift"),
+        ">>" => builder.build_right_shift(val1, val2, signed, "rshift"),
+        _ => panic!("[BUG] unknown arithmetic operator: {}", op),
+    }
+}
+
+fn float32_methods() -> Vec<SkMethod> {
+    let mut methods = vec![];
+    for &op in &COMPARISON_OPS {
+        methods.push(create_closure_method_generic(
+            "Float32",
+            &format!("{}(other: Float32) -> Bool", op),
+            Box::new(move |code_gen, function| {
+                let val1 = code_gen.unbox_float(function.get_params()[0]);
+                let val2 = code_gen.unbox_float(function.get_params()[1]);
+                let result =
+                    code_gen
+                        .builder
+                        .build_float_compare(float_predicate(op), val1, val2, "result");
+                let sk_result = code_gen.box_bool(result);
+                code_gen.builder.build_return(Some(&sk_result));
+                Ok(())
+            }),
+            &[],
+        ));
+    }
+    for &op in &FLOAT_ARITHMETIC_OPS {
+        methods.push(create_closure_method_generic(
+            "Float32",
+            &format!("{}(other: Float32) -> Float32", op),
+            Box::new(move |code_gen, function| {
+                let val1 = code_gen.unbox_float(function.get_params()[0]);
+                let val2 = code_gen.unbox_float(function.get_params()[1]);
+                let result = build_float_op(code_gen, op, val1, val2);
+                let sk_result = code_gen.box_float32(&result);
+                code_gen.builder.build_return(Some(&sk_result));
+                Ok(())
+            }),
+            &[],
+        ));
+    }
+    methods.push(create_method(
+        "Float32",
+        "-@ -> Float32",
+        |code_gen, function| {
+            let float = code_gen.unbox_float(function.get_params()[0]);
+            let result = code_gen.builder.build_float_neg(float, "result");
+            let sk_result = code_gen.box_float32(&result);
+            code_gen.builder.build_return(Some(&sk_result));
+            Ok(())
+        },
+    ));
+    methods.push(create_method(
+        "Float32",
+        "to_f() -> Float",
+        |code_gen, function| {
+            let float = code_gen.unbox_float(function.get_params()[0]);
+            let result = code_gen
+                .builder
+                .build_float_ext(float, code_gen.f64_type, "result");
+            let sk_result = code_gen.box_float(&result);
+            code_gen.builder.build_return(Some(&sk_result));
+            Ok(())
+        },
+    ));
+    methods.push(create_method(
+        "Float32",
+        "to_i() -> Int",
+        |code_gen, function| {
+            let float = code_gen.unbox_float(function.get_params()[0]);
+            let int = code_gen
+                .builder
+                .build_float_to_signed_int(float, code_gen.i64_type, "int");
+            let sk_result = code_gen.box_int(&int);
+            code_gen.builder.build_return(Some(&sk_result));
+            Ok(())
+        },
+    ));
+    methods
+}
+
+fn float_predicate(op: &str) -> FloatPredicate {
+    match op {
+        "==" => FloatPredicate::OEQ,
+        "!=" => FloatPredicate::UNE,
+        "<" => FloatPredicate::OLT,
+        ">" => FloatPredicate::OGT,
+        "<=" => FloatPredicate::OLE,
+        ">=" => FloatPredicate::OGE,
+        _ => panic!("[BUG] unknown comparison operator: {}", op),
+    }
+}
+
+fn build_float_op<'a>(
+    code_gen: &'a CodeGen,
+    op: &str,
+    val1: FloatValue<'a>,
+    val2: FloatValue<'a>,
+) -> FloatValue<'a> {
+    let builder = &code_gen.builder;
+    match op {
+        "+" => builder.build_float_add(val1, val2, "add"),
+        "-" => builder.build_float_sub(val1, val2, "sub"),
+        "*" => builder.build_float_mul(val1, val2, "mul"),
+        "/" => builder.build_float_div(val1, val2, "div"),
+        _ => panic!("[BUG] unknown arithmetic operator: {}", op),
+    }
+}
@@ -5,15 +5,56 @@ use crate::ty;
 use std::collections::HashMap;
 
 pub fn create_methods() -> Vec<SkMethod> {
-    vec![create_method(
-        "String",
-        "to_s() -> String",
-        |code_gen, function| {
+    vec![
+        create_method("String", "to_s() -> String", |code_gen, function| {
             let this = function.get_params()[0];
             code_gen.builder.build_return(Some(&this));
             Ok(())
-        },
-    )]
+        }),
+        create_method(
+            "String",
+            "==(other: String) -> Bool",
+            |code_gen, function| {
+                let this = function.get_params()[0];
+                let other = function.get_params()[1];
+                let size1 = code_gen.unbox_int(code_gen.build_ivar_load(this, 1, "@bytesize"));
+                let size2 = code_gen.unbox_int(code_gen.build_ivar_load(other, 1, "@bytesize"));
+                let same_size = code_gen.builder.build_int_compare(
+                    inkwell::IntPredicate::EQ,
+                    size1,
+                    size2,
+                    "same_size",
+                );
+                // Compare no bytes when the sizes differ (memcmp returns 0 then)
+                let n = code_gen.builder.build_select(
+                    same_size,
+                    size1,
+                    code_gen.i64_type.const_int(0, false),
+                    "n",
+                );
+                let ptr1 = code_gen.build_ivar_load(this, 0, "@ptr");
+                let ptr2 = code_gen.build_ivar_load(other, 0, "@ptr");
+                let func = code_gen.module.get_function("memcmp").unwrap();
+                let cmp = code_gen
+                    .builder
+                    .build_call(func, &[ptr1, ptr2, n], "cmp")
+                    .try_as_basic_value()
+                    .left()
+                    .unwrap()
+                    .into_int_value();
+                let same_bytes = code_gen.builder.build_int_compare(
+                    inkwell::IntPredicate::EQ,
+                    cmp,
+                    code_gen.i32_type.const_int(0, false),
+                    "same_bytes",
+                );
+                let result = code_gen.builder.build_and(same_size, same_bytes, "result");
+                let sk_bool = code_gen.box_bool(result);
+                code_gen.builder.build_return(Some(&sk_bool));
+                Ok(())
+            },
+        ),
+    ]
 }
 
 pub fn ivars() -> HashMap<String, SkIVar> {
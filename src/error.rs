@@ -1,3 +1,4 @@
+use crate::location::LocationSpan;
 use backtrace::Backtrace;
 
 #[derive(Debug)]
@@ -6,6 +7,10 @@ pub struct Error {
     pub backtrace: Backtrace,
     pub details: ErrorDetails,
     pub source: Option<Box<dyn std::error::Error>>,
+    /// Where in the source program the error occurred (if known)
+    pub location: Option<LocationSpan>,
+    /// Additional messages shown after the source snippet
+    pub notes: Vec<String>,
 }
 #[derive(Debug)]
 pub enum ErrorDetails {
@@ -27,19 +32,158 @@ pub enum ErrorDetails {
     Bug,
 }
 
+impl ErrorDetails {
+    /// Name of the error kind shown to the user
+    pub fn kind_name(&self) -> &'static str {
+        match self {
+            ErrorDetails::ParseError { .. } => "ParseError",
+            ErrorDetails::SyntaxError => "SyntaxError",
+            ErrorDetails::TypeError => "TypeError",
+            ErrorDetails::NameError => "NameError",
+            ErrorDetails::ProgramError => "ProgramError",
+            ErrorDetails::RunnerError => "RunnerError",
+            ErrorDetails::Bug => "Bug",
+        }
+    }
+}
+
+impl Error {
+    /// Set the location of the error unless already set
+    /// (i.e. the innermost location is kept)
+    pub fn with_location(mut self, locs: &LocationSpan) -> Error {
+        if self.location.is_none() {
+            self.location = Some(locs.clone());
+        }
+        self
+    }
+
+    /// Add a note (eg. "help: ...") to show with the error
+    pub fn with_note(mut self, note: impl Into<String>) -> Error {
+        self.notes.push(note.into());
+        self
+    }
+
+    /// Format the error for the user, like this:
+    ///
+    /// ```text
+    /// TypeError: method `bar' not found on Int
+    ///  --> a.sk:3:5
+    ///   |
+    /// 3 |     1.bar
+    ///   |     ^^^^^
+    ///   = note: ...
+    /// ```
+    pub fn render(&self) -> String {
+        let mut s = format!("{}: {}\n", self.details.kind_name(), self.msg);
+        let mut gutter = String::new();
+        if let Some(locs) = &self.location {
+            let line_no = (locs.begin.line() + 1).to_string();
+            gutter = " ".repeat(line_no.len());
+            s += &format!("{}--> {}\n", gutter, locs);
+            if let Some(file) = &locs.file {
+                if let Some(line) = file.content.lines().nth(locs.begin.line()) {
+                    s += &format!("{} |\n", gutter);
+                    s += &format!("{} | {}\n", line_no, line);
+                    s += &format!("{} | {}\n", gutter, underline(locs, line));
+                }
+            }
+        }
+        for note in &self.notes {
+            s += &format!("{} = {}\n", gutter, note);
+        }
+        if let Some(source) = &self.source {
+            s += &format!("{} = caused by: {}\n", gutter, source);
+        }
+        if let ErrorDetails::Bug = self.details {
+            s += &format!("{:?}\n", self.backtrace);
+        }
+        s
+    }
+}
+
+/// Make the `^^^` line for the first line of `locs`
+fn underline(locs: &LocationSpan, line: &str) -> String {
+    let begin = locs.begin.col();
+    let line_len = line.chars().count();
+    let end = if locs.end.line() == locs.begin.line() {
+        locs.end.col()
+    } else {
+        line_len
+    };
+    let width = std::cmp::max(std::cmp::min(end, line_len).saturating_sub(begin), 1);
+    format!("{}{}", " ".repeat(begin), "^".repeat(width))
+}
+
 impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "{}", self.msg)
+        match &self.location {
+            Some(locs) => write!(f, "{}: {}", locs, self.msg),
+            None => write!(f, "{}", self.msg),
+        }
     }
 }
 impl std::error::Error for Error {}
 
+/// Max number of errors reported at once
+pub const MAX_ERRORS: usize = 20;
+
+/// Errors reported at once (eg. all the type errors in a program)
+#[derive(Debug)]
+pub struct Errors(pub Vec<Error>);
+
+impl Errors {
+    /// Combine the result of a compilation phase and the errors recovered
+    /// from during it
+    pub fn check<T>(result: Result<T, Error>, mut errors: Vec<Error>) -> Result<T, Errors> {
+        match result {
+            Ok(x) if errors.is_empty() => Ok(x),
+            Ok(_) => Err(Errors(errors)),
+            Err(e) => {
+                errors.push(e);
+                Err(Errors(errors))
+            }
+        }
+    }
+
+    /// Format the errors for the user
+    pub fn render(&self) -> String {
+        let mut s = self
+            .0
+            .iter()
+            .map(|e| e.render())
+            .collect::<Vec<_>>()
+            .join("\n");
+        if self.0.len() >= MAX_ERRORS {
+            s += &format!("\nstopped after {} errors\n", self.0.len());
+        } else if self.0.len() > 1 {
+            s += &format!("\nfound {} errors\n", self.0.len());
+        }
+        s
+    }
+}
+
+impl From<Error> for Errors {
+    fn from(err: Error) -> Errors {
+        Errors(vec![err])
+    }
+}
+
+impl std::fmt::Display for Errors {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let msgs = self.0.iter().map(|e| e.to_string()).collect::<Vec<_>>();
+        write!(f, "{}", msgs.join("\n"))
+    }
+}
+impl std::error::Error for Errors {}
+
 pub fn syntax_error(msg: &str) -> Error {
     Error {
         msg: msg.to_string(),
         backtrace: backtrace::Backtrace::new(),
         details: ErrorDetails::SyntaxError,
         source: None,
+        location: None,
+        notes: vec![],
     }
 }
 
@@ -49,6 +193,8 @@ pub fn type_error(msg: &str) -> Error {
         backtrace: backtrace::Backtrace::new(),
         details: ErrorDetails::TypeError,
         source: None,
+        location: None,
+        notes: vec![],
     }
 }
 
@@ -58,6 +204,8 @@ pub fn name_error(msg: &str) -> Error {
         backtrace: backtrace::Backtrace::new(),
         details: ErrorDetails::NameError,
         source: None,
+        location: None,
+        notes: vec![],
     }
 }
 
@@ -67,6 +215,8 @@ pub fn program_error(msg: &str) -> Error {
         backtrace: backtrace::Backtrace::new(),
         details: ErrorDetails::ProgramError,
         source: None,
+        location: None,
+        notes: vec![],
     }
 }
 
@@ -76,6 +226,8 @@ pub fn runner_error(msg: impl Into<String>, source: impl std::error::Error + 'st
         backtrace: backtrace::Backtrace::new(),
         details: ErrorDetails::RunnerError,
         source: Some(Box::new(source)),
+        location: None,
+        notes: vec![],
     }
 }
 
@@ -85,5 +237,7 @@ pub fn plain_runner_error(msg: impl Into<String>) -> Error {
         backtrace: backtrace::Backtrace::new(),
         details: ErrorDetails::RunnerError,
         source: None,
+        location: None,
+        notes: vec![],
     }
 }
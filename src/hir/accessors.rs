@@ -1,6 +1,7 @@
 use crate::code_gen::CodeGen;
 use crate::hir::hir_maker::HirMaker;
 use crate::hir::*;
+use std::collections::HashSet;
 
 impl HirMaker {
     /// Define getters and setters (unless there is a method of the same name)
@@ -14,22 +15,31 @@ impl HirMaker {
             .iter()
             .filter_map(|def| {
                 if let ast::Definition::InstanceMethodDefinition { sig, .. } = def {
-                    Some(&sig.name.0)
+                    Some(sig.name.clone())
                 } else {
                     None
                 }
             })
-            .collect::<Vec<_>>();
+            .collect::<HashSet<_>>();
+        self.define_accessors_except(clsname, ivars, &method_names);
+    }
+
+    /// Define getters and setters except the ones in `method_names`
+    pub(super) fn define_accessors_except(
+        &mut self,
+        clsname: &ClassFullname,
+        ivars: SkIVars,
+        method_names: &HashSet<MethodFirstname>,
+    ) {
         for (name, ivar) in ivars {
-            if !method_names.iter().any(|x| ***x == name) {
+            if !method_names.contains(&method_firstname(&name)) {
                 let getter = create_getter(&clsname, &ivar);
                 let sig = getter.signature.clone();
                 self.method_dict.add_method(&clsname, getter);
                 self.class_dict.add_method(&clsname, sig);
             }
 
-            let setter_name = format!("{}=", name);
-            if !method_names.iter().any(|x| ***x == setter_name) {
+            if !method_names.contains(&method_firstname(&format!("{}=", name))) {
                 let setter = create_setter(&clsname, &ivar);
                 let sig = setter.signature.clone();
                 self.method_dict.add_method(&clsname, setter);
@@ -44,6 +54,7 @@ fn create_getter(clsname: &ClassFullname, ivar: &SkIVar) -> SkMethod {
         fullname: method_fullname(clsname, &ivar.name),
         ret_ty: ivar.ty.clone(),
         params: vec![],
+        typarams: vec![],
     };
     let name = ivar.name.clone(); // Clone to embed into the closure
     let idx = ivar.idx;
@@ -71,6 +82,7 @@ fn create_setter(clsname: &ClassFullname, ivar: &SkIVar) -> SkMethod {
             name: ivar.name.clone(),
             ty: ivar.ty.clone(),
         }],
+        typarams: vec![],
     };
     let idx = ivar.idx;
     let getter_body = move |code_gen: &CodeGen, function: &inkwell::values::FunctionValue| {
@@ -15,10 +15,7 @@ impl ClassDict {
         classname: &ClassFullname,
         own_ivars: HashMap<String, SkIVar>,
     ) -> Result<(), Error> {
-        let super_ivars = match self.get_superclass(&classname) {
-            Some(super_cls) => super_cls.ivars.clone(),
-            None => HashMap::new(),
-        };
+        let super_ivars = self.inherited_ivars(&classname);
         let class = self.get_class_mut(&classname, "ClassDict::define_ivars");
         class.ivars = super_ivars;
         own_ivars.into_iter().for_each(|(k, v)| {
@@ -41,36 +38,48 @@ impl ClassDict {
             .insert(sig.fullname.first_name.clone(), sig);
     }
 
+    /// Register classes of corelib (or the precompiled library)
     pub fn index_corelib(&mut self, corelib: HashMap<ClassFullname, SkClass>) {
-        corelib.into_iter().for_each(|(_, c)| {
-            self.add_class(SkClass {
-                fullname: c.fullname,
-                typarams: vec![],
-                superclass_fullname: c.superclass_fullname,
-                instance_ty: c.instance_ty,
-                ivars: c.ivars,
-                method_sigs: c.method_sigs,
-                const_is_obj: c.const_is_obj,
-            })
-        });
+        corelib.into_iter().for_each(|(_, c)| self.add_class(c));
     }
 
     pub fn index_program(&mut self, toplevel_defs: &[&ast::Definition]) -> Result<(), Error> {
+        // Index modules first so that a class can include a module defined later
+        for def in toplevel_defs {
+            if let ast::Definition::ModuleDefinition { name, defs, locs } = def {
+                self.index_module(&name.add_namespace(""), &defs)
+                    .map_err(|e| e.with_location(locs))?;
+            }
+        }
         toplevel_defs.iter().try_for_each(|def| match def {
             ast::Definition::ClassDefinition {
                 name,
                 typarams,
                 super_name,
                 defs,
+                locs,
             } => {
-                self.index_class(&name.add_namespace(""), &typarams, &super_name, &defs)?;
+                self.index_class(&name.add_namespace(""), &typarams, &super_name, &defs)
+                    .map_err(|e| e.with_location(locs))?;
                 Ok(())
             }
-            ast::Definition::ConstDefinition { .. } => Ok(()),
-            _ => Err(error::syntax_error(&format!(
-                "must not be toplevel: {:?}",
-                def
-            ))),
+            ast::Definition::EnumDefinition {
+                name,
+                cases,
+                defs,
+                locs,
+            } => {
+                self.index_enum(&name.add_namespace(""), &cases, &defs)
+                    .map_err(|e| e.with_location(locs))?;
+                Ok(())
+            }
+            ast::Definition::ModuleDefinition { .. } | ast::Definition::ConstDefinition { .. } => {
+                Ok(())
+            }
+            _ => Err(
+                error::syntax_error(&format!("must not be toplevel: {:?}", def))
+                    .with_location(def.locs()),
+            ),
         })
     }
 
@@ -87,6 +96,8 @@ impl ClassDict {
         let metaclass_fullname = class_ty.fullname.clone();
         let mut instance_methods = HashMap::new();
         let mut class_methods = HashMap::new();
+        let mut includes = vec![];
+        let mut extends = vec![];
         let new_sig = signature::signature_of_new(
             &metaclass_fullname,
             self.initializer_params(&super_name.instance_ty(), &defs),
@@ -109,9 +120,35 @@ impl ClassDict {
                     typarams,
                     super_name,
                     defs,
+                    locs,
+                } => {
+                    let full = name.add_namespace(&fullname.0);
+                    self.index_class(&full, &typarams, &super_name, &defs)
+                        .map_err(|e| e.with_location(locs))?;
+                }
+                ast::Definition::EnumDefinition {
+                    name,
+                    cases,
+                    defs,
+                    locs,
                 } => {
                     let full = name.add_namespace(&fullname.0);
-                    self.index_class(&full, &typarams, &super_name, &defs)?;
+                    self.index_enum(&full, &cases, &defs)
+                        .map_err(|e| e.with_location(locs))?;
+                }
+                ast::Definition::ModuleDefinition { locs, .. } => {
+                    return Err(error::syntax_error("module must be defined at toplevel")
+                        .with_location(locs))
+                }
+                ast::Definition::IncludeDefinition { module_name, locs } => {
+                    self.check_module(module_name)
+                        .map_err(|e| e.with_location(locs))?;
+                    includes.push(module_name.clone());
+                }
+                ast::Definition::ExtendDefinition { module_name, locs } => {
+                    self.check_module(module_name)
+                        .map_err(|e| e.with_location(locs))?;
+                    extends.push(module_name.clone());
                 }
             }
         }
@@ -119,12 +156,20 @@ impl ClassDict {
         match self.sk_classes.get_mut(&fullname) {
             Some(class) => {
                 // Merge methods to existing class (Class is reopened)
+                if class.is_module {
+                    return Err(error::program_error(&format!(
+                        "`{}' is a module, not a class",
+                        fullname
+                    )));
+                }
                 class.method_sigs.extend(instance_methods);
+                add_includes(&mut class.includes, includes);
                 let metaclass = self
                     .sk_classes
                     .get_mut(&metaclass_fullname)
                     .expect("[BUG] Only class is indexed");
                 metaclass.method_sigs.extend(class_methods);
+                add_includes(&mut metaclass.includes, extends);
                 // Add `.new` to the metaclass
                 if !metaclass.method_sigs.contains_key(&method_firstname("new")) {
                     metaclass
@@ -135,20 +180,172 @@ impl ClassDict {
             None => {
                 // Add `.new` to the metaclass
                 class_methods.insert(new_sig.fullname.first_name.clone(), new_sig);
-                if !self.class_exists(&super_name.0) {
-                    return Err(error::name_error(&format!(
-                        "unknown superclass: {:?}",
-                        super_name
+                match self.find_class(&super_name) {
+                    None => {
+                        return Err(error::name_error(&format!(
+                            "unknown superclass: {:?}",
+                            super_name
+                        )))
+                    }
+                    Some(super_class) if super_class.is_module => {
+                        return Err(error::program_error(&format!(
+                            "cannot inherit module `{}' (Hint: use `include')",
+                            super_name
+                        )))
+                    }
+                    Some(super_class) if super_class.enum_cases.is_some() => {
+                        return Err(error::program_error(&format!(
+                            "cannot inherit enum `{}'",
+                            super_name
+                        )))
+                    }
+                    Some(super_class) if self.is_enum_case(super_class) => {
+                        return Err(error::program_error(&format!(
+                            "cannot inherit enum case `{}'",
+                            super_name
+                        )))
+                    }
+                    _ => (),
+                }
+                self.add_class(SkClass {
+                    fullname: fullname.clone(),
+                    typarams: typarams
+                        .iter()
+                        .map(|name| TyParam { name: name.clone() })
+                        .collect(),
+                    superclass_fullname: Some(super_name.clone()),
+                    instance_ty,
+                    ivars: HashMap::new(),
+                    method_sigs: instance_methods,
+                    is_module: false,
+                    includes: vec![],
+                    const_is_obj: false,
+                    enum_cases: None,
+                });
+                self.add_class(SkClass {
+                    fullname: metaclass_fullname.clone(),
+                    typarams: vec![],
+                    superclass_fullname: Some(class_fullname("Class")),
+                    instance_ty: class_ty,
+                    ivars: HashMap::new(),
+                    method_sigs: class_methods,
+                    is_module: false,
+                    includes: vec![],
+                    const_is_obj: false,
+                    enum_cases: None,
+                });
+                let class = self.get_class_mut(fullname, "ClassDict::index_class");
+                add_includes(&mut class.includes, includes);
+                let metaclass = self.get_class_mut(&metaclass_fullname, "ClassDict::index_class");
+                add_includes(&mut metaclass.includes, extends);
+            }
+        }
+        Ok(())
+    }
+
+    /// Index an enum and its cases (each case is a subclass of the enum)
+    fn index_enum(
+        &mut self,
+        fullname: &ClassFullname,
+        cases: &[ast::EnumCase],
+        defs: &[ast::Definition],
+    ) -> Result<(), Error> {
+        if self.sk_classes.contains_key(fullname) {
+            return Err(error::program_error(&format!(
+                "`{}' is already defined",
+                fullname
+            )));
+        }
+        self.index_class(fullname, &[], &class_fullname("Object"), defs)?;
+        // Only the cases can be instantiated
+        self.get_class_mut(&fullname.meta_name(), "ClassDict::index_enum")
+            .method_sigs
+            .remove(&method_firstname("new"));
+
+        let mut case_names = vec![];
+        for case in cases {
+            let case_fullname = case.name.add_namespace(&fullname.0);
+            self.index_class(&case_fullname, &[], fullname, &case.defs())
+                .map_err(|e| e.with_location(&case.locs))?;
+            case_names.push(case_fullname);
+        }
+        self.get_class_mut(fullname, "ClassDict::index_enum")
+            .enum_cases = Some(case_names);
+        Ok(())
+    }
+
+    /// Returns true if `sk_class` is a case of an enum
+    fn is_enum_case(&self, sk_class: &SkClass) -> bool {
+        sk_class
+            .superclass_fullname
+            .as_ref()
+            .and_then(|super_name| self.find_class(super_name))
+            .and_then(|super_class| super_class.enum_cases.as_ref())
+            .map_or(false, |cases| cases.contains(&sk_class.fullname))
+    }
+
+    fn index_module(
+        &mut self,
+        fullname: &ClassFullname,
+        defs: &[ast::Definition],
+    ) -> Result<(), Error> {
+        let instance_ty = ty::raw(&fullname.0);
+        let class_ty = instance_ty.meta_ty();
+        let metaclass_fullname = class_ty.fullname.clone();
+        let mut instance_methods = HashMap::new();
+        let mut class_methods = HashMap::new();
+
+        for def in defs {
+            match def {
+                ast::Definition::InstanceMethodDefinition { sig, locs, .. } => {
+                    if def.is_initializer() && !sig.params.is_empty() {
+                        return Err(error::program_error(
+                            "initializer of a module cannot take parameters",
+                        )
+                        .with_location(locs));
+                    }
+                    let hir_sig = signature::create_signature(&fullname, sig, &[]);
+                    instance_methods.insert(sig.name.clone(), hir_sig);
+                }
+                ast::Definition::ClassMethodDefinition { sig, .. } => {
+                    let hir_sig = signature::create_signature(&metaclass_fullname, sig, &[]);
+                    class_methods.insert(sig.name.clone(), hir_sig);
+                }
+                ast::Definition::ConstDefinition { .. } => (),
+                _ => {
+                    return Err(
+                        error::syntax_error(&format!("must not be in a module: {:?}", def))
+                            .with_location(def.locs()),
+                    )
+                }
+            }
+        }
+
+        match self.sk_classes.get_mut(&fullname) {
+            Some(module) => {
+                // Module is reopened
+                if !module.is_module {
+                    return Err(error::program_error(&format!(
+                        "`{}' is a class, not a module",
+                        fullname
                     )));
                 }
+                module.method_sigs.extend(instance_methods);
+                let metaclass = self.get_class_mut(&metaclass_fullname, "ClassDict::index_module");
+                metaclass.method_sigs.extend(class_methods);
+            }
+            None => {
                 self.add_class(SkClass {
                     fullname: fullname.clone(),
                     typarams: vec![],
-                    superclass_fullname: Some(super_name.clone()),
+                    superclass_fullname: Some(class_fullname("Object")),
                     instance_ty,
                     ivars: HashMap::new(),
                     method_sigs: instance_methods,
+                    is_module: true,
+                    includes: vec![],
                     const_is_obj: false,
+                    enum_cases: None,
                 });
                 self.add_class(SkClass {
                     fullname: metaclass_fullname,
@@ -157,10 +354,34 @@ impl ClassDict {
                     instance_ty: class_ty,
                     ivars: HashMap::new(),
                     method_sigs: class_methods,
+                    is_module: false,
+                    includes: vec![],
                     const_is_obj: false,
+                    enum_cases: None,
                 });
             }
         }
         Ok(())
     }
+
+    /// Check that `name` is a module
+    fn check_module(&self, name: &ClassFullname) -> Result<(), Error> {
+        match self.find_class(name) {
+            Some(sk_class) if sk_class.is_module => Ok(()),
+            Some(_) => Err(error::program_error(&format!(
+                "`{}' is a class, not a module",
+                name
+            ))),
+            None => Err(error::name_error(&format!("unknown module: {}", name))),
+        }
+    }
+}
+
+/// Append modules to the list of included modules, skipping the ones already included
+fn add_includes(includes: &mut Vec<ClassFullname>, modules: Vec<ClassFullname>) {
+    for module in modules {
+        if !includes.contains(&module) {
+            includes.push(module);
+        }
+    }
 }
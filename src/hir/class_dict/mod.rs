@@ -36,7 +36,7 @@ impl ClassDict {
         {
             // Has explicit initializer definition
             // TODO: Support typarams in initializer params
-            hir::signature::convert_params(&sig.params, &[])
+            hir::signature::convert_params(&sig.params, &[], &[])
         } else {
             // Inherit #initialize from superclass
             let (sig, _found_cls) = self
@@ -1,9 +1,11 @@
 use crate::error;
 use crate::error::*;
 use crate::hir::class_dict::class_dict::ClassDict;
+use crate::hir::did_you_mean::did_you_mean;
 use crate::hir::*;
 use crate::names::*;
 use crate::ty::*;
+use std::collections::HashMap;
 
 impl ClassDict {
     /// Find a method from class name and first name
@@ -34,7 +36,10 @@ impl ClassDict {
                 .expect("[BUG] base_cls not found")
                 .instance_ty;
             let (base_sig, found_cls) = self.lookup_method_(base_cls, base_cls, method_name)?;
-            Ok((base_sig.specialize(&type_args), found_cls))
+            Ok((base_sig.specialize(&type_args, &[]), found_cls))
+        } else if let TyBody::TyParamRef { .. } = &class.body {
+            // Only the methods of the upper bound are available
+            self.lookup_method_(class, &ty::raw("Object"), method_name)
         } else {
             self.lookup_method_(class, class, method_name)
         }
@@ -48,6 +53,8 @@ impl ClassDict {
     ) -> Result<(MethodSignature, ClassFullname), Error> {
         if let Some(sig) = self.find_method(&class.fullname, method_name) {
             Ok((sig.clone(), class.fullname.clone()))
+        } else if let Some(sig) = self.find_mixin_method(&class.fullname, method_name) {
+            Ok((sig, class.fullname.clone()))
         } else {
             // Look up in superclass
             let sk_class = self.find_class(&class.fullname).unwrap_or_else(|| {
@@ -62,13 +69,96 @@ impl ClassDict {
                 self.lookup_method_(receiver_class, &super_class, method_name)
             } else {
                 Err(error::program_error(&format!(
-                    "method {:?} not found on {:?}",
-                    method_name, receiver_class.fullname
+                    "method `{}' not found on {}{}",
+                    method_name,
+                    receiver_class.fullname,
+                    did_you_mean(&method_name.0, self.method_names(&receiver_class.fullname))
                 )))
             }
         }
     }
 
+    /// Find a method from the modules included into the class (not including
+    /// the ones of the superclass.) The returned signature is the one of the
+    /// copy of the method compiled for `classname`
+    pub fn find_mixin_method(
+        &self,
+        classname: &ClassFullname,
+        method_name: &MethodFirstname,
+    ) -> Option<MethodSignature> {
+        // Module initializers are not inherited as `initialize`
+        if method_name.0 == "initialize" {
+            return None;
+        }
+        let sk_class = self.find_class(classname)?;
+        // The module included last wins
+        sk_class.includes.iter().rev().find_map(|module_name| {
+            self.find_method(module_name, method_name)
+                .map(|sig| sig.with_owner(classname))
+        })
+    }
+
+    /// Return the ivars which the class gets from its superclass and the
+    /// modules it includes
+    pub fn inherited_ivars(&self, classname: &ClassFullname) -> HashMap<String, SkIVar> {
+        let mut ivars = match self.get_superclass(classname) {
+            Some(super_cls) => super_cls.ivars.clone(),
+            None => HashMap::new(),
+        };
+        let sk_class = self.get_class(classname, "ClassDict::inherited_ivars");
+        for module_name in &sk_class.includes {
+            let module = self.get_class(module_name, "ClassDict::inherited_ivars");
+            let mut module_ivars = module.ivars.values().collect::<Vec<_>>();
+            module_ivars.sort_by_key(|ivar| ivar.idx);
+            for ivar in module_ivars {
+                if !ivars.contains_key(&ivar.name) {
+                    let idx = ivars.len();
+                    ivars.insert(
+                        ivar.name.clone(),
+                        SkIVar {
+                            idx,
+                            ..ivar.clone()
+                        },
+                    );
+                }
+            }
+        }
+        ivars
+    }
+
+    /// Return the modules included into the class and its ancestors
+    pub fn included_modules(&self, classname: &ClassFullname) -> Vec<&ClassFullname> {
+        let mut modules = vec![];
+        let mut cls = self.find_class(classname);
+        while let Some(sk_class) = cls {
+            modules.extend(sk_class.includes.iter().rev());
+            cls = sk_class
+                .superclass_fullname
+                .as_ref()
+                .and_then(|super_name| self.find_class(super_name));
+        }
+        modules
+    }
+
+    /// Return the names of the methods of the class (including inherited ones)
+    fn method_names(&self, classname: &ClassFullname) -> Vec<&str> {
+        let mut names = vec![];
+        let mut cls = self.find_class(classname);
+        while let Some(sk_class) = cls {
+            names.extend(sk_class.method_sigs.keys().map(|name| name.0.as_str()));
+            for module_name in &sk_class.includes {
+                if let Some(module) = self.find_class(module_name) {
+                    names.extend(module.method_sigs.keys().map(|name| name.0.as_str()));
+                }
+            }
+            cls = sk_class
+                .superclass_fullname
+                .as_ref()
+                .and_then(|super_name| self.find_class(super_name));
+        }
+        names
+    }
+
     /// Find a class
     pub fn find_class(&self, class_fullname: &ClassFullname) -> Option<&SkClass> {
         self.sk_classes.get(class_fullname)
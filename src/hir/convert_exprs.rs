@@ -1,8 +1,10 @@
 use crate::ast::*;
 use crate::error;
 use crate::error::Error;
+use crate::hir::did_you_mean::did_you_mean;
 use crate::hir::hir_maker::HirMaker;
 use crate::hir::hir_maker_context::*;
+use crate::hir::maybe::maybe_item_ty;
 use crate::hir::*;
 use crate::parser::token::Token;
 use crate::type_checking;
@@ -12,15 +14,46 @@ impl HirMaker {
         &mut self,
         exprs: &[AstExpression],
     ) -> Result<HirExpressions, Error> {
+        // Continue converting on error to report more errors at once
         let hir_exprs = exprs
             .iter()
-            .map(|expr| self.convert_expr(expr))
+            .map(|expr| self.convert_expr(expr).or_else(|e| self.recover(e)))
             .collect::<Result<Vec<_>, _>>()?;
 
         Ok(HirExpressions::new(hir_exprs))
     }
 
     pub(super) fn convert_expr(&mut self, expr: &AstExpression) -> Result<HirExpression, Error> {
+        let mut hir = self
+            .convert_expr_(expr)
+            .map_err(|e| e.with_location(&expr.locs))?;
+        hir.locs = expr.locs.clone();
+        Ok(hir)
+    }
+
+    /// Like `convert_expr` but the type of the value is known in advance.
+    /// Used to infer the parameter types of a block
+    pub(super) fn convert_expr_with_expected(
+        &mut self,
+        expr: &AstExpression,
+        expected: Option<&TermTy>,
+    ) -> Result<HirExpression, Error> {
+        let (params, exprs, is_fn) = match &expr.body {
+            AstExpressionBody::LambdaExpr {
+                params,
+                exprs,
+                is_fn,
+            } => (params, exprs, *is_fn),
+            _ => return self.convert_expr(expr),
+        };
+        let mut hir = self
+            .convert_lambda_expr(params, exprs, is_fn, expected)
+            .map_err(|e| e.with_location(&expr.locs))?;
+        hir.locs = expr.locs.clone();
+        Ok(hir)
+    }
+
+    fn convert_expr_(&mut self, expr: &AstExpression) -> Result<HirExpression, Error> {
         match &expr.body {
             AstExpressionBody::LogicalNot { expr } => self.convert_logical_not(expr),
             AstExpressionBody::LogicalAnd { left, right } => self.convert_logical_and(left, right),
@@ -31,17 +64,45 @@ impl HirMaker {
                 else_exprs,
             } => self.convert_if_expr(cond_expr, then_exprs, else_exprs),
 
+            AstExpressionBody::IfLet {
+                name,
+                expr,
+                then_exprs,
+                else_exprs,
+            } => self.convert_if_let_expr(name, expr, then_exprs, else_exprs),
+
             AstExpressionBody::While {
                 cond_expr,
                 body_exprs,
-            } => self.convert_while_expr(cond_expr, body_exprs),
+                label,
+            } => self.convert_while_expr(cond_expr, body_exprs, label),
 
-            AstExpressionBody::Break => self.convert_break_expr(),
+            AstExpressionBody::Loop { body_exprs, label } => {
+                self.convert_loop_expr(body_exprs, label)
+            }
 
-            AstExpressionBody::LVarAssign { name, rhs, is_var } => {
-                self.convert_lvar_assign(name, &*rhs, is_var)
+            AstExpressionBody::Break { label, arg } => self.convert_break_expr(label, arg),
+            AstExpressionBody::Next { label } => self.convert_next_expr(label),
+            AstExpressionBody::Return { arg } => self.convert_return_expr(arg),
+
+            AstExpressionBody::Begin {
+                body_exprs,
+                rescue_var,
+                rescue_exprs,
+                ensure_exprs,
+            } => self.convert_begin_expr(body_exprs, rescue_var, rescue_exprs, ensure_exprs),
+
+            AstExpressionBody::Match { cond_expr, clauses } => {
+                self.convert_match_expr(cond_expr, clauses)
             }
 
+            AstExpressionBody::LVarAssign {
+                name,
+                rhs,
+                is_var,
+                typ,
+            } => self.convert_lvar_assign(name, &*rhs, is_var, typ),
+
             AstExpressionBody::IVarAssign { name, rhs, is_var } => {
                 self.convert_ivar_assign(name, &*rhs, is_var)
             }
@@ -50,16 +111,30 @@ impl HirMaker {
                 self.convert_const_assign(names, &*rhs)
             }
 
+            AstExpressionBody::OpAssign { lhs, op, rhs } => self.convert_op_assign(lhs, op, rhs),
+
             AstExpressionBody::MethodCall {
                 receiver_expr,
                 method_name,
+                type_args,
                 arg_exprs,
                 ..
-            } => self.convert_method_call(receiver_expr, method_name, arg_exprs),
+            } => self.convert_method_call(receiver_expr, method_name, type_args, arg_exprs),
 
-            AstExpressionBody::LambdaExpr { params, exprs } => {
-                self.convert_lambda_expr(params, exprs)
-            }
+            AstExpressionBody::SafeMethodCall {
+                receiver_expr,
+                method_name,
+                type_args,
+                arg_exprs,
+            } => self.convert_safe_method_call(receiver_expr, method_name, type_args, arg_exprs),
+
+            AstExpressionBody::SuperCall { arg_exprs } => self.convert_super_call(arg_exprs),
+
+            AstExpressionBody::LambdaExpr {
+                params,
+                exprs,
+                is_fn,
+            } => self.convert_lambda_expr(params, exprs, *is_fn, None),
 
             AstExpressionBody::BareName(name) => self.convert_bare_name(name),
 
@@ -73,10 +148,15 @@ impl HirMaker {
 
             AstExpressionBody::FloatLiteral { value } => Ok(Hir::float_literal(*value)),
 
-            AstExpressionBody::DecimalLiteral { value } => Ok(Hir::decimal_literal(*value)),
+            AstExpressionBody::DecimalLiteral { value } => {
+                convert_decimal_literal(*value, i64::MAX as u64)
+            }
 
             AstExpressionBody::StringLiteral { content } => self.convert_string_literal(content),
-            //x => panic!("TODO: {:?}", x)
+
+            AstExpressionBody::StringWithInterpolation { parts } => {
+                self.convert_string_with_interpolation(parts)
+            }
         }
     }
 
@@ -104,6 +184,10 @@ impl HirMaker {
         right: &AstExpression,
     ) -> Result<HirExpression, Error> {
         let left_hir = self.convert_expr(left)?;
+        // `maybe || default`
+        if maybe_item_ty(&left_hir.ty).is_some() {
+            return self.convert_maybe_or(left_hir, right);
+        }
         let right_hir = self.convert_expr(right)?;
         type_checking::check_logical_operator_ty(&left_hir.ty, "lhs of logical or")?;
         type_checking::check_logical_operator_ty(&right_hir.ty, "rhs of logical or")?;
@@ -124,29 +208,249 @@ impl HirMaker {
             Some(exprs) => Some(self.convert_exprs(exprs)?),
             None => None,
         };
-        // TODO: then and else must have conpatible type
-        Ok(Hir::if_expression(
-            then_hirs.ty.clone(),
-            cond_hir,
-            then_hirs,
-            else_hirs,
-        ))
+        let if_ty = self.if_expr_ty(&then_hirs, &else_hirs);
+        Ok(Hir::if_expression(if_ty, cond_hir, then_hirs, else_hirs))
+    }
+
+    /// Return the type of an `if` expression
+    pub(super) fn if_expr_ty(
+        &self,
+        then_hirs: &HirExpressions,
+        else_hirs: &Option<HirExpressions>,
+    ) -> TermTy {
+        match else_hirs {
+            // eg. `if x then return 1 else 2 end`
+            Some(hirs) if then_hirs.ty.is_never_type() => hirs.ty.clone(),
+            // eg. `if x then return end`
+            None if then_hirs.ty.is_never_type() => ty::raw("Void"),
+            // eg. `if x then Some(1) else None end`
+            Some(hirs)
+                if !hirs.ty.is_never_type()
+                    && !hirs.ty.is_void_type()
+                    && !then_hirs.ty.is_void_type() =>
+            {
+                self.nearest_common_ancestor_type(&then_hirs.ty, &hirs.ty)
+            }
+            _ => then_hirs.ty.clone(),
+        }
     }
 
     fn convert_while_expr(
         &mut self,
         cond_expr: &AstExpression,
         body_exprs: &[AstExpression],
+        label: &Option<String>,
     ) -> Result<HirExpression, Error> {
         let cond_hir = self.convert_expr(cond_expr)?;
         type_checking::check_condition_ty(&cond_hir.ty, "while")?;
 
+        self.ctx_mut().loops.push(CtxLoop {
+            label: label.clone(),
+            break_tys: None,
+        });
+        let result = self.convert_exprs(body_exprs);
+        self.ctx_mut().loops.pop();
+        let body_hirs = result?;
+        Ok(Hir::while_expression(cond_hir, body_hirs, label.clone()))
+    }
+
+    fn convert_loop_expr(
+        &mut self,
+        body_exprs: &[AstExpression],
+        label: &Option<String>,
+    ) -> Result<HirExpression, Error> {
+        self.ctx_mut().loops.push(CtxLoop {
+            label: label.clone(),
+            break_tys: Some(vec![]),
+        });
+        let result = self.convert_exprs(body_exprs);
+        let ctx_loop = self.ctx_mut().loops.pop().unwrap();
+        let body_hirs = result?;
+        // The type of a `loop` is the common ancestor of the values given to
+        // `break` (Never if there is no `break`)
+        let ty = ctx_loop
+            .break_tys
+            .unwrap()
+            .iter()
+            .fold(None, |acc: Option<TermTy>, ty| match acc {
+                Some(t) => Some(self.nearest_common_ancestor_type(&t, ty)),
+                None => Some(ty.clone()),
+            })
+            .unwrap_or_else(|| ty::raw("Never"));
+        Ok(Hir::loop_expression(ty, body_hirs, label.clone()))
+    }
+
+    fn convert_break_expr(
+        &mut self,
+        label: &Option<String>,
+        arg: &Option<Box<AstExpression>>,
+    ) -> Result<HirExpression, Error> {
+        let arg_hir = match arg {
+            Some(expr) => Some(self.convert_expr(expr)?),
+            None => None,
+        };
+        let ctx_loop = self.find_loop(label, "break")?;
+        let arg_hir = match &mut ctx_loop.break_tys {
+            Some(tys) => {
+                let hir = arg_hir
+                    .unwrap_or_else(|| Hir::const_ref(ty::raw("Void"), const_fullname("::Void")));
+                tys.push(hir.ty.clone());
+                Some(hir)
+            }
+            None => {
+                if arg_hir.is_some() {
+                    return Err(error::program_error(
+                        "`break' with a value is only allowed in `loop'",
+                    ));
+                }
+                None
+            }
+        };
+        Ok(Hir::break_expression(label.clone(), arg_hir))
+    }
+
+    fn convert_next_expr(&mut self, label: &Option<String>) -> Result<HirExpression, Error> {
+        self.find_loop(label, "next")?;
+        Ok(Hir::next_expression(label.clone()))
+    }
+
+    /// Return the loop which `break` or `next` jumps out of
+    fn find_loop(&mut self, label: &Option<String>, keyword: &str) -> Result<&mut CtxLoop, Error> {
+        let loops = &mut self.ctx_mut().loops;
+        let found = match label {
+            Some(name) => loops
+                .iter_mut()
+                .rev()
+                .find(|l| l.label.as_ref() == Some(name)),
+            None => loops.last_mut(),
+        };
+        match (found, label) {
+            (Some(ctx_loop), _) => Ok(ctx_loop),
+            (None, Some(name)) => Err(error::program_error(&format!(
+                "loop labeled `'{}' not found",
+                name
+            ))),
+            (None, None) => Err(error::program_error(&format!(
+                "`{}' out of a loop",
+                keyword
+            ))),
+        }
+    }
+
+    fn convert_return_expr(
+        &mut self,
+        arg: &Option<Box<AstExpression>>,
+    ) -> Result<HirExpression, Error> {
+        let arg_hir = match arg {
+            Some(expr) => self.convert_expr(expr)?,
+            None => Hir::const_ref(ty::raw("Void"), const_fullname("::Void")),
+        };
+        let ctx = self.ctx();
+        match ctx.kind {
+            CtxKind::Method => {
+                let sig = ctx.method_sig.as_ref().unwrap();
+                type_checking::check_return_value(&self.class_dict, sig, &arg_hir.ty)?;
+            }
+            // Checked in convert_lambda_expr because the result type of a
+            // lambda is not known yet
+            CtxKind::Lambda => self.ctx_mut().return_tys.push(arg_hir.ty.clone()),
+            _ => {
+                return Err(error::program_error(
+                    "`return' must be used in a method or a lambda",
+                ))
+            }
+        }
+        Ok(Hir::return_expression(arg_hir))
+    }
+
+    fn convert_begin_expr(
+        &mut self,
+        body_exprs: &[AstExpression],
+        rescue_var: &Option<String>,
+        rescue_exprs: &Option<Vec<AstExpression>>,
+        ensure_exprs: &Option<Vec<AstExpression>>,
+    ) -> Result<HirExpression, Error> {
         let body_hirs = self.convert_exprs(body_exprs)?;
-        Ok(Hir::while_expression(cond_hir, body_hirs))
+        if let Some(name) = rescue_var {
+            self.declare_readonly_lvar(name, ty::raw("Exception"))?;
+        }
+        let rescue_hirs = match rescue_exprs {
+            Some(exprs) => Some(self.convert_exprs(exprs)?),
+            None => None,
+        };
+        let ensure_hirs = match ensure_exprs {
+            Some(exprs) => Some(self.convert_exprs(exprs)?),
+            None => None,
+        };
+        // The value of `ensure` is discarded
+        let ty = match &rescue_hirs {
+            None => body_hirs.ty.clone(),
+            Some(hirs) if body_hirs.ty.is_never_type() => hirs.ty.clone(),
+            Some(hirs) if hirs.ty.is_never_type() => body_hirs.ty.clone(),
+            Some(hirs) if body_hirs.ty.is_void_type() || hirs.ty.is_void_type() => ty::raw("Void"),
+            Some(hirs) => self.nearest_common_ancestor_type(&body_hirs.ty, &hirs.ty),
+        };
+        Ok(Hir::begin_expression(
+            ty,
+            body_hirs,
+            rescue_var.clone(),
+            rescue_hirs,
+            ensure_hirs,
+        ))
+    }
+
+    /// Declare the lvar bound by `rescue => e`
+    pub(super) fn declare_readonly_lvar(&mut self, name: &str, ty: TermTy) -> Result<(), Error> {
+        let ctx = self.ctx_mut();
+        match ctx.lvars.get(name) {
+            // eg. `rescue => e` appears twice in a method
+            Some(lvar) if lvar.readonly && lvar.ty == ty => Ok(()),
+            Some(_) => Err(error::program_error(&format!(
+                "variable `{}' already exists",
+                name
+            ))),
+            None => {
+                ctx.lvars.insert(
+                    name.to_string(),
+                    CtxLVar {
+                        name: name.to_string(),
+                        ty,
+                        readonly: true,
+                        has_error: false,
+                    },
+                );
+                Ok(())
+            }
+        }
     }
 
-    fn convert_break_expr(&mut self) -> Result<HirExpression, Error> {
-        Ok(Hir::break_expression())
+    /// Declare the lvar bound by a pattern of `match` or `if let`.
+    /// It is visible until `undeclare_scoped_lvar` is called and stored in a
+    /// gensym'd lvar, so that another clause may bind the same name with
+    /// another type. Returns the name of the storage
+    pub(super) fn declare_scoped_lvar(&mut self, name: &str, ty: TermTy) -> Result<String, Error> {
+        if self.ctx().lvars.contains_key(name) {
+            return Err(error::program_error(&format!(
+                "variable `{}' already exists",
+                name
+            )));
+        }
+        let storage = self.gensym();
+        self.ctx_mut().lvars.insert(
+            name.to_string(),
+            CtxLVar {
+                name: storage.clone(),
+                ty,
+                readonly: true,
+                has_error: false,
+            },
+        );
+        Ok(storage)
+    }
+
+    /// Make the lvar declared by `declare_scoped_lvar` invisible
+    pub(super) fn undeclare_scoped_lvar(&mut self, name: &str) {
+        self.ctx_mut().lvars.remove(name);
     }
 
     fn convert_lvar_assign(
@@ -154,13 +458,34 @@ impl HirMaker {
         name: &str,
         rhs: &AstExpression,
         is_var: &bool,
+        typ: &Option<ast::Typ>,
     ) -> Result<HirExpression, Error> {
-        let expr = self.convert_expr(rhs)?;
-        let ctx = self.ctx_mut();
-        match ctx.lvars.get(name) {
-            Some(lvar) => {
+        let declared_ty = typ.as_ref().map(|t| self.convert_typ(t));
+        // Declare the variable even if rhs has an error, to avoid cascading errors
+        let mut expr = self
+            .convert_expr_with_expected(rhs, declared_ty.as_ref())
+            .or_else(|e| self.recover(e))?;
+        if let Some(ty) = declared_ty {
+            type_checking::check_lvar_annotation(&self.class_dict, &ty, &expr.ty, name)?;
+            if expr.ty != ty {
+                expr = Hir::bit_cast(ty, expr);
+            }
+        }
+        let existing = self
+            .ctx()
+            .lvars
+            .get(name)
+            .map(|lvar| (lvar.readonly, lvar.ty.clone()));
+        match existing {
+            Some(_) if typ.is_some() => {
+                return Err(error::program_error(&format!(
+                    "variable `{}' already exists",
+                    name
+                )));
+            }
+            Some((readonly, lvar_ty)) => {
                 // Reassigning
-                if lvar.readonly {
+                if readonly {
                     return Err(error::program_error(&format!(
                         "cannot reassign to {} (Hint: declare it with `var')",
                         name
@@ -171,17 +496,22 @@ impl HirMaker {
                         name
                     )));
                 } else {
-                    type_checking::check_reassign_var(&lvar.ty, &expr.ty, name)?;
+                    type_checking::check_reassign_var(&self.class_dict, &lvar_ty, &expr.ty, name)?;
+                    if lvar_ty != expr.ty {
+                        // Upcast needed (eg. assigning `None` to a `Maybe<Int>`)
+                        return Ok(Hir::assign_lvar(name, Hir::bit_cast(lvar_ty, expr)));
+                    }
                 }
             }
             None => {
                 // Newly introduced lvar
-                ctx.lvars.insert(
+                self.ctx_mut().lvars.insert(
                     name.to_string(),
                     CtxLVar {
                         name: name.to_string(),
                         ty: expr.ty.clone(),
                         readonly: !is_var,
+                        has_error: expr.is_error_placeholder(),
                     },
                 );
             }
@@ -213,18 +543,24 @@ impl HirMaker {
                     name
                 )));
             }
-            if !ivar.ty.equals_to(&expr.ty) {
-                // TODO: Subtype (@obj = 1, etc.)
+            if !expr.ty.conforms_to(&ivar.ty, &self.class_dict) {
                 return Err(error::type_error(&format!(
                     "instance variable `{}' has type {:?} but tried to assign a {:?}",
                     name, ivar.ty, expr.ty
                 )));
             }
-            Ok(Hir::assign_ivar(name, ivar.idx, expr, false))
+            let rhs = if expr.ty != ivar.ty {
+                // Upcast needed
+                Hir::bit_cast(ivar.ty.clone(), expr)
+            } else {
+                expr
+            };
+            Ok(Hir::assign_ivar(name, ivar.idx, rhs, false))
         } else {
             Err(error::program_error(&format!(
-                "instance variable `{}' not found",
-                name
+                "instance variable `{}' not found{}",
+                name,
+                self.did_you_mean_ivar(name)
             )))
         }
     }
@@ -268,44 +604,221 @@ impl HirMaker {
         rhs: &AstExpression,
     ) -> Result<HirExpression, Error> {
         let name = const_firstname(&names.join("::")); // TODO: pass entire `names` rather than ConstFirstname?
-        let fullname = self.register_const(&name, &rhs)?;
+        let namespace = self.ctx().namespace.clone();
+        let fullname = self.register_const(&namespace, &name, &rhs)?;
         Ok(Hir::assign_const(fullname, self.convert_expr(rhs)?))
     }
 
+    /// `a[i] += c` is compiled into
+    /// `(tmp1 = a; tmp2 = i; tmp1[tmp2] = tmp1[tmp2] + c)`
+    /// so that `a` and `i` are evaluated only once. Same for `a.b += c`
+    fn convert_op_assign(
+        &mut self,
+        lhs: &AstExpression,
+        op: &str,
+        rhs: &AstExpression,
+    ) -> Result<HirExpression, Error> {
+        let (receiver_expr, method_name, arg_exprs) = match &lhs.body {
+            AstExpressionBody::MethodCall {
+                receiver_expr,
+                method_name,
+                arg_exprs,
+                ..
+            } => (receiver_expr, method_name, arg_exprs),
+            _ => panic!("[BUG] unexpected lhs of op-assign: {:?}", lhs.body),
+        };
+        let mut hirs = vec![];
+        let receiver_ref = match receiver_expr {
+            Some(expr) => Some(self.convert_to_tmp_lvar(expr, &mut hirs)?),
+            None => None,
+        };
+        let arg_refs = arg_exprs
+            .iter()
+            .map(|expr| self.convert_to_tmp_lvar(expr, &mut hirs))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let getter = ast::method_call(receiver_ref, &method_name.0, arg_refs, true, false)
+            .with_locs(lhs.locs.clone());
+        if op == "&&" || op == "||" {
+            hirs.push(self.convert_logical_op_assign(getter, op, rhs)?);
+        } else {
+            let value = ast::binary_expr(getter.clone(), op, rhs.clone());
+            hirs.push(self.convert_expr(&ast::assignment(getter, value))?);
+        }
+        Ok(Hir::parenthesized_expression(hirs))
+    }
+
+    /// `a.b ||= c` is compiled into `a.b || (tmp = c; a.b = tmp; tmp)`
+    /// so that the setter is called only when needed. Same for `&&=`
+    fn convert_logical_op_assign(
+        &mut self,
+        getter: AstExpression,
+        op: &str,
+        rhs: &AstExpression,
+    ) -> Result<HirExpression, Error> {
+        let left_hir = self.convert_expr(&getter)?;
+        let mut hirs = vec![];
+        let value = self.convert_to_tmp_lvar(rhs, &mut hirs)?;
+        hirs.push(self.convert_expr(&ast::assignment(getter, value.clone()))?);
+        hirs.push(self.convert_expr(&value)?);
+        let right_hir = Hir::parenthesized_expression(hirs);
+        if op == "&&" {
+            type_checking::check_logical_operator_ty(&left_hir.ty, "lhs of logical and")?;
+            type_checking::check_logical_operator_ty(&right_hir.ty, "rhs of logical and")?;
+            Ok(Hir::logical_and(left_hir, right_hir))
+        } else {
+            type_checking::check_logical_operator_ty(&left_hir.ty, "lhs of logical or")?;
+            type_checking::check_logical_operator_ty(&right_hir.ty, "rhs of logical or")?;
+            Ok(Hir::logical_or(left_hir, right_hir))
+        }
+    }
+
+    /// Convert `expr` into an assignment to a temporary local variable
+    /// and push it to `hirs`. Returns a reference to the variable
+    fn convert_to_tmp_lvar(
+        &mut self,
+        expr: &AstExpression,
+        hirs: &mut Vec<HirExpression>,
+    ) -> Result<AstExpression, Error> {
+        let tmp = self.gensym();
+        hirs.push(self.convert_expr(&ast::assignment(ast::bare_name(&tmp), expr.clone()))?);
+        Ok(ast::bare_name(&tmp))
+    }
+
     fn convert_method_call(
         &mut self,
         receiver_expr: &Option<Box<AstExpression>>,
         method_name: &MethodFirstname,
+        type_args: &[ast::Typ],
         arg_exprs: &[AstExpression],
     ) -> Result<HirExpression, Error> {
         let receiver_hir = match receiver_expr {
-            Some(expr) => self.convert_expr(&expr)?,
+            Some(expr) => self.convert_receiver_expr(&expr, method_name)?,
             // Implicit self
             _ => self.convert_self_expr()?,
         };
-        // TODO: arg types must match with method signature
-        let arg_hirs = arg_exprs
+        let method_tyargs = type_args
             .iter()
-            .map(|arg_expr| self.convert_expr(arg_expr))
-            .collect::<Result<Vec<_>, _>>()?;
+            .map(|typ| self.convert_typ(typ))
+            .collect::<Vec<_>>();
+        let arg_hirs =
+            self.convert_method_args(&receiver_hir, method_name, &method_tyargs, arg_exprs)?;
+
+        self.make_method_call(receiver_hir, &method_name, &method_tyargs, arg_hirs)
+    }
+
+    /// Convert the receiver of a method call. An integer literal out of the
+    /// range of Int is allowed for `-@` (`-9223372036854775808`) and
+    /// `to_u64` (`0xffff_ffff_ffff_ffff.to_u64`)
+    fn convert_receiver_expr(
+        &mut self,
+        expr: &AstExpression,
+        method_name: &MethodFirstname,
+    ) -> Result<HirExpression, Error> {
+        if let AstExpressionBody::DecimalLiteral { value } = &expr.body {
+            let max = match method_name.0.as_str() {
+                "-@" => i64::MAX as u64 + 1,
+                "to_u64" => u64::MAX,
+                _ => i64::MAX as u64,
+            };
+            return convert_decimal_literal(*value, max);
+        }
+        self.convert_expr(expr)
+    }
 
-        self.make_method_call(receiver_hir, &method_name, arg_hirs)
+    /// Convert the arguments of a method call. Lambdas (and blocks) are
+    /// converted with the parameter types of the method so that their
+    /// parameter types can be omitted
+    pub(super) fn convert_method_args(
+        &mut self,
+        receiver_hir: &HirExpression,
+        method_name: &MethodFirstname,
+        method_tyargs: &[TermTy],
+        arg_exprs: &[AstExpression],
+    ) -> Result<Vec<HirExpression>, Error> {
+        let has_lambda = arg_exprs.iter().any(|expr| expr.is_lambda());
+        if !has_lambda || receiver_hir.is_error_placeholder() {
+            return arg_exprs
+                .iter()
+                .map(|arg_expr| self.convert_expr(arg_expr))
+                .collect();
+        }
+        let (sig, _) = self
+            .class_dict
+            .lookup_method(&receiver_hir.ty, method_name)?;
+        // Convert the other arguments first so that the type arguments of a
+        // generic method can be inferred from them (eg. `S` of
+        // `apply<S, T>(x: S, f: Fn1<S, T>)`)
+        let hirs = arg_exprs
+            .iter()
+            .map(|arg_expr| {
+                if arg_expr.is_lambda() {
+                    Ok(None)
+                } else {
+                    self.convert_expr(arg_expr).map(Some)
+                }
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        let sig = if method_tyargs.is_empty() && !sig.typarams.is_empty() {
+            let arg_tys = hirs
+                .iter()
+                .map(|hir| hir.as_ref().map(|x| &x.ty))
+                .collect::<Vec<_>>();
+            let tyargs = type_checking::infer_method_tyargs_partially(&sig, &arg_tys);
+            sig.specialize(&[], &tyargs)
+        } else {
+            sig.specialize(&[], method_tyargs)
+        };
+        arg_exprs
+            .iter()
+            .zip(hirs.into_iter())
+            .enumerate()
+            .map(|(i, (arg_expr, hir))| match hir {
+                Some(hir) => Ok(hir),
+                None => self.convert_expr_with_expected(arg_expr, sig.params.get(i).map(|p| &p.ty)),
+            })
+            .collect()
     }
 
-    fn make_method_call(
+    pub(super) fn make_method_call(
         &self,
         receiver_hir: HirExpression,
         method_name: &MethodFirstname,
+        method_tyargs: &[TermTy],
         arg_hirs: Vec<HirExpression>,
     ) -> Result<HirExpression, Error> {
-        let specialized = receiver_hir.ty.is_specialized();
+        // Suppress cascading errors (the receiver failed to compile)
+        if receiver_hir.is_error_placeholder() {
+            return Ok(Hir::error_placeholder());
+        }
         let class_fullname = &receiver_hir.ty.fullname;
-        let (sig, found_class_name) = self
+        let (_, found_class_name) = self
             .class_dict
             .lookup_method(&receiver_hir.ty, method_name)?;
+        // The signature before applying type arguments (i.e. that of the llvm function)
+        let base_sig = self
+            .class_dict
+            .find_method(&found_class_name, method_name)
+            .cloned()
+            .or_else(|| {
+                self.class_dict
+                    .find_mixin_method(&found_class_name, method_name)
+            })
+            .expect("[BUG] method found by lookup_method not found");
 
+        let class_tyargs = match &receiver_hir.ty.body {
+            TyBody::TySpe { type_args, .. } => type_args.clone(),
+            _ => vec![],
+        };
         let param_tys = arg_hirs.iter().map(|expr| &expr.ty).collect::<Vec<_>>();
-        type_checking::check_method_args(&sig, &param_tys, &receiver_hir, &arg_hirs)?;
+        let method_tyargs = if method_tyargs.is_empty() && !base_sig.typarams.is_empty() {
+            type_checking::infer_method_tyargs(&base_sig, &param_tys)?
+        } else {
+            type_checking::check_method_tyargs(&base_sig, method_tyargs)?;
+            method_tyargs.to_vec()
+        };
+        let sig = base_sig.specialize(&class_tyargs, &method_tyargs);
+        type_checking::check_method_args(&self.class_dict, &sig, &param_tys)?;
 
         let receiver = if &found_class_name != class_fullname {
             // Upcast needed
@@ -314,35 +827,247 @@ impl HirMaker {
             receiver_hir
         };
 
-        let args;
-        if specialized {
-            args = arg_hirs
-                .into_iter()
-                .map(|expr| Hir::bit_cast(ty::raw("Object"), expr))
-                .collect::<Vec<_>>();
-        } else {
-            args = arg_hirs;
-        }
+        // Type parameters are erased to their upper bound in the llvm function
+        let args = arg_hirs
+            .into_iter()
+            .zip(base_sig.params.iter())
+            .map(|(expr, param)| {
+                let param_ty = param.ty.upper_bound();
+                if expr.ty != param_ty {
+                    // Upcast needed
+                    Hir::bit_cast(param_ty, expr)
+                } else {
+                    expr
+                }
+            })
+            .collect::<Vec<_>>();
 
         let mut ret = Hir::method_call(sig.ret_ty.clone(), receiver, sig.fullname.clone(), args);
-        if specialized {
+        if sig.ret_ty != base_sig.ret_ty {
             ret = Hir::bit_cast(sig.ret_ty, ret)
         }
         Ok(ret)
     }
 
+    /// Generate HIR for `super` (call the method of the same name in the
+    /// superclass). Bare `super` passes the arguments of the current method
+    fn convert_super_call(
+        &mut self,
+        arg_exprs: &Option<Vec<AstExpression>>,
+    ) -> Result<HirExpression, Error> {
+        let ctx = self.ctx();
+        let method_sig = match (&ctx.kind, &ctx.method_sig) {
+            (CtxKind::Method, Some(sig)) => sig.clone(),
+            _ => {
+                return Err(error::program_error(
+                    "`super' must be used directly in a method",
+                ))
+            }
+        };
+        let self_ty = ctx.self_ty.clone();
+        let classname = method_sig.fullname.class_name();
+        let method_name = &method_sig.fullname.first_name;
+        let super_name = match self.superclass_for_super(&classname) {
+            Some(name) => name,
+            None => {
+                return Err(error::program_error(&format!(
+                    "`{}' has no superclass",
+                    classname
+                )))
+            }
+        };
+        let (_, found_class_name) = self
+            .class_dict
+            .lookup_method(&super_name.to_ty(), method_name)
+            .map_err(|_| {
+                error::program_error(&format!(
+                    "method `{}' not found in the superclasses of `{}'",
+                    method_name, classname
+                ))
+            })?;
+        let sig = self
+            .class_dict
+            .find_method(&found_class_name, method_name)
+            .cloned()
+            .or_else(|| {
+                self.class_dict
+                    .find_mixin_method(&found_class_name, method_name)
+            })
+            .expect("[BUG] method found by lookup_method not found");
+
+        let arg_hirs = match arg_exprs {
+            Some(exprs) => exprs
+                .iter()
+                .map(|arg_expr| self.convert_expr(arg_expr))
+                .collect::<Result<Vec<_>, _>>()?,
+            None => method_sig
+                .params
+                .iter()
+                .enumerate()
+                .map(|(idx, param)| Hir::hir_arg_ref(param.ty.clone(), idx))
+                .collect(),
+        };
+        let param_tys = arg_hirs.iter().map(|expr| &expr.ty).collect::<Vec<_>>();
+        type_checking::check_method_args(&self.class_dict, &sig, &param_tys)?;
+
+        let receiver = Hir::bit_cast(found_class_name.to_ty(), Hir::self_expression(self_ty));
+        let args = arg_hirs
+            .into_iter()
+            .zip(sig.params.iter())
+            .map(|(expr, param)| {
+                let param_ty = param.ty.upper_bound();
+                if expr.ty != param_ty {
+                    // Upcast needed
+                    Hir::bit_cast(param_ty, expr)
+                } else {
+                    expr
+                }
+            })
+            .collect::<Vec<_>>();
+        Ok(Hir::super_call(
+            sig.ret_ty.clone(),
+            receiver,
+            sig.fullname,
+            args,
+        ))
+    }
+
+    /// Return the class where `super` in the methods of `classname` starts
+    /// looking up. For class methods, this is the metaclass of the superclass
+    /// (not `Class`)
+    fn superclass_for_super(&self, classname: &ClassFullname) -> Option<ClassFullname> {
+        if classname.is_meta() {
+            let instance_name = class_fullname(&classname.0["Meta:".len()..]);
+            self.superclass_for_super(&instance_name)
+                .map(|name| name.meta_name())
+        } else {
+            self.class_dict
+                .find_class(classname)
+                .and_then(|c| c.superclass_fullname.clone())
+        }
+    }
+
+    /// Convert a type in the program, resolving the type parameters of the
+    /// current class and method
+    pub(super) fn convert_typ(&self, typ: &ast::Typ) -> TermTy {
+        let (class_typarams, method_typarams) = self.current_typarams();
+        signature::convert_typ(typ, &class_typarams, &method_typarams)
+    }
+
+    /// Return the names of the type parameters of the current class and method
+    fn current_typarams(&self) -> (Vec<String>, Vec<String>) {
+        let method_ctx = match self.method_ctx() {
+            Some(ctx) => ctx,
+            None => return (vec![], vec![]),
+        };
+        let class_typarams = match self.class_dict.find_class(&method_ctx.self_ty.fullname) {
+            Some(sk_class) => sk_class.typarams.iter().map(|t| t.name.clone()).collect(),
+            None => vec![],
+        };
+        let method_typarams = match &method_ctx.method_sig {
+            Some(sig) => sig.typarams.iter().map(|t| t.name.clone()).collect(),
+            None => vec![],
+        };
+        (class_typarams, method_typarams)
+    }
+
+    /// Convert a lambda or a block. `expected` is the type the value should
+    /// be (eg. `Fn1<Int, Void>` if passed to `Int#times`), if known. Omitted
+    /// parameter types and the return type are taken from it
     fn convert_lambda_expr(
         &mut self,
-        params: &[ast::Param],
+        params: &[ast::BlockParam],
         exprs: &[AstExpression],
+        is_fn: bool,
+        expected: Option<&TermTy>,
     ) -> Result<HirExpression, Error> {
+        let kind = if is_fn { "lambda" } else { "block" };
+        let expected_tys = expected.and_then(fn_type_args);
+        if let (Some(ty), Some(tys)) = (expected, expected_tys) {
+            // Parameters of a block may be omitted (eg. `3.times do ... end`)
+            let arity = tys.len() - 1;
+            if params.len() > arity || (is_fn && params.len() < arity) {
+                return Err(error::type_error(&format!(
+                    "expected {} but the {} takes {} parameter(s)",
+                    ty.fullname,
+                    kind,
+                    params.len()
+                )));
+            }
+        }
+        let (class_typarams, method_typarams) = self.current_typarams();
+        let mut hir_params = vec![];
+        for (i, param) in params.iter().enumerate() {
+            let expected_ty = expected_tys
+                .map(|tys| &tys[i])
+                .filter(|ty| !ty.has_typaram_ref());
+            let ty = match (&param.opt_typ, expected_ty) {
+                (Some(typ), _) => {
+                    let ty = signature::convert_typ(typ, &class_typarams, &method_typarams);
+                    match expected_ty {
+                        Some(t) if *t != ty => {
+                            return Err(error::type_error(&format!(
+                                "the {} parameter `{}' should be {} but is {}",
+                                kind, param.name, t.fullname, ty.fullname
+                            )))
+                        }
+                        _ => ty,
+                    }
+                }
+                (None, Some(t)) => t.clone(),
+                (None, None) => {
+                    return Err(error::type_error(&format!(
+                        "cannot infer the type of the {} parameter `{}'",
+                        kind, param.name
+                    )))
+                }
+            };
+            hir_params.push(MethodParam {
+                name: param.name.clone(),
+                ty,
+            });
+        }
+        if let Some(tys) = expected_tys {
+            for ty in &tys[params.len()..tys.len() - 1] {
+                hir_params.push(MethodParam {
+                    name: self.gensym(),
+                    ty: ty.clone(),
+                });
+            }
+        }
         self.lambda_ct += 1;
         let lambda_id = self.lambda_ct;
-        let hir_params = signature::convert_params(params, &[]);
         self.push_ctx(HirMakerContext::lambda_ctx(self.ctx(), hir_params.clone()));
-        let hir_exprs = self.convert_exprs(exprs)?;
+        let mut hir_exprs = self.convert_exprs(exprs)?;
+        let return_tys = std::mem::take(&mut self.ctx_mut().return_tys);
+        if hir_exprs.ty.is_never_type() {
+            // Ends with `return`
+            if let Some(ty) = return_tys.first() {
+                hir_exprs = hir_exprs.bitcast_to(ty.clone());
+            }
+        }
+        for ty in &return_tys {
+            type_checking::check_lambda_return_value(&self.class_dict, &hir_exprs.ty, ty)?;
+        }
         // This pops ctx
         let capture_exprs = self.resolve_lambda_captures();
+        if let Some(ret_ty) = expected_tys.and_then(|tys| tys.last()) {
+            // Make the value conform to the expected type. (Note that `Fn1<Int, Int>`
+            // does not conform to `Fn1<Int, Void>` or `Fn1<Int, Object>`)
+            if ret_ty.is_void_type() {
+                if !hir_exprs.ty.is_void_type() {
+                    hir_exprs = hir_exprs.voidify();
+                }
+            } else if !ret_ty.has_typaram_ref() && hir_exprs.ty != *ret_ty {
+                if !hir_exprs.ty.conforms_to(ret_ty, &self.class_dict) {
+                    return Err(error::type_error(&format!(
+                        "the {} should return {} but returns {}",
+                        kind, ret_ty.fullname, hir_exprs.ty.fullname
+                    )));
+                }
+                hir_exprs = hir_exprs.bitcast_to(ret_ty.clone());
+            }
+        }
         let captures_ary = self.convert_array_literal_(capture_exprs)?;
         Ok(Hir::lambda_expr(
             lambda_id,
@@ -385,18 +1110,44 @@ impl HirMaker {
             Ok(expr)
         } else {
             Err(error::program_error(&format!(
-                "variable `{}' was not found",
-                name
+                "variable `{}' was not found{}",
+                name,
+                did_you_mean(name, self.visible_var_names())
             )))
         }
     }
 
+    /// Return the names of the local variables and the arguments visible
+    /// from the current scope
+    fn visible_var_names(&self) -> Vec<&str> {
+        let mut names = vec![];
+        let mut ctx = Some(self.ctx());
+        while let Some(c) = ctx {
+            // Skip the ones made by gensym
+            names.extend(
+                c.lvars
+                    .keys()
+                    .map(|name| name.as_str())
+                    .filter(|name| !name.starts_with(' ')),
+            );
+            if let Some(sig) = &c.method_sig {
+                names.extend(sig.params.iter().map(|param| param.name.as_str()));
+            }
+            ctx = self.outer_lvar_scope_of(c);
+        }
+        names
+    }
+
     /// Lookup variable of the given name.
     /// If it is a free variable, ctx.captures will be modified
     fn lookup_var(&mut self, name: &str) -> Option<HirExpression> {
         let ctx = self.ctx();
         if let Some(lvar) = ctx.find_lvar(name) {
-            return Some(Hir::lvar_ref(lvar.ty.clone(), name.to_string()));
+            // Suppress cascading errors (the variable failed to compile)
+            if lvar.has_error {
+                return Some(Hir::error_placeholder());
+            }
+            return Some(Hir::lvar_ref(lvar.ty.clone(), lvar.name.clone()));
         }
         if let Some((idx, param)) = ctx.find_fn_arg(name) {
             return Some(Hir::hir_arg_ref(param.ty.clone(), idx));
@@ -429,7 +1180,7 @@ impl HirMaker {
                 ctx_depth: ctx.depth,
                 ty: lvar.ty.clone(),
                 detail: LambdaCaptureDetail::CapLVar {
-                    name: name.to_string(),
+                    name: lvar.name.clone(),
                 },
             };
             return Some((cap, Hir::lambda_capture_ref(lvar.ty.clone(), arity, cidx)));
@@ -461,32 +1212,82 @@ impl HirMaker {
         {
             Some(ivar) => Ok(Hir::ivar_ref(ivar.ty.clone(), name.to_string(), ivar.idx)),
             None => Err(error::program_error(&format!(
-                "ivar `{}' was not found",
-                name
+                "ivar `{}' was not found{}",
+                name,
+                self.did_you_mean_ivar(name)
             ))),
         }
     }
 
+    /// Return suggestion for the mistyped ivar name (if any)
+    fn did_you_mean_ivar(&self, name: &str) -> String {
+        let method_ctx = match self.method_ctx() {
+            Some(c) => c,
+            None => return "".to_string(),
+        };
+        let mut names = vec![];
+        if let Some(sk_class) = self.class_dict.find_class(&method_ctx.self_ty.fullname) {
+            names.extend(sk_class.ivars.keys());
+        }
+        names.extend(method_ctx.iivars.keys());
+        names.extend(method_ctx.super_ivars.keys());
+        did_you_mean(name, names.into_iter().map(|s| s.as_str()))
+    }
+
     /// Resolve constant name
     fn convert_const_ref(&self, names: &[String]) -> Result<HirExpression, Error> {
         // TODO: Resolve using ctx
         let fullname = ConstFullname("::".to_string() + &names.join("::"));
-        match self.constants.get(&fullname) {
+        let found = self
+            .constants
+            .get(&fullname)
+            .or_else(|| self.imports.constants.get(&fullname));
+        match found {
             Some(ty) => Ok(Hir::const_ref(ty.clone(), fullname)),
             None => {
                 let c = class_fullname(&names.join("::"));
                 if self.class_dict.class_exists(&c.0) {
                     Ok(Hir::const_ref(c.class_ty(), fullname))
+                } else if let Some(expr) = self.find_mixin_const(names) {
+                    Ok(expr)
                 } else {
+                    let const_names = self
+                        .constants
+                        .keys()
+                        .chain(self.imports.constants.keys())
+                        .map(|name| &name.0[2..]);
+                    let class_names = self
+                        .class_dict
+                        .sk_classes
+                        .keys()
+                        .filter(|name| !name.is_meta())
+                        .map(|name| name.0.as_str());
                     Err(error::program_error(&format!(
-                        "constant `{:?}' was not found",
-                        fullname
+                        "constant `{}' was not found{}",
+                        names.join("::"),
+                        did_you_mean(&names.join("::"), const_names.chain(class_names))
                     )))
                 }
             }
         }
     }
 
+    /// Find a constant like `A::X` from the modules included into `A`
+    fn find_mixin_const(&self, names: &[String]) -> Option<HirExpression> {
+        let (last, owner) = names.split_last()?;
+        let classname = class_fullname(owner.join("::"));
+        self.class_dict
+            .included_modules(&classname)
+            .into_iter()
+            .find_map(|module_name| {
+                let fullname = const_fullname(&format!("::{}::{}", module_name, last));
+                self.constants
+                    .get(&fullname)
+                    .or_else(|| self.imports.constants.get(&fullname))
+                    .map(|ty| Hir::const_ref(ty.clone(), fullname))
+            })
+    }
+
     fn convert_pseudo_variable(&self, token: &Token) -> Result<HirExpression, Error> {
         match token {
             Token::KwSelf => self.convert_self_expr(),
@@ -536,7 +1337,7 @@ impl HirMaker {
                 ary_ty.clone(),
                 Hir::const_ref(ty::meta("Array"), const_fullname("::Array")),
                 method_fullname(&class_fullname("Meta:Array"), "new"),
-                vec![Hir::decimal_literal(item_exprs.len() as i32)],
+                vec![Hir::decimal_literal(item_exprs.len() as i64)],
             ),
         ));
         // `tmp.push(item)`
@@ -563,6 +1364,64 @@ impl HirMaker {
         Ok(Hir::string_literal(idx))
     }
 
+    /// `"a#{b}c"` is compiled into
+    /// `tmp = MutableString.new(2); tmp.append("a"); tmp.append(b.to_s);
+    /// tmp.append("c"); tmp._unsafe_to_s`
+    fn convert_string_with_interpolation(
+        &mut self,
+        parts: &[AstExpression],
+    ) -> Result<HirExpression, Error> {
+        let mstr_ty = ty::raw("MutableString");
+        let mut capa = 0;
+        let mut str_hirs = vec![];
+        for part in parts {
+            let str_hir = match &part.body {
+                AstExpressionBody::StringLiteral { content } => {
+                    capa += content.len();
+                    self.convert_string_literal(content)?
+                }
+                _ => {
+                    let hir = self.convert_expr(part)?;
+                    let receiver_ty = hir.ty.clone();
+                    let str_hir =
+                        self.make_method_call(hir, &method_firstname("to_s"), &[], vec![])?;
+                    type_checking::check_to_s_ty(&self.class_dict, &receiver_ty, &str_hir.ty)?;
+                    str_hir
+                }
+            };
+            str_hirs.push(str_hir);
+        }
+
+        let tmp = self.gensym();
+        let mut exprs = vec![];
+        // `tmp = MutableString.new(capa)`
+        exprs.push(Hir::assign_lvar(
+            &tmp,
+            Hir::method_call(
+                mstr_ty.clone(),
+                Hir::const_ref(ty::meta("MutableString"), const_fullname("::MutableString")),
+                method_fullname(&class_fullname("Meta:MutableString"), "new"),
+                vec![Hir::decimal_literal(capa as i64)],
+            ),
+        ));
+        // `tmp.append(part)`
+        for str_hir in str_hirs {
+            exprs.push(Hir::method_call(
+                ty::raw("Void"),
+                Hir::lvar_ref(mstr_ty.clone(), tmp.clone()),
+                method_fullname(&class_fullname("MutableString"), "append"),
+                vec![Hir::bit_cast(ty::raw("String"), str_hir)],
+            ));
+        }
+        exprs.push(Hir::method_call(
+            ty::raw("String"),
+            Hir::lvar_ref(mstr_ty, tmp),
+            method_fullname(&class_fullname("MutableString"), "_unsafe_to_s"),
+            vec![],
+        ));
+        Ok(Hir::parenthesized_expression(exprs))
+    }
+
     pub(super) fn register_string_literal(&mut self, content: &str) -> usize {
         let idx = self.str_literals.len();
         self.str_literals.push(content.to_string());
@@ -570,7 +1429,14 @@ impl HirMaker {
     }
 
     /// Return the nearest common ancestor of the classes
-    fn nearest_common_ancestor_type(&self, ty1: &TermTy, ty2: &TermTy) -> TermTy {
+    pub(super) fn nearest_common_ancestor_type(&self, ty1: &TermTy, ty2: &TermTy) -> TermTy {
+        // eg. `Maybe<Int>` and `Maybe<Never>`
+        if ty2.conforms_to(ty1, &self.class_dict) {
+            return ty1.clone();
+        }
+        if ty1.conforms_to(ty2, &self.class_dict) {
+            return ty2.clone();
+        }
         let ancestors1 = self.class_dict.ancestor_types(ty1);
         let ancestors2 = self.class_dict.ancestor_types(ty2);
         for t2 in ancestors2 {
@@ -581,3 +1447,30 @@ impl HirMaker {
         panic!("[BUG] nearest_common_ancestor_type not found");
     }
 }
+
+/// Return the type arguments of `ty` if it is the type of a function
+/// (eg. `[Int, Void]` for `Fn1<Int, Void>`)
+fn fn_type_args(ty: &TermTy) -> Option<&[TermTy]> {
+    match &ty.body {
+        TyBody::TySpe {
+            base_name,
+            type_args,
+        } if !type_args.is_empty() && fn_class_arity(base_name) == Some(type_args.len() - 1) => {
+            Some(type_args)
+        }
+        _ => None,
+    }
+}
+
+/// Convert an integer literal whose value must be <= `max`.
+/// Values larger than `i64::MAX` are wrapped around (the bits are the same
+/// as the `UInt64`, and `-@` of the wrapped `2**63` is `-2**63`)
+fn convert_decimal_literal(value: u64, max: u64) -> Result<HirExpression, Error> {
+    if value > max {
+        return Err(error::program_error(&format!(
+            "integer literal {} is out of the range of Int",
+            value
+        )));
+    }
+    Ok(Hir::decimal_literal(value as i64))
+}
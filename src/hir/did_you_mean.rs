@@ -0,0 +1,62 @@
+//! Helpers to make "did you mean" suggestions for error messages
+
+/// Return the candidate which is most similar to `name`, if any is close enough
+///
+/// # Examples
+///
+/// ```
+/// use shiika::hir::did_you_mean::find_similar;
+///
+/// let candidates = vec!["length", "first", "push"];
+/// assert_eq!(find_similar("lenght", candidates.clone()), Some("length"));
+/// assert_eq!(find_similar("foo", candidates), None);
+/// ```
+pub fn find_similar<'a>(
+    name: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+) -> Option<&'a str> {
+    let max_distance = std::cmp::max(name.chars().count() / 3, 1);
+    candidates
+        .into_iter()
+        .filter(|c| *c != name)
+        .map(|c| (edit_distance(name, c), c))
+        .filter(|(d, _)| *d <= max_distance)
+        .min()
+        .map(|(_, c)| c)
+}
+
+/// Return a message like "; did you mean `foo'?" (or empty string when there
+/// is no similar candidate)
+pub fn did_you_mean<'a>(name: &str, candidates: impl IntoIterator<Item = &'a str>) -> String {
+    match find_similar(name, candidates) {
+        Some(s) => format!("; did you mean `{}'?", s),
+        None => "".to_string(),
+    }
+}
+
+/// Edit distance of the two strings (swapping adjacent chars counts as one edit)
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a = a.chars().collect::<Vec<_>>();
+    let b = b.chars().collect::<Vec<_>>();
+    // d[i][j]: distance of a[..i] and b[..j]
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for j in 0..=b.len() {
+        d[0][j] = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            d[i][j] = *[d[i - 1][j] + 1, d[i][j - 1] + 1, d[i - 1][j - 1] + cost]
+                .iter()
+                .min()
+                .unwrap();
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = std::cmp::min(d[i][j], d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    d[a.len()][b.len()]
+}
@@ -1,12 +1,15 @@
 use crate::ast::*;
 use crate::code_gen::CodeGen;
-use crate::error::Error;
+use crate::corelib;
+use crate::error::{self, Error, Errors, MAX_ERRORS};
 use crate::hir;
 use crate::hir::class_dict::ClassDict;
 use crate::hir::hir_maker_context::*;
 use crate::hir::method_dict::MethodDict;
 use crate::hir::*;
+use crate::library::LibraryExports;
 use crate::names;
+use crate::parser;
 use crate::type_checking;
 
 #[derive(Debug)]
@@ -26,11 +29,22 @@ pub struct HirMaker {
     gensym_ct: usize,
     /// Counter to give unique name for lambdas
     pub(super) lambda_ct: usize,
+    /// Errors recovered from so far
+    errors: Vec<Error>,
+    /// Items of the precompiled library
+    pub(super) imports: LibraryExports,
+    /// Definitions in the modules (compiled again for each class which
+    /// includes the module)
+    module_defs: HashMap<ClassFullname, Vec<ast::Definition>>,
 }
 
-pub fn make_hir(ast: ast::Program, corelib: Corelib) -> Result<Hir, Error> {
+pub fn make_hir(mut ast: ast::Program, mut corelib: Corelib) -> Result<Hir, Errors> {
+    let fn_classes = add_fn_classes(&mut ast, &corelib.sk_classes)?;
+    corelib.sk_classes.extend(fn_classes.sk_classes);
+    corelib.sk_methods.extend(fn_classes.sk_methods);
+
     let class_dict = class_dict::create(&ast, corelib.sk_classes)?;
-    let mut hir = convert_program(class_dict, ast)?;
+    let mut hir = convert_program(class_dict, LibraryExports::default(), ast)?;
 
     // While corelib classes are included in `class_dict`,
     // corelib methods are not. Here we need to add them manually
@@ -39,15 +53,90 @@ pub fn make_hir(ast: ast::Program, corelib: Corelib) -> Result<Hir, Error> {
     Ok(hir)
 }
 
-fn convert_program(class_dict: ClassDict, prog: ast::Program) -> Result<Hir, Error> {
-    let mut hir_maker = HirMaker::new(class_dict);
+pub fn make_hir_with_library(
+    mut ast: ast::Program,
+    imports: LibraryExports,
+) -> Result<Hir, Errors> {
+    check_reopened_classes(&ast, &imports)?;
+    let fn_classes = add_fn_classes(&mut ast, &imports.sk_classes)?;
+    let mut classes = imports.sk_classes.clone();
+    classes.extend(fn_classes.sk_classes);
+
+    let class_dict = class_dict::create(&ast, classes)?;
+    let mut hir = convert_program(class_dict, imports, ast)?;
+    hir.add_methods(fn_classes.sk_methods);
+    Ok(hir)
+}
+
+/// Generate the classes `FnN` which are used in the program but not
+/// defined yet (Fn0 ~ Fn9 are predefined.) The Shiika part of them is
+/// appended to `ast` and the Rust part is returned
+fn add_fn_classes(
+    ast: &mut ast::Program,
+    defined: &HashMap<ClassFullname, SkClass>,
+) -> Result<Corelib, Errors> {
+    let mut arities = ast
+        .fn_arities
+        .iter()
+        .filter(|&&i| !defined.contains_key(&names::fn_class_fullname(i)))
+        .cloned()
+        .collect::<Vec<_>>();
+    arities.sort_unstable();
+    for &i in &arities {
+        let prog = parser::Parser::parse(&corelib::fn_class_src(i))?;
+        ast.toplevel_items.extend(prog.toplevel_items);
+    }
+    Ok(Corelib::fn_classes(&arities))
+}
+
+/// Classes of the library cannot have new methods because their vtables are
+/// already compiled
+fn check_reopened_classes(ast: &ast::Program, imports: &LibraryExports) -> Result<(), Error> {
+    for item in &ast.toplevel_items {
+        if let ast::TopLevelItem::Def(ast::Definition::ClassDefinition {
+            name, defs, locs, ..
+        }) = item
+        {
+            let fullname = name.add_namespace("");
+            let has_methods = defs.iter().any(|def| match def {
+                ast::Definition::InstanceMethodDefinition { .. }
+                | ast::Definition::ClassMethodDefinition { .. } => true,
+                _ => false,
+            });
+            let has_methods = has_methods
+                || defs.iter().any(|def| match def {
+                    ast::Definition::IncludeDefinition { .. }
+                    | ast::Definition::ExtendDefinition { .. } => true,
+                    _ => false,
+                });
+            if has_methods && imports.sk_classes.contains_key(&fullname) {
+                return Err(error::program_error(&format!(
+                    "cannot add methods to `{}' because it is in the precompiled library",
+                    fullname
+                ))
+                .with_location(locs)
+                .with_note("note: use --builtin-dir to compile the builtin library from source"));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn convert_program(
+    class_dict: ClassDict,
+    imports: LibraryExports,
+    prog: ast::Program,
+) -> Result<Hir, Errors> {
+    let mut hir_maker = HirMaker::new(class_dict, imports);
     hir_maker.register_class_consts();
-    let main_exprs = hir_maker.convert_toplevel_items(&prog.toplevel_items)?;
+    hir_maker.register_maybe_items();
+    let result = hir_maker.convert_toplevel_items(&prog.toplevel_items);
+    let main_exprs = Errors::check(result, std::mem::take(&mut hir_maker.errors))?;
     Ok(hir_maker.extract_hir(main_exprs))
 }
 
 impl HirMaker {
-    fn new(class_dict: ClassDict) -> HirMaker {
+    fn new(class_dict: ClassDict, imports: LibraryExports) -> HirMaker {
         HirMaker {
             class_dict,
             method_dict: MethodDict::new(),
@@ -57,7 +146,26 @@ impl HirMaker {
             ctx_stack: vec![],
             gensym_ct: 0,
             lambda_ct: 0,
+            errors: vec![],
+            imports,
+            module_defs: HashMap::new(),
+        }
+    }
+
+    /// Record `err` to report later.
+    /// Returns `err` if there are too many errors
+    pub(super) fn add_error(&mut self, err: Error) -> Result<(), Error> {
+        if self.errors.len() + 1 >= MAX_ERRORS {
+            return Err(err);
         }
+        self.errors.push(err);
+        Ok(())
+    }
+
+    /// Record `err` and return a placeholder for the expression
+    pub(super) fn recover(&mut self, err: Error) -> Result<HirExpression, Error> {
+        self.add_error(err)?;
+        Ok(Hir::error_placeholder())
     }
 
     /// Destructively convert self to Hir
@@ -72,8 +180,11 @@ impl HirMaker {
         let mut const_inits = vec![];
         std::mem::swap(&mut const_inits, &mut self.const_inits);
 
-        // Register void
-        constants.insert(const_fullname("::Void"), ty::raw("Void"));
+        // Register void (unless defined in the library)
+        let void = const_fullname("::Void");
+        if !self.imports.constants.contains_key(&void) {
+            constants.insert(void, ty::raw("Void"));
+        }
 
         Hir {
             sk_classes,
@@ -82,6 +193,7 @@ impl HirMaker {
             str_literals,
             const_inits,
             main_exprs,
+            imports: std::mem::take(&mut self.imports),
         }
     }
 
@@ -89,7 +201,8 @@ impl HirMaker {
         // mem::take is needed to avoid compile error
         let classes = std::mem::take(&mut self.class_dict.sk_classes);
         for (name, class) in &classes {
-            if !name.is_meta() && !class.const_is_obj {
+            let imported = self.imports.sk_classes.contains_key(name);
+            if !name.is_meta() && !class.const_is_obj && !imported {
                 self.register_class_const(name);
             }
         }
@@ -118,16 +231,30 @@ impl HirMaker {
         let mut main_exprs = vec![];
         // Contains local vars defined at toplevel
         self.push_ctx(HirMakerContext::toplevel());
+        // Modules first because the classes including them need their ivars
+        for item in items {
+            if let ast::TopLevelItem::Def(ast::Definition::ModuleDefinition { name, defs, locs }) =
+                item
+            {
+                if let Err(e) = self.process_module(&name.add_namespace(""), defs) {
+                    self.add_error(e.with_location(locs))?;
+                }
+            }
+        }
         for item in items {
             match item {
                 ast::TopLevelItem::Def(def) => {
-                    self.process_toplevel_def(&def)?;
+                    if let Err(e) = self.process_toplevel_def(&def) {
+                        self.add_error(e)?;
+                    }
                 }
                 ast::TopLevelItem::Expr(expr) => {
-                    main_exprs.push(self.convert_expr(&expr)?);
+                    let hir_expr = self.convert_expr(&expr).or_else(|e| self.recover(e))?;
+                    main_exprs.push(hir_expr);
                 }
             }
         }
+        self.process_mixins()?;
         self.pop_ctx();
         Ok(HirExpressions::new(main_exprs))
     }
@@ -135,18 +262,174 @@ impl HirMaker {
     fn process_toplevel_def(&mut self, def: &ast::Definition) -> Result<(), Error> {
         match def {
             // Extract instance/class methods
-            ast::Definition::ClassDefinition { name, defs, .. } => {
+            ast::Definition::ClassDefinition {
+                name, defs, locs, ..
+            } => {
+                let full = name.add_namespace("");
+                self.collect_sk_methods(&full, defs)
+                    .map_err(|e| e.with_location(locs))?;
+            }
+            ast::Definition::EnumDefinition {
+                name,
+                cases,
+                defs,
+                locs,
+            } => {
                 let full = name.add_namespace("");
-                self.collect_sk_methods(&full, defs)?;
+                self.process_enum_def(&full, cases, defs)
+                    .map_err(|e| e.with_location(locs))?;
             }
-            ast::Definition::ConstDefinition { name, expr } => {
-                self.register_const(name, expr)?;
+            ast::Definition::ConstDefinition { name, expr, locs } => {
+                self.register_const(&class_fullname(""), name, expr)
+                    .map_err(|e| e.with_location(locs))?;
             }
+            // Already processed
+            ast::Definition::ModuleDefinition { .. } => (),
             _ => panic!("should be checked in hir::class_dict"),
         }
         Ok(())
     }
 
+    /// Extract methods and constants of a module
+    fn process_module(
+        &mut self,
+        fullname: &ClassFullname,
+        defs: &[ast::Definition],
+    ) -> Result<(), Error> {
+        let n_errors = self.errors.len();
+        self.collect_sk_methods(fullname, defs)?;
+        // Do not compile the methods again if they have errors
+        if self.errors.len() == n_errors {
+            self.module_defs.insert(fullname.clone(), defs.to_vec());
+        }
+        Ok(())
+    }
+
+    /// Compile the methods of the modules for each class which includes (or
+    /// extends) them
+    fn process_mixins(&mut self) -> Result<(), Error> {
+        let mut classnames = self
+            .class_dict
+            .sk_classes
+            .iter()
+            .filter(|(name, sk_class)| {
+                !sk_class.includes.is_empty() && !self.imports.sk_classes.contains_key(name)
+            })
+            .map(|(name, _)| name.clone())
+            .collect::<Vec<_>>();
+        // Sort to make the order of the constant initialization stable
+        classnames.sort_by(|a, b| a.0.cmp(&b.0));
+        for classname in classnames {
+            if let Err(e) = self.process_mixins_of(&classname) {
+                self.add_error(e)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn process_mixins_of(&mut self, classname: &ClassFullname) -> Result<(), Error> {
+        let ctx = HirMakerContext::class_ctx(classname);
+        let sk_class = self.class_dict.get_class(classname, "process_mixins_of");
+        let includes = sk_class.includes.clone();
+        // Methods defined in the class or in a module included later
+        let mut defined = sk_class
+            .method_sigs
+            .keys()
+            .cloned()
+            .collect::<std::collections::HashSet<_>>();
+        for module_name in includes.iter().rev() {
+            let defs = match self.module_defs.get(module_name) {
+                Some(defs) => defs.clone(),
+                None if self.imports.sk_classes.contains_key(module_name) => {
+                    return Err(error::program_error(&format!(
+                        "cannot include `{}' because it is in the precompiled library",
+                        module_name
+                    )))
+                }
+                // The module has errors (already reported)
+                None => continue,
+            };
+            for def in &defs {
+                let (sig, body_exprs, locs) = match def {
+                    ast::Definition::InstanceMethodDefinition {
+                        sig,
+                        body_exprs,
+                        locs,
+                    } => (sig, body_exprs, locs),
+                    _ => continue,
+                };
+                let is_initializer = def.is_initializer();
+                let signature = if is_initializer {
+                    signature::signature_of_module_initializer(classname, module_name)
+                } else if defined.insert(sig.name.clone()) {
+                    self.class_dict
+                        .find_method(module_name, &sig.name)
+                        .expect("[BUG] module method not indexed")
+                        .with_owner(classname)
+                } else {
+                    // Overridden
+                    continue;
+                };
+                let super_ivars = if is_initializer {
+                    Some(
+                        self.class_dict
+                            .get_class(classname, "process_mixins_of")
+                            .ivars
+                            .clone(),
+                    )
+                } else {
+                    None
+                };
+                let fullname = signature.fullname.clone();
+                match self.convert_method_body(
+                    &ctx,
+                    signature,
+                    body_exprs,
+                    is_initializer,
+                    super_ivars,
+                ) {
+                    Ok((method, _)) => self.method_dict.add_method(classname, method),
+                    Err(e) => {
+                        self.add_error(e.with_location(locs))?;
+                        continue;
+                    }
+                }
+                if is_initializer && classname.is_meta() {
+                    // Initialize the ivars of the class object (`extend`)
+                    self.const_inits
+                        .push(extended_module_init(classname, fullname));
+                }
+            }
+
+            // Accessors for the ivars of the module
+            let module = self.class_dict.get_class(module_name, "process_mixins_of");
+            let class_ivars = &self
+                .class_dict
+                .get_class(classname, "process_mixins_of")
+                .ivars;
+            let ivars = module
+                .ivars
+                .keys()
+                .filter_map(|name| {
+                    class_ivars
+                        .get(name)
+                        .map(|ivar| (name.clone(), ivar.clone()))
+                })
+                .collect::<SkIVars>();
+            let mut except = defined.clone();
+            except.extend(defs.iter().filter_map(|def| match def {
+                ast::Definition::InstanceMethodDefinition { sig, .. } => Some(sig.name.clone()),
+                _ => None,
+            }));
+            for name in ivars.keys() {
+                defined.insert(method_firstname(name));
+                defined.insert(method_firstname(&format!("{}=", name)));
+            }
+            self.define_accessors_except(classname, ivars, &except);
+        }
+        Ok(())
+    }
+
     /// Extract instance/class methods and constants
     fn collect_sk_methods(
         &mut self,
@@ -158,6 +441,22 @@ impl HirMaker {
         Ok(())
     }
 
+    /// Extract methods of an enum and its cases
+    fn process_enum_def(
+        &mut self,
+        fullname: &ClassFullname,
+        cases: &[ast::EnumCase],
+        defs: &[ast::Definition],
+    ) -> Result<(), Error> {
+        self.collect_sk_methods(fullname, defs)?;
+        for case in cases {
+            let case_fullname = case.name.add_namespace(&fullname.0);
+            self.collect_sk_methods(&case_fullname, &case.defs())
+                .map_err(|e| e.with_location(&case.locs))?;
+        }
+        Ok(())
+    }
+
     fn register_meta_ivar(&mut self, name: &ClassFullname) -> Result<(), Error> {
         let mut meta_ivars = HashMap::new();
         meta_ivars.insert(
@@ -185,18 +484,23 @@ impl HirMaker {
         // Add `#initialize`
         let mut own_ivars = HashMap::default();
         if let Some(ast::Definition::InstanceMethodDefinition {
-            sig, body_exprs, ..
+            sig,
+            body_exprs,
+            locs,
         }) = defs.iter().find(|d| d.is_initializer())
         {
-            let (sk_method, found_ivars) =
-                self.create_initialize(&mut ctx, &fullname, &sig.name, &body_exprs)?;
+            let (sk_method, found_ivars) = self
+                .create_initialize(&mut ctx, &fullname, &sig.name, &body_exprs)
+                .map_err(|e| e.with_location(locs))?;
             self.method_dict.add_method(&fullname, sk_method);
             own_ivars = found_ivars;
         }
         self.define_ivars(fullname, own_ivars, defs)?;
 
         // Add `.new`
-        if has_new(&fullname) {
+        let sk_class = self.class_dict.get_class(&fullname, "process_defs");
+        let instantiable = !sk_class.is_module && sk_class.enum_cases.is_none();
+        if has_new(&fullname) && instantiable {
             self.method_dict
                 .add_method(&meta_name, self.create_new(&fullname)?);
         }
@@ -204,26 +508,47 @@ impl HirMaker {
         for def in defs.iter().filter(|d| !d.is_initializer()) {
             match def {
                 ast::Definition::InstanceMethodDefinition {
-                    sig, body_exprs, ..
-                } => {
-                    let method =
-                        self.convert_method_def(&ctx, &fullname, &sig.name, &body_exprs)?;
-                    self.method_dict.add_method(&fullname, method);
-                }
+                    sig,
+                    body_exprs,
+                    locs,
+                } => match self.convert_method_def(&ctx, &fullname, &sig.name, &body_exprs) {
+                    Ok(method) => self.method_dict.add_method(&fullname, method),
+                    Err(e) => self.add_error(e.with_location(locs))?,
+                },
                 ast::Definition::ClassMethodDefinition {
-                    sig, body_exprs, ..
-                } => {
-                    let method =
-                        self.convert_method_def(&ctx, &meta_name, &sig.name, &body_exprs)?;
-                    self.method_dict.add_method(&meta_name, method);
+                    sig,
+                    body_exprs,
+                    locs,
+                } => match self.convert_method_def(&ctx, &meta_name, &sig.name, &body_exprs) {
+                    Ok(method) => self.method_dict.add_method(&meta_name, method),
+                    Err(e) => self.add_error(e.with_location(locs))?,
+                },
+                ast::Definition::ConstDefinition { name, expr, locs } => {
+                    if let Err(e) = self.register_const(fullname, name, expr) {
+                        self.add_error(e.with_location(locs))?;
+                    }
                 }
-                ast::Definition::ConstDefinition { name, expr } => {
-                    self.register_const(name, expr)?;
+                ast::Definition::ClassDefinition {
+                    name, defs, locs, ..
+                } => {
+                    let full = name.add_namespace(&fullname.0);
+                    self.collect_sk_methods(&full, defs)
+                        .map_err(|e| e.with_location(locs))?;
                 }
-                ast::Definition::ClassDefinition { name, defs, .. } => {
+                ast::Definition::EnumDefinition {
+                    name,
+                    cases,
+                    defs,
+                    locs,
+                } => {
                     let full = name.add_namespace(&fullname.0);
-                    self.collect_sk_methods(&full, defs)?;
+                    self.process_enum_def(&full, cases, defs)
+                        .map_err(|e| e.with_location(locs))?;
                 }
+                // Checked in hir::class_dict
+                ast::Definition::ModuleDefinition { .. }
+                | ast::Definition::IncludeDefinition { .. }
+                | ast::Definition::ExtendDefinition { .. } => (),
             }
         }
         Ok(())
@@ -238,11 +563,15 @@ impl HirMaker {
         name: &MethodFirstname,
         body_exprs: &[AstExpression],
     ) -> Result<(SkMethod, SkIVars), Error> {
-        let super_ivars = self
-            .class_dict
-            .get_superclass(class_fullname)
-            .map(|super_cls| super_cls.ivars.clone());
-        self.convert_method_def_(ctx, class_fullname, name, body_exprs, true, super_ivars)
+        let super_ivars = self.class_dict.inherited_ivars(class_fullname);
+        self.convert_method_def_(
+            ctx,
+            class_fullname,
+            name,
+            body_exprs,
+            true,
+            Some(super_ivars),
+        )
     }
 
     /// Define ivars of a class
@@ -267,11 +596,32 @@ impl HirMaker {
         let meta_name = class_fullname.meta_name();
         let need_bitcast = init_cls_name != class_fullname;
         let arity = initialize_params.len();
+        let module_inits = self.module_initializers(&class_fullname);
 
         let new_body = move |code_gen: &CodeGen, function: &inkwell::values::FunctionValue| {
             // Allocate memory
             let obj = code_gen.allocate_sk_obj(&class_fullname, "addr");
 
+            // Call initializers of the included modules
+            for (init_name, cls_name) in &module_inits {
+                let init = code_gen
+                    .module
+                    .get_function(&init_name.full_name)
+                    .unwrap_or_else(|| panic!("[BUG] function `{}' not found", &init_name));
+                let mut addr = obj;
+                if *cls_name != class_fullname {
+                    let ances_type = code_gen
+                        .llvm_struct_types
+                        .get(cls_name)
+                        .expect("ances_type not found")
+                        .ptr_type(inkwell::AddressSpace::Generic);
+                    addr = code_gen
+                        .builder
+                        .build_bitcast(addr, ances_type, "obj_as_super");
+                }
+                code_gen.builder.build_call(init, &[addr], "");
+            }
+
             // Call initialize
             let initialize = code_gen
                 .module
@@ -315,6 +665,34 @@ impl HirMaker {
         })
     }
 
+    /// Return the initializers of the modules included into the class and its
+    /// ancestors (with the class which includes the module), in the order to
+    /// call
+    fn module_initializers(
+        &self,
+        class_fullname: &ClassFullname,
+    ) -> Vec<(MethodFullname, ClassFullname)> {
+        let mut inits = vec![];
+        let mut cls = self.class_dict.find_class(class_fullname);
+        while let Some(sk_class) = cls {
+            for module_name in sk_class.includes.iter().rev() {
+                let initialize = method_firstname("initialize");
+                if self
+                    .class_dict
+                    .find_method(module_name, &initialize)
+                    .is_some()
+                {
+                    let sig =
+                        signature::signature_of_module_initializer(&sk_class.fullname, module_name);
+                    inits.push((sig.fullname, sk_class.fullname.clone()));
+                }
+            }
+            cls = self.class_dict.get_superclass(&sk_class.fullname);
+        }
+        inits.reverse();
+        inits
+    }
+
     fn find_initialize(
         &self,
         class: &TermTy,
@@ -329,15 +707,18 @@ impl HirMaker {
         ))
     }
 
-    /// Register a constant
+    /// Register a constant defined in `namespace` (empty for the toplevel)
     pub(super) fn register_const(
         &mut self,
+        namespace: &ClassFullname,
         name: &ConstFirstname,
         expr: &AstExpression,
     ) -> Result<ConstFullname, Error> {
-        let ctx = self.ctx();
-        // TODO: resolve name using ctx
-        let fullname = const_fullname(&format!("{}::{}", ctx.namespace.0, &name.0));
+        let fullname = if namespace.0.is_empty() {
+            const_fullname(&format!("::{}", &name.0))
+        } else {
+            const_fullname(&format!("::{}::{}", namespace.0, &name.0))
+        };
         let hir_expr = self.convert_expr(expr)?;
         self.constants.insert(fullname.clone(), hir_expr.ty.clone());
         let op = Hir::assign_const(fullname.clone(), hir_expr);
@@ -377,16 +758,31 @@ impl HirMaker {
             .find_method(class_fullname, name)
             .expect(&err)
             .clone();
+        self.convert_method_body(ctx, signature, body_exprs, is_initializer, super_ivars)
+    }
 
+    /// Create a SkMethod of the given signature and return it with ctx.iivars
+    fn convert_method_body(
+        &mut self,
+        ctx: &HirMakerContext,
+        signature: MethodSignature,
+        body_exprs: &[AstExpression],
+        is_initializer: bool,
+        super_ivars: Option<SkIVars>,
+    ) -> Result<(SkMethod, HashMap<String, SkIVar>), Error> {
         self.push_ctx(HirMakerContext::method_ctx(
             ctx,
             &signature,
             is_initializer,
             super_ivars.unwrap_or_else(|| HashMap::new()),
         ));
-        let body_exprs = self.convert_exprs(body_exprs)?;
+        let mut body_exprs = self.convert_exprs(body_exprs)?;
         let iivars = self.pop_ctx().iivars;
-        type_checking::check_return_value(&signature, &body_exprs.ty)?;
+        type_checking::check_return_value(&self.class_dict, &signature, &body_exprs.ty)?;
+        if !signature.ret_ty.is_void_type() && body_exprs.ty != signature.ret_ty {
+            // Upcast needed
+            body_exprs = body_exprs.bitcast_to(signature.ret_ty.clone());
+        }
 
         let body = SkMethodBody::ShiikaMethodBody { exprs: body_exprs };
         Ok((SkMethod { signature, body }, iivars))
@@ -400,11 +796,31 @@ impl HirMaker {
     }
 }
 
+/// Create a call of the initializer of an extended module on the class object
+/// (eg. `A.initialize@M`)
+fn extended_module_init(
+    metaclass_name: &ClassFullname,
+    init_name: MethodFullname,
+) -> HirExpression {
+    let class_ty = metaclass_name.to_ty();
+    let const_name = const_fullname(&format!("::{}", &metaclass_name.0["Meta:".len()..]));
+    Hir::method_call(
+        ty::raw("Void"),
+        Hir::const_ref(class_ty, const_name),
+        init_name,
+        vec![],
+    )
+}
+
 // Whether the class has .new
 fn has_new(fullname: &ClassFullname) -> bool {
     // TODO: maybe more?
     // At least these two must be excluded (otherwise wrong .ll is generated)
-    if fullname.0 == "Int" || fullname.0 == "Float" {
+    if fullname.0 == "Int"
+        || fullname.0 == "Float"
+        || fullname.0 == "Float32"
+        || corelib::sized_int_bits(&fullname.0).is_some()
+    {
         return false;
     }
     true
@@ -23,6 +23,11 @@ pub struct HirMakerContext {
     pub lvars: HashMap<String, CtxLVar>,
     /// List of free variables captured in this context
     pub captures: Vec<LambdaCapture>,
+    /// Types of the values given to `return` in this context
+    /// Only used for lambdas
+    pub return_tys: Vec<TermTy>,
+    /// Loops which contain the current expression (innermost last)
+    pub loops: Vec<CtxLoop>,
 
     //
     // ivar-related stuffs
@@ -49,6 +54,17 @@ pub struct CtxLVar {
     pub name: String,
     pub ty: TermTy,
     pub readonly: bool,
+    /// True if the initial value failed to compile
+    pub has_error: bool,
+}
+
+/// A loop (`while`, `until` or `loop`)
+#[derive(Debug)]
+pub struct CtxLoop {
+    pub label: Option<String>,
+    /// Types of the values given to `break`.
+    /// None if this loop is not a `loop` (i.e. cannot have a value)
+    pub break_tys: Option<Vec<TermTy>>,
 }
 
 #[derive(Debug)]
@@ -76,6 +92,8 @@ impl HirMakerContext {
             namespace: ClassFullname("".to_string()),
             lvars: HashMap::new(),
             captures: vec![],
+            return_tys: vec![],
+            loops: vec![],
             iivars: HashMap::new(),
             is_initializer: false,
             super_ivars: HashMap::new(),
@@ -92,6 +110,8 @@ impl HirMakerContext {
             namespace: fullname.clone(),
             lvars: HashMap::new(),
             captures: vec![],
+            return_tys: vec![],
+            loops: vec![],
             iivars: HashMap::new(),
             is_initializer: false,
             super_ivars: HashMap::new(),
@@ -113,6 +133,8 @@ impl HirMakerContext {
             namespace: class_ctx.namespace.clone(),
             lvars: HashMap::new(),
             captures: vec![],
+            return_tys: vec![],
+            loops: vec![],
             iivars: HashMap::new(),
             is_initializer,
             super_ivars,
@@ -125,6 +147,7 @@ impl HirMakerContext {
             fullname: method_fullname(&class_fullname("(anon)"), "(anon)"),
             ret_ty: ty::raw("(dummy)"),
             params,
+            typarams: vec![],
         };
         HirMakerContext {
             kind: CtxKind::Lambda,
@@ -134,6 +157,8 @@ impl HirMakerContext {
             namespace: method_ctx.namespace.clone(),
             lvars: HashMap::new(),
             captures: vec![],
+            return_tys: vec![],
+            loops: vec![],
             iivars: HashMap::new(),
             is_initializer: false,
             super_ivars: HashMap::new(),
@@ -0,0 +1,229 @@
+//! Optional values (`Maybe<T>`)
+//!
+//! `Maybe<T>` is a builtin generic class (builtin/maybe.sk). Its instances
+//! are created by the method `Object#Some` (corelib/object.rs) and the
+//! constant `None`, which is a `Maybe<Never>` and conforms to any `Maybe<T>`.
+//! `if let`, `?.` and `||` are compiled into `if` expressions which check
+//! `Maybe#is_some`.
+use crate::ast::*;
+use crate::error;
+use crate::error::Error;
+use crate::hir::hir_maker::HirMaker;
+use crate::hir::*;
+
+impl HirMaker {
+    /// Register the constant `None` and hide `Maybe.new` from the programs
+    /// (unless `Maybe` is defined in the library)
+    pub(super) fn register_maybe_items(&mut self) {
+        let maybe = class_fullname("Maybe");
+        if self.imports.sk_classes.contains_key(&maybe)
+            || self.class_dict.find_class(&maybe).is_none()
+        {
+            return;
+        }
+        // Only `Some` and `None` can create a Maybe
+        self.class_dict
+            .get_class_mut(&maybe.meta_name(), "HirMaker::register_maybe_items")
+            .method_sigs
+            .remove(&method_firstname("new"));
+
+        // The value of `None` is never used
+        let none = maybe_new(ty::raw("Never"), Hir::boolean_literal(false), false);
+        let fullname = const_fullname("::None");
+        self.constants.insert(fullname.clone(), none.ty.clone());
+        self.const_inits.push(Hir::assign_const(fullname, none));
+    }
+
+    /// `if let x = maybe then ... else ... end` is compiled into
+    /// `if (tmp = maybe).is_some then x = tmp.get; ... else ... end`
+    pub(super) fn convert_if_let_expr(
+        &mut self,
+        name: &str,
+        expr: &AstExpression,
+        then_exprs: &[AstExpression],
+        else_exprs: &Option<Vec<AstExpression>>,
+    ) -> Result<HirExpression, Error> {
+        let maybe_hir = self.convert_expr(expr)?;
+        let maybe_ty = maybe_hir.ty.clone();
+        let item_ty = match maybe_item_ty(&maybe_ty) {
+            Some(t) => t.clone(),
+            None => {
+                return Err(error::type_error(&format!(
+                    "`if let' needs a Maybe but got {}",
+                    maybe_ty
+                )))
+            }
+        };
+        let tmp = self.gensym();
+        let cond_hir = self.maybe_is_some(Hir::assign_lvar(&tmp, maybe_hir))?;
+
+        // `name` is only visible in the then-clause
+        let storage = self.declare_scoped_lvar(name, item_ty)?;
+        let value_hir = self.maybe_get(Hir::lvar_ref(maybe_ty, tmp))?;
+        let mut then_hirs = vec![Hir::assign_lvar(&storage, value_hir)];
+        let then_result = self.convert_exprs(then_exprs);
+        self.undeclare_scoped_lvar(name);
+        then_hirs.extend(then_result?.exprs);
+        let then_hirs = HirExpressions::new(then_hirs);
+        let else_hirs = match else_exprs {
+            Some(exprs) => Some(self.convert_exprs(exprs)?),
+            None => None,
+        };
+        let if_ty = self.if_expr_ty(&then_hirs, &else_hirs);
+        Ok(Hir::if_expression(if_ty, cond_hir, then_hirs, else_hirs))
+    }
+
+    /// `maybe?.foo(args)` is compiled into
+    /// `if (tmp = maybe).is_some then Some(tmp.get.foo(args)) else None end`.
+    /// `Some` is omitted if `foo` returns a Maybe or Void
+    pub(super) fn convert_safe_method_call(
+        &mut self,
+        receiver_expr: &AstExpression,
+        method_name: &MethodFirstname,
+        type_args: &[ast::Typ],
+        arg_exprs: &[AstExpression],
+    ) -> Result<HirExpression, Error> {
+        let maybe_hir = self.convert_expr(receiver_expr)?;
+        let maybe_ty = maybe_hir.ty.clone();
+        if maybe_item_ty(&maybe_ty).is_none() {
+            return Err(error::type_error(&format!(
+                "receiver of `?.' must be a Maybe but got {}",
+                maybe_ty
+            )));
+        }
+        let method_tyargs = type_args
+            .iter()
+            .map(|typ| self.convert_typ(typ))
+            .collect::<Vec<_>>();
+        let tmp = self.gensym();
+        let cond_hir = self.maybe_is_some(Hir::assign_lvar(&tmp, maybe_hir))?;
+        let receiver_hir = self.maybe_get(Hir::lvar_ref(maybe_ty, tmp))?;
+        let arg_hirs =
+            self.convert_method_args(&receiver_hir, method_name, &method_tyargs, arg_exprs)?;
+        let call_hir =
+            self.make_method_call(receiver_hir, method_name, &method_tyargs, arg_hirs)?;
+
+        let ret_ty = call_hir.ty.clone();
+        let (ty, then_hir, else_hir) = if ret_ty.is_void_type() {
+            let void = Hir::const_ref(ty::raw("Void"), const_fullname("::Void"));
+            (ret_ty, call_hir, void)
+        } else if maybe_item_ty(&ret_ty).is_some() {
+            (ret_ty, call_hir, none_ref())
+        } else {
+            let ty = ty::spe("Maybe", vec![ret_ty.clone()]);
+            let some_hir = maybe_new(ret_ty, call_hir, true);
+            (ty, some_hir, none_ref())
+        };
+        Ok(Hir::if_expression(
+            ty,
+            cond_hir,
+            Hir::expressions(vec![then_hir]),
+            Some(Hir::expressions(vec![else_hir])),
+        ))
+    }
+
+    /// `maybe || default` is compiled into
+    /// `if (tmp = maybe).is_some then tmp.get else default end`.
+    /// If `default` is also a Maybe, the value is not unwrapped
+    pub(super) fn convert_maybe_or(
+        &mut self,
+        maybe_hir: HirExpression,
+        default_expr: &AstExpression,
+    ) -> Result<HirExpression, Error> {
+        let maybe_ty = maybe_hir.ty.clone();
+        let item_ty = maybe_item_ty(&maybe_ty)
+            .expect("[BUG] convert_maybe_or: not a Maybe")
+            .clone();
+        let default_hir = self.convert_expr(default_expr)?;
+        let default_ty = default_hir.ty.clone();
+        let (ty, unwrap) = if maybe_item_ty(&default_ty).is_some() {
+            match self.wider_type(&maybe_ty, &default_ty) {
+                Some(ty) => (ty, false),
+                None => {
+                    return Err(error::type_error(&format!(
+                        "rhs of `||' must be {} but got {}",
+                        maybe_ty, default_ty
+                    )))
+                }
+            }
+        } else {
+            match self.wider_type(&item_ty, &default_ty) {
+                Some(ty) => (ty, true),
+                None => {
+                    return Err(error::type_error(&format!(
+                        "rhs of `||' must be {} but got {}",
+                        item_ty, default_ty
+                    )))
+                }
+            }
+        };
+
+        let tmp = self.gensym();
+        let cond_hir = self.maybe_is_some(Hir::assign_lvar(&tmp, maybe_hir))?;
+        let value_hir = Hir::lvar_ref(maybe_ty, tmp);
+        let then_hir = if unwrap {
+            self.maybe_get(value_hir)?
+        } else {
+            value_hir
+        };
+        Ok(Hir::if_expression(
+            ty,
+            cond_hir,
+            Hir::expressions(vec![then_hir]),
+            Some(Hir::expressions(vec![default_hir])),
+        ))
+    }
+
+    /// Return the one of the two types which the other conforms to, if any
+    fn wider_type(&self, ty1: &TermTy, ty2: &TermTy) -> Option<TermTy> {
+        if ty2.conforms_to(ty1, &self.class_dict) {
+            Some(ty1.clone())
+        } else if ty1.conforms_to(ty2, &self.class_dict) {
+            Some(ty2.clone())
+        } else {
+            None
+        }
+    }
+
+    /// Generate `maybe.is_some`
+    fn maybe_is_some(&self, maybe_hir: HirExpression) -> Result<HirExpression, Error> {
+        self.make_method_call(maybe_hir, &method_firstname("is_some"), &[], vec![])
+    }
+
+    /// Generate `maybe.get`
+    fn maybe_get(&self, maybe_hir: HirExpression) -> Result<HirExpression, Error> {
+        self.make_method_call(maybe_hir, &method_firstname("get"), &[], vec![])
+    }
+}
+
+/// Return `T` if `ty` is `Maybe<T>`
+pub(super) fn maybe_item_ty(ty: &TermTy) -> Option<&TermTy> {
+    match &ty.body {
+        TyBody::TySpe {
+            base_name,
+            type_args,
+        } if base_name == "Maybe" => type_args.first(),
+        _ => None,
+    }
+}
+
+/// Generate a reference to `None`
+fn none_ref() -> HirExpression {
+    Hir::const_ref(
+        ty::spe("Maybe", vec![ty::raw("Never")]),
+        const_fullname("::None"),
+    )
+}
+
+/// Generate `Maybe<item_ty>.new(value, is_some)`
+fn maybe_new(item_ty: TermTy, value_hir: HirExpression, is_some: bool) -> HirExpression {
+    Hir::method_call(
+        ty::spe("Maybe", vec![item_ty]),
+        Hir::const_ref(ty::meta("Maybe"), const_fullname("::Maybe")),
+        method_fullname(&class_fullname("Meta:Maybe"), "new"),
+        vec![
+            Hir::bit_cast(ty::raw("Object"), value_hir),
+            Hir::boolean_literal(is_some),
+        ],
+    )
+}
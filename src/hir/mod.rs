@@ -1,16 +1,22 @@
 mod accessors;
 pub mod class_dict;
 mod convert_exprs;
+pub mod did_you_mean;
 mod hir_maker;
 mod hir_maker_context;
+mod maybe;
 mod method_dict;
+mod pattern_match;
 pub mod signature;
 mod sk_class;
 use crate::ast;
 use crate::corelib::Corelib;
+use crate::library::LibraryExports;
+use crate::location::LocationSpan;
 use crate::names::*;
 use crate::ty;
 use crate::ty::*;
+use serde::{Deserialize, Serialize};
 pub use sk_class::SkClass;
 use std::collections::HashMap;
 
@@ -22,12 +28,22 @@ pub struct Hir {
     pub str_literals: Vec<String>,
     pub const_inits: Vec<HirExpression>,
     pub main_exprs: HirExpressions,
+    /// Items of the precompiled library (empty unless linked with it)
+    pub imports: LibraryExports,
 }
 
-pub fn build(ast: ast::Program, corelib: Corelib) -> Result<Hir, crate::error::Error> {
+pub fn build(ast: ast::Program, corelib: Corelib) -> Result<Hir, crate::error::Errors> {
     hir_maker::make_hir(ast, corelib)
 }
 
+/// Build Hir of a program which is linked with the precompiled library
+pub fn build_with_library(
+    ast: ast::Program,
+    imports: LibraryExports,
+) -> Result<Hir, crate::error::Errors> {
+    hir_maker::make_hir_with_library(ast, imports)
+}
+
 impl Hir {
     pub fn add_methods(&mut self, sk_methods: HashMap<ClassFullname, Vec<SkMethod>>) {
         for (classname, mut new_methods) in sk_methods {
@@ -43,7 +59,7 @@ impl Hir {
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct SkIVar {
     pub idx: usize,
     pub name: String, // Without `@`
@@ -98,12 +114,40 @@ impl HirExpressions {
 
         HirExpressions { ty, exprs }
     }
+
+    /// Make the value of `self` Void
+    pub fn voidify(mut self) -> HirExpressions {
+        self.exprs
+            .push(Hir::const_ref(ty::raw("Void"), const_fullname("::Void")));
+        self.ty = ty::raw("Void");
+        self
+    }
+
+    /// Change the type of self to `ty` by adding a bitcast to the last expr
+    pub fn bitcast_to(mut self, ty: TermTy) -> HirExpressions {
+        let last_expr = self.exprs.pop().unwrap();
+        self.exprs.push(Hir::bit_cast(ty.clone(), last_expr));
+        self.ty = ty;
+        self
+    }
 }
 
 #[derive(Debug)]
 pub struct HirExpression {
     pub ty: TermTy,
     pub node: HirExpressionBase,
+    /// Location in the source program (default if made by the compiler)
+    pub locs: LocationSpan,
+}
+
+impl HirExpression {
+    /// Returns true if this expression failed to compile
+    pub fn is_error_placeholder(&self) -> bool {
+        match self.node {
+            HirExpressionBase::HirErrorPlaceholder => true,
+            _ => false,
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -127,8 +171,41 @@ pub enum HirExpressionBase {
     HirWhileExpression {
         cond_expr: Box<HirExpression>,
         body_exprs: Box<HirExpressions>,
+        label: Option<String>,
+    },
+    /// `loop do ... end`
+    HirLoopExpression {
+        body_exprs: Box<HirExpressions>,
+        label: Option<String>,
+    },
+    /// Exit the loop of the label (or the innermost one)
+    HirBreakExpression {
+        label: Option<String>,
+        /// The value of the `loop`. None if exiting `while`
+        arg: Option<Box<HirExpression>>,
+    },
+    /// Go to the next iteration of the loop of the label (or the innermost one)
+    HirNextExpression {
+        label: Option<String>,
+    },
+    /// `begin ... rescue => e ... ensure ... end`
+    HirBeginExpression {
+        body_exprs: Box<HirExpressions>,
+        /// Name of the lvar bound to the exception
+        rescue_var: Option<String>,
+        rescue_exprs: Box<Option<HirExpressions>>,
+        ensure_exprs: Box<Option<HirExpressions>>,
+    },
+    /// `match ... when ... end`
+    HirMatchExpression {
+        /// Assigns the value to match to a temporary lvar
+        cond_assign_expr: Box<HirExpression>,
+        clauses: Vec<MatchClause>,
+    },
+    /// Leave the current method (or lambda) with the value
+    HirReturnExpression {
+        arg: Box<HirExpression>,
     },
-    HirBreakExpression,
     HirLVarAssign {
         name: String,
         rhs: Box<HirExpression>,
@@ -148,6 +225,13 @@ pub enum HirExpressionBase {
         method_fullname: MethodFullname,
         arg_exprs: Vec<HirExpression>,
     },
+    /// Call of a method of the superclass (`super`). Not dispatched via the
+    /// vtable
+    HirSuperCall {
+        receiver_expr: Box<HirExpression>,
+        method_fullname: MethodFullname,
+        arg_exprs: Vec<HirExpression>,
+    },
     HirArgRef {
         idx: usize,
     },
@@ -171,11 +255,16 @@ pub enum HirExpressionBase {
     HirArrayLiteral {
         exprs: HirExpressions,
     },
+    /// Evaluate `exprs` in order (the value is the last one.)
+    /// Created by desugaring
+    HirParenthesizedExpr {
+        exprs: HirExpressions,
+    },
     HirFloatLiteral {
         value: f64,
     },
     HirDecimalLiteral {
-        value: i32,
+        value: i64,
     },
     /// A string literal. Its body is stored in str_literals
     HirStringLiteral {
@@ -196,6 +285,12 @@ pub enum HirExpressionBase {
     HirBitCast {
         expr: Box<HirExpression>,
     },
+    /// Whether the object is an instance of the class (instances of its
+    /// subclasses are not counted)
+    HirIsInstanceOf {
+        expr: Box<HirExpression>,
+        classname: ClassFullname,
+    },
     /// A special expression that evaluates to a class
     /// (eg. `class A; end; A = 1` shadows A, but this special expr
     /// is never be shadowed)
@@ -203,6 +298,25 @@ pub enum HirExpressionBase {
         fullname: ClassFullname,
         str_literal_idx: usize,
     },
+    /// Stands for an expression which failed to compile.
+    /// (Used to continue type checking; never reaches code_gen)
+    HirErrorPlaceholder,
+}
+
+/// A clause of `match`
+#[derive(Debug)]
+pub struct MatchClause {
+    /// Evaluated in order. The clause is taken if all the tests passed
+    pub components: Vec<MatchComponent>,
+    pub body_hir: HirExpressions,
+}
+
+#[derive(Debug)]
+pub enum MatchComponent {
+    /// Go to the next clause if this evaluates to false
+    Test(HirExpression),
+    /// Assign the value to the lvar
+    Bind(String, HirExpression),
 }
 
 impl Hir {
@@ -216,6 +330,7 @@ impl Hir {
             node: HirExpressionBase::HirLogicalNot {
                 expr: Box::new(expr_hir),
             },
+            locs: LocationSpan::default(),
         }
     }
 
@@ -226,6 +341,7 @@ impl Hir {
                 left: Box::new(left_hir),
                 right: Box::new(right_hir),
             },
+            locs: LocationSpan::default(),
         }
     }
 
@@ -236,6 +352,7 @@ impl Hir {
                 left: Box::new(left_hir),
                 right: Box::new(right_hir),
             },
+            locs: LocationSpan::default(),
         }
     }
 
@@ -252,23 +369,99 @@ impl Hir {
                 then_exprs: Box::new(then_hir),
                 else_exprs: Box::new(else_hir),
             },
+            locs: LocationSpan::default(),
         }
     }
 
-    pub fn while_expression(cond_hir: HirExpression, body_hirs: HirExpressions) -> HirExpression {
+    pub fn while_expression(
+        cond_hir: HirExpression,
+        body_hirs: HirExpressions,
+        label: Option<String>,
+    ) -> HirExpression {
         HirExpression {
             ty: ty::raw("Void"),
             node: HirExpressionBase::HirWhileExpression {
                 cond_expr: Box::new(cond_hir),
                 body_exprs: Box::new(body_hirs),
+                label,
             },
+            locs: LocationSpan::default(),
         }
     }
 
-    pub fn break_expression() -> HirExpression {
+    pub fn loop_expression(
+        ty: TermTy,
+        body_hirs: HirExpressions,
+        label: Option<String>,
+    ) -> HirExpression {
+        HirExpression {
+            ty,
+            node: HirExpressionBase::HirLoopExpression {
+                body_exprs: Box::new(body_hirs),
+                label,
+            },
+            locs: LocationSpan::default(),
+        }
+    }
+
+    pub fn break_expression(label: Option<String>, arg: Option<HirExpression>) -> HirExpression {
+        HirExpression {
+            ty: ty::raw("Never"),
+            node: HirExpressionBase::HirBreakExpression {
+                label,
+                arg: arg.map(Box::new),
+            },
+            locs: LocationSpan::default(),
+        }
+    }
+
+    pub fn next_expression(label: Option<String>) -> HirExpression {
         HirExpression {
             ty: ty::raw("Never"),
-            node: HirExpressionBase::HirBreakExpression {},
+            node: HirExpressionBase::HirNextExpression { label },
+            locs: LocationSpan::default(),
+        }
+    }
+
+    pub fn begin_expression(
+        ty: TermTy,
+        body_hirs: HirExpressions,
+        rescue_var: Option<String>,
+        rescue_hirs: Option<HirExpressions>,
+        ensure_hirs: Option<HirExpressions>,
+    ) -> HirExpression {
+        HirExpression {
+            ty,
+            node: HirExpressionBase::HirBeginExpression {
+                body_exprs: Box::new(body_hirs),
+                rescue_var,
+                rescue_exprs: Box::new(rescue_hirs),
+                ensure_exprs: Box::new(ensure_hirs),
+            },
+            locs: LocationSpan::default(),
+        }
+    }
+
+    pub fn match_expression(
+        ty: TermTy,
+        cond_assign_hir: HirExpression,
+        clauses: Vec<MatchClause>,
+    ) -> HirExpression {
+        HirExpression {
+            ty,
+            node: HirExpressionBase::HirMatchExpression {
+                cond_assign_expr: Box::new(cond_assign_hir),
+                clauses,
+            },
+            locs: LocationSpan::default(),
+        }
+    }
+
+    pub fn return_expression(arg: HirExpression) -> HirExpression {
+        HirExpression {
+            ty: ty::raw("Never"),
+            node: HirExpressionBase::HirReturnExpression { arg: Box::new(arg) },
+            locs: LocationSpan::default(),
         }
     }
 
@@ -279,6 +472,7 @@ impl Hir {
                 name: name.to_string(),
                 rhs: Box::new(rhs),
             },
+            locs: LocationSpan::default(),
         }
     }
 
@@ -296,6 +490,7 @@ impl Hir {
                 rhs: Box::new(rhs),
                 writable,
             },
+            locs: LocationSpan::default(),
         }
     }
 
@@ -306,6 +501,7 @@ impl Hir {
                 fullname,
                 rhs: Box::new(rhs),
             },
+            locs: LocationSpan::default(),
         }
     }
 
@@ -322,6 +518,24 @@ impl Hir {
                 method_fullname,
                 arg_exprs: arg_hirs,
             },
+            locs: LocationSpan::default(),
+        }
+    }
+
+    pub fn super_call(
+        result_ty: TermTy,
+        receiver_hir: HirExpression,
+        method_fullname: MethodFullname,
+        arg_hirs: Vec<HirExpression>,
+    ) -> HirExpression {
+        HirExpression {
+            ty: result_ty,
+            node: HirExpressionBase::HirSuperCall {
+                receiver_expr: Box::new(receiver_hir),
+                method_fullname,
+                arg_exprs: arg_hirs,
+            },
+            locs: LocationSpan::default(),
         }
     }
 
@@ -330,6 +544,7 @@ impl Hir {
         HirExpression {
             ty,
             node: HirExpressionBase::HirArgRef { idx },
+            locs: LocationSpan::default(),
         }
     }
 
@@ -337,6 +552,7 @@ impl Hir {
         HirExpression {
             ty,
             node: HirExpressionBase::HirLVarRef { name },
+            locs: LocationSpan::default(),
         }
     }
 
@@ -344,6 +560,7 @@ impl Hir {
         HirExpression {
             ty,
             node: HirExpressionBase::HirIVarRef { name, idx },
+            locs: LocationSpan::default(),
         }
     }
 
@@ -351,6 +568,7 @@ impl Hir {
         HirExpression {
             ty,
             node: HirExpressionBase::HirConstRef { fullname },
+            locs: LocationSpan::default(),
         }
     }
 
@@ -374,6 +592,7 @@ impl Hir {
                 exprs,
                 captures_ary: Box::new(captures_ary),
             },
+            locs: LocationSpan::default(),
         }
     }
 
@@ -381,6 +600,7 @@ impl Hir {
         HirExpression {
             ty,
             node: HirExpressionBase::HirSelfExpression,
+            locs: LocationSpan::default(),
         }
     }
 
@@ -390,6 +610,16 @@ impl Hir {
             node: HirExpressionBase::HirArrayLiteral {
                 exprs: HirExpressions::new(exprs),
             },
+            locs: LocationSpan::default(),
+        }
+    }
+
+    pub fn parenthesized_expression(exprs: Vec<HirExpression>) -> HirExpression {
+        let exprs = HirExpressions::new(exprs);
+        HirExpression {
+            ty: exprs.ty.clone(),
+            node: HirExpressionBase::HirParenthesizedExpr { exprs },
+            locs: LocationSpan::default(),
         }
     }
 
@@ -397,13 +627,15 @@ impl Hir {
         HirExpression {
             ty: ty::raw("Float"),
             node: HirExpressionBase::HirFloatLiteral { value },
+            locs: LocationSpan::default(),
         }
     }
 
-    pub fn decimal_literal(value: i32) -> HirExpression {
+    pub fn decimal_literal(value: i64) -> HirExpression {
         HirExpression {
             ty: ty::raw("Int"),
             node: HirExpressionBase::HirDecimalLiteral { value },
+            locs: LocationSpan::default(),
         }
     }
 
@@ -411,6 +643,7 @@ impl Hir {
         HirExpression {
             ty: ty::raw("String"),
             node: HirExpressionBase::HirStringLiteral { idx },
+            locs: LocationSpan::default(),
         }
     }
 
@@ -418,6 +651,7 @@ impl Hir {
         HirExpression {
             ty: ty::raw("Bool"),
             node: HirExpressionBase::HirBooleanLiteral { value },
+            locs: LocationSpan::default(),
         }
     }
 
@@ -427,6 +661,26 @@ impl Hir {
             node: HirExpressionBase::HirBitCast {
                 expr: Box::new(expr),
             },
+            locs: LocationSpan::default(),
+        }
+    }
+
+    pub fn is_instance_of(expr: HirExpression, classname: ClassFullname) -> HirExpression {
+        HirExpression {
+            ty: ty::raw("Bool"),
+            node: HirExpressionBase::HirIsInstanceOf {
+                expr: Box::new(expr),
+                classname,
+            },
+            locs: LocationSpan::default(),
+        }
+    }
+
+    pub fn error_placeholder() -> HirExpression {
+        HirExpression {
+            ty: ty::raw("Never"),
+            node: HirExpressionBase::HirErrorPlaceholder,
+            locs: LocationSpan::default(),
         }
     }
 
@@ -437,6 +691,7 @@ impl Hir {
                 fullname,
                 str_literal_idx,
             },
+            locs: LocationSpan::default(),
         }
     }
 
@@ -444,6 +699,7 @@ impl Hir {
         HirExpression {
             ty,
             node: HirExpressionBase::HirLambdaCaptureRef { idx },
+            locs: LocationSpan::default(),
         }
     }
 }
@@ -452,5 +708,5 @@ fn lambda_ty(params: &Vec<MethodParam>, ret_ty: &TermTy) -> TermTy {
     let i = params.len();
     let mut tyargs = params.iter().map(|x| x.ty.clone()).collect::<Vec<_>>();
     tyargs.push(ret_ty.clone());
-    ty::spe(&format!("Fn{}", i), tyargs)
+    ty::spe(&fn_class_fullname(i).0, tyargs)
 }
@@ -0,0 +1,322 @@
+//! Pattern matching (`match`)
+//!
+//! A `match` is compiled into a list of clauses. Each clause consists of
+//! tests (eg. "the value is an instance of `Shape::Circle`") and bindings
+//! (eg. "assign the field `radius` to `r`"), which are evaluated in order.
+use crate::ast::*;
+use crate::error;
+use crate::error::Error;
+use crate::hir::hir_maker::HirMaker;
+use crate::hir::*;
+use crate::type_checking;
+use std::iter::once;
+
+/// Simplified pattern used for the exhaustiveness check
+#[derive(Debug, Clone)]
+enum Pat {
+    /// Matches anything (`_` or a variable)
+    Any,
+    /// Matches a case of an enum
+    Case(ClassFullname, Vec<Pat>),
+    /// Other patterns (eg. literals). Not considered to cover anything
+    Other,
+}
+
+impl HirMaker {
+    pub(super) fn convert_match_expr(
+        &mut self,
+        cond_expr: &AstExpression,
+        clauses: &[AstMatchClause],
+    ) -> Result<HirExpression, Error> {
+        let cond_hir = self.convert_expr(cond_expr)?;
+        let value_ty = cond_hir.ty.clone();
+        let tmp = self.gensym();
+        let cond_assign_hir = Hir::assign_lvar(&tmp, cond_hir);
+
+        let mut hir_clauses = vec![];
+        // Patterns of the clauses without guards
+        let mut rows = vec![];
+        for clause in clauses {
+            let mut components = vec![];
+            let body_hir =
+                self.convert_match_clause(clause, &tmp, &value_ty, &mut components, &mut rows);
+            // The variables bound by the pattern are only visible in the clause
+            self.undeclare_pattern_lvars(&components);
+            let body_hir = body_hir?;
+            hir_clauses.push(MatchClause {
+                components,
+                body_hir,
+            });
+        }
+        if let Some(missing) = self.missing_pattern(&rows, &[value_ty]) {
+            return Err(error::program_error(&format!(
+                "match is not exhaustive; missing `{}'",
+                missing[0]
+            )));
+        }
+
+        let ty = self.match_result_ty(&hir_clauses);
+        Ok(Hir::match_expression(ty, cond_assign_hir, hir_clauses))
+    }
+
+    /// Convert the pattern, the guard and the body of a clause. The pattern
+    /// is added to `rows` if the clause has no guard
+    fn convert_match_clause(
+        &mut self,
+        clause: &AstMatchClause,
+        lvar_name: &str,
+        value_ty: &TermTy,
+        components: &mut Vec<MatchComponent>,
+        rows: &mut Vec<Vec<Pat>>,
+    ) -> Result<HirExpressions, Error> {
+        let pat = self.convert_pattern(&clause.pattern, lvar_name, value_ty, components)?;
+        match &clause.guard {
+            Some(guard) => {
+                let guard_hir = self.convert_expr(guard)?;
+                type_checking::check_condition_ty(&guard_hir.ty, "guard")?;
+                components.push(MatchComponent::Test(guard_hir));
+            }
+            None => rows.push(vec![pat]),
+        }
+        self.convert_exprs(&clause.body_exprs)
+    }
+
+    /// Make the variables bound by a pattern invisible
+    fn undeclare_pattern_lvars(&mut self, components: &[MatchComponent]) {
+        for component in components {
+            if let MatchComponent::Bind(storage, _) = component {
+                self.ctx_mut().lvars.retain(|_, lvar| lvar.name != *storage);
+            }
+        }
+    }
+
+    /// Convert a pattern which matches the value of the lvar `lvar_name` into
+    /// `MatchComponent`s
+    fn convert_pattern(
+        &mut self,
+        pattern: &AstPattern,
+        lvar_name: &str,
+        ty: &TermTy,
+        components: &mut Vec<MatchComponent>,
+    ) -> Result<Pat, Error> {
+        let value = Hir::lvar_ref(ty.clone(), lvar_name.to_string());
+        match pattern {
+            AstPattern::WildcardPattern => Ok(Pat::Any),
+            AstPattern::VariablePattern(name) => {
+                let storage = self.declare_scoped_lvar(name, ty.clone())?;
+                components.push(MatchComponent::Bind(storage, value));
+                Ok(Pat::Any)
+            }
+            AstPattern::LiteralPattern(expr) => {
+                let literal_hir = self.convert_expr(expr)?;
+                let test_hir =
+                    self.make_method_call(value, &method_firstname("=="), &[], vec![literal_hir])?;
+                type_checking::check_condition_ty(&test_hir.ty, "pattern")?;
+                components.push(MatchComponent::Test(test_hir));
+                Ok(Pat::Other)
+            }
+            AstPattern::ExtractorPattern { names, params } => {
+                self.convert_extractor_pattern(names, params, lvar_name, ty, components)
+            }
+        }
+    }
+
+    /// Convert a pattern like `Shape::Rect(w, h)`
+    fn convert_extractor_pattern(
+        &mut self,
+        names: &[String],
+        params: &[AstPattern],
+        lvar_name: &str,
+        ty: &TermTy,
+        components: &mut Vec<MatchComponent>,
+    ) -> Result<Pat, Error> {
+        let case_name = self.resolve_enum_case(names, ty)?;
+        let case_ty = case_name.instance_ty();
+        if !case_ty.conforms_to(ty, &self.class_dict) {
+            return Err(error::type_error(&format!(
+                "pattern `{}' never matches a value of {}",
+                case_name, ty.fullname
+            )));
+        }
+        let fields = self.enum_case_fields(&case_name);
+        // `Shape::Rect` is the same as `Shape::Rect(_, _)`
+        if !params.is_empty() && params.len() != fields.len() {
+            return Err(error::program_error(&format!(
+                "`{}' has {} field(s) but {} pattern(s) are given",
+                case_name,
+                fields.len(),
+                params.len()
+            )));
+        }
+
+        let value = Hir::lvar_ref(ty.clone(), lvar_name.to_string());
+        components.push(MatchComponent::Test(Hir::is_instance_of(
+            value,
+            case_name.clone(),
+        )));
+        let mut pats = vec![];
+        for (i, field) in fields.iter().enumerate() {
+            let param = match params.get(i) {
+                None | Some(AstPattern::WildcardPattern) => {
+                    pats.push(Pat::Any);
+                    continue;
+                }
+                Some(p) => p,
+            };
+            // Assign the field to a temporary lvar and match it with `param`
+            let field_tmp = self.gensym();
+            let receiver = Hir::bit_cast(
+                case_ty.clone(),
+                Hir::lvar_ref(ty.clone(), lvar_name.to_string()),
+            );
+            let getter = Hir::method_call(
+                field.ty.clone(),
+                receiver,
+                method_fullname(&case_name, &field.name),
+                vec![],
+            );
+            components.push(MatchComponent::Bind(field_tmp.clone(), getter));
+            pats.push(self.convert_pattern(param, &field_tmp, &field.ty, components)?);
+        }
+        Ok(Pat::Case(case_name, pats))
+    }
+
+    /// Find the enum case named by the pattern. `Circle` is resolved as
+    /// `Shape::Circle` if the value is a `Shape`
+    fn resolve_enum_case(&self, names: &[String], ty: &TermTy) -> Result<ClassFullname, Error> {
+        let name = names.join("::");
+        let mut candidates = vec![];
+        if let Some(enum_name) = self.enum_of(ty) {
+            candidates.push(class_fullname(format!("{}::{}", enum_name, name)));
+        }
+        candidates.push(class_fullname(&name));
+        candidates
+            .into_iter()
+            .find(|classname| {
+                self.class_dict
+                    .find_class(classname)
+                    .map_or(false, |sk_class| {
+                        sk_class.enum_cases.is_none()
+                            && self.enum_of(&sk_class.instance_ty).is_some()
+                    })
+            })
+            .ok_or_else(|| error::program_error(&format!("enum case `{}' was not found", name)))
+    }
+
+    /// Return the enum if `ty` is an enum or a case of an enum
+    fn enum_of(&self, ty: &TermTy) -> Option<ClassFullname> {
+        let sk_class = self.class_dict.find_class(&ty.fullname)?;
+        if sk_class.enum_cases.is_some() {
+            return Some(sk_class.fullname.clone());
+        }
+        let super_name = sk_class.superclass_fullname.as_ref()?;
+        let super_class = self.class_dict.find_class(super_name)?;
+        match &super_class.enum_cases {
+            Some(cases) if cases.contains(&sk_class.fullname) => Some(super_name.clone()),
+            _ => None,
+        }
+    }
+
+    /// Return the cases a value of `ty` can be (None if `ty` is not an enum)
+    fn enum_cases(&self, ty: &TermTy) -> Option<Vec<ClassFullname>> {
+        let enum_name = self.enum_of(ty)?;
+        if enum_name == ty.fullname {
+            self.class_dict
+                .get_class(&enum_name, "enum_cases")
+                .enum_cases
+                .clone()
+        } else {
+            Some(vec![ty.fullname.clone()])
+        }
+    }
+
+    /// Return the fields of the enum case (i.e. the parameters of its
+    /// initializer)
+    fn enum_case_fields(&self, case_name: &ClassFullname) -> Vec<MethodParam> {
+        self.class_dict
+            .find_method(case_name, &method_firstname("initialize"))
+            .map_or(vec![], |sig| sig.params.clone())
+    }
+
+    /// Return a list of patterns which is not covered by `rows`, if any.
+    /// `tys` are the types of the columns
+    fn missing_pattern(&self, rows: &[Vec<Pat>], tys: &[TermTy]) -> Option<Vec<String>> {
+        if rows.is_empty() {
+            return Some(vec!["_".to_string(); tys.len()]);
+        }
+        let (ty, rest_tys) = match tys.split_first() {
+            Some(x) => x,
+            // There is a row which matches anything
+            None => return None,
+        };
+        match self.enum_cases(ty) {
+            Some(cases) => cases.iter().find_map(|case| {
+                let field_tys = self
+                    .enum_case_fields(case)
+                    .into_iter()
+                    .map(|param| param.ty)
+                    .collect::<Vec<_>>();
+                let n = field_tys.len();
+                // Rows which match `case`, with the first column expanded to its fields
+                let specialized = rows
+                    .iter()
+                    .filter_map(|row| match &row[0] {
+                        Pat::Any => Some(
+                            vec![Pat::Any; n]
+                                .into_iter()
+                                .chain(row[1..].iter().cloned())
+                                .collect(),
+                        ),
+                        Pat::Case(name, pats) if name == case => {
+                            Some(pats.iter().chain(row[1..].iter()).cloned().collect())
+                        }
+                        _ => None,
+                    })
+                    .collect::<Vec<Vec<_>>>();
+                let tys = field_tys
+                    .into_iter()
+                    .chain(rest_tys.iter().cloned())
+                    .collect::<Vec<_>>();
+                self.missing_pattern(&specialized, &tys).map(|mut missing| {
+                    let rest = missing.split_off(n);
+                    let pat = if n == 0 {
+                        case.0.clone()
+                    } else {
+                        format!("{}({})", case, missing.join(", "))
+                    };
+                    once(pat).chain(rest).collect()
+                })
+            }),
+            None => {
+                let rest_rows = rows
+                    .iter()
+                    .filter_map(|row| match row[0] {
+                        Pat::Any => Some(row[1..].to_vec()),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>();
+                self.missing_pattern(&rest_rows, rest_tys)
+                    .map(|missing| once("_".to_string()).chain(missing).collect())
+            }
+        }
+    }
+
+    /// Return the type of `match` (the clauses which does not return, eg.
+    /// ends with `return`, are not counted)
+    fn match_result_ty(&self, clauses: &[MatchClause]) -> TermTy {
+        let tys = clauses
+            .iter()
+            .map(|clause| &clause.body_hir.ty)
+            .filter(|ty| !ty.is_never_type())
+            .collect::<Vec<_>>();
+        if tys.is_empty() {
+            ty::raw("Never")
+        } else if tys.iter().any(|ty| ty.is_void_type()) {
+            ty::raw("Void")
+        } else {
+            tys[1..].iter().fold(tys[0].clone(), |acc, ty| {
+                self.nearest_common_ancestor_type(&acc, ty)
+            })
+        }
+    }
+}
@@ -10,44 +10,73 @@ pub fn create_signature(
     typarams: &[String],
 ) -> MethodSignature {
     let fullname = method_fullname(class_fullname, &sig.name.0);
-    let ret_ty = convert_typ(&sig.ret_typ, typarams);
-    let params = convert_params(&sig.params, typarams);
+    let ret_ty = convert_typ(&sig.ret_typ, typarams, &sig.typarams);
+    let params = convert_params(&sig.params, typarams, &sig.typarams);
     MethodSignature {
         fullname,
         ret_ty,
         params,
+        typarams: sig
+            .typarams
+            .iter()
+            .map(|name| TyParam { name: name.clone() })
+            .collect(),
     }
 }
 
 // TODO: pass the list of visible classes
-fn convert_typ(typ: &ast::Typ, typarams: &[String]) -> TermTy {
-    let found = typarams.iter().enumerate().find(|(_, s)| **s == typ.name);
-    if let Some((idx, _)) = found {
-        ty::typaram(&typ.name, idx)
+pub fn convert_typ(
+    typ: &ast::Typ,
+    class_typarams: &[String],
+    method_typarams: &[String],
+) -> TermTy {
+    // Method typarams shadow class typarams of the same name
+    if let Some(idx) = method_typarams.iter().position(|s| *s == typ.name) {
+        return ty::typaram(&typ.name, TyParamKind::Method, idx);
+    }
+    if let Some(idx) = class_typarams.iter().position(|s| *s == typ.name) {
+        return ty::typaram(&typ.name, TyParamKind::Class, idx);
+    }
+    if typ.typ_args.is_empty() {
+        ty::raw(&typ.name)
     } else {
-        if typ.typ_args.len() == 0 {
-            ty::raw(&typ.name)
-        } else {
-            let tyargs = typ
-                .typ_args
-                .iter()
-                .map(|t| convert_typ(t, typarams))
-                .collect();
-            ty::spe(&typ.name, tyargs)
-        }
+        let tyargs = typ
+            .typ_args
+            .iter()
+            .map(|t| convert_typ(t, class_typarams, method_typarams))
+            .collect();
+        ty::spe(&typ.name, tyargs)
     }
 }
 
-pub fn convert_params(params: &[ast::Param], typarams: &[String]) -> Vec<MethodParam> {
+pub fn convert_params(
+    params: &[ast::Param],
+    class_typarams: &[String],
+    method_typarams: &[String],
+) -> Vec<MethodParam> {
     params
         .iter()
         .map(|param| MethodParam {
             name: param.name.to_string(),
-            ty: convert_typ(&param.typ, typarams),
+            ty: convert_typ(&param.typ, class_typarams, method_typarams),
         })
         .collect()
 }
 
+/// Signature of the copy of a module's `initialize` compiled for a class
+/// (eg. `A#initialize@M`)
+pub fn signature_of_module_initializer(
+    class_fullname: &ClassFullname,
+    module_fullname: &ClassFullname,
+) -> MethodSignature {
+    MethodSignature {
+        fullname: method_fullname(class_fullname, &format!("initialize@{}", module_fullname)),
+        ret_ty: ty::raw("Void"),
+        params: vec![],
+        typarams: vec![],
+    }
+}
+
 pub fn signature_of_new(
     metaclass_fullname: &ClassFullname,
     initialize_params: Vec<MethodParam>,
@@ -57,5 +86,6 @@ pub fn signature_of_new(
         fullname: method_fullname(metaclass_fullname, "new"),
         ret_ty: instance_ty.clone(),
         params: initialize_params,
+        typarams: vec![],
     }
 }
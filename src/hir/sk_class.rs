@@ -1,9 +1,10 @@
 use crate::names::*;
 use crate::ty::*;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 /// A Shiika class, possibly generic
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct SkClass {
     pub fullname: ClassFullname,
     pub typarams: Vec<TyParam>,
@@ -11,8 +12,14 @@ pub struct SkClass {
     pub instance_ty: TermTy,
     pub ivars: HashMap<String, super::SkIVar>,
     pub method_sigs: HashMap<MethodFirstname, MethodSignature>,
+    /// True if this is a module (cannot be instantiated)
+    pub is_module: bool,
+    /// Modules included into this class (for a metaclass, modules `extend`ed)
+    pub includes: Vec<ClassFullname>,
     /// eg. `Void` is an instance, not the class
     pub const_is_obj: bool,
+    /// Cases of the enum (None if this is not an enum)
+    pub enum_cases: Option<Vec<ClassFullname>>,
 }
 
 impl SkClass {
@@ -4,6 +4,9 @@ pub mod code_gen;
 pub mod corelib;
 pub mod error;
 pub mod hir;
+pub mod library;
+pub mod loader;
+pub mod location;
 pub mod names;
 pub mod parser;
 pub mod runner;
@@ -0,0 +1,66 @@
+//! Precompiled library
+//!
+//! `shiika build-corelib` compiles builtin/*.sk and corelib once into an
+//! object file and writes the classes, methods and constants defined in it
+//! to `exports.json`. Programs compiled against the library only declare
+//! these items and are linked with the object file.
+use crate::error::*;
+use crate::hir::*;
+use crate::names::*;
+use crate::ty::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// Items defined in a library
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct LibraryExports {
+    /// Identifies the compiler (and builtin/*.sk) which built the library
+    pub fingerprint: String,
+    pub sk_classes: HashMap<ClassFullname, SkClass>,
+    /// Signatures of the methods which have llvm functions in the library
+    pub sk_methods: HashMap<ClassFullname, Vec<MethodSignature>>,
+    pub constants: HashMap<ConstFullname, TermTy>,
+}
+
+impl LibraryExports {
+    /// Collect the items defined in `hir`
+    pub fn new(hir: &Hir, fingerprint: impl Into<String>) -> LibraryExports {
+        let sk_methods = hir
+            .sk_methods
+            .iter()
+            .map(|(classname, methods)| {
+                let sigs = methods.iter().map(|m| m.signature.clone()).collect();
+                (classname.clone(), sigs)
+            })
+            .collect();
+        LibraryExports {
+            fingerprint: fingerprint.into(),
+            sk_classes: hir.sk_classes.clone(),
+            sk_methods,
+            constants: hir.constants.clone(),
+        }
+    }
+
+    /// Returns true if this has nothing (i.e. not linked with a library)
+    pub fn is_empty(&self) -> bool {
+        self.sk_classes.is_empty()
+    }
+
+    /// Read exports.json
+    pub fn load(path: &Path) -> Result<LibraryExports, Error> {
+        let json = fs::read_to_string(path)
+            .map_err(|e| runner_error(format!("failed to read {}", path.display()), e))?;
+        serde_json::from_str(&json)
+            .map_err(|e| runner_error(format!("broken library index {}", path.display()), e))
+    }
+
+    /// Write exports.json
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        let json = serde_json::to_string(self)
+            .map_err(|e| runner_error("failed to serialize library index", e))?;
+        fs::write(path, json)
+            .map_err(|e| runner_error(format!("failed to write {}", path.display()), e))
+    }
+}
@@ -0,0 +1,106 @@
+//! Loads a program which consists of multiple source files
+//!
+//! `require "foo"` loads `foo.sk`, searching the directory of the
+//! requiring file first, and then the search paths.
+//! Each file is loaded only once.
+use crate::ast;
+use crate::error;
+use crate::error::{Error, Errors};
+use crate::location::SourceFile;
+use crate::parser::Parser;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+#[derive(Debug)]
+pub struct Loader {
+    /// Directories to search for `require`d files
+    search_paths: Vec<PathBuf>,
+    /// Files already loaded (canonicalized)
+    loaded: HashSet<PathBuf>,
+    /// Items of the loaded files
+    toplevel_items: Vec<ast::TopLevelItem>,
+    /// Arities of the functions used in the loaded files
+    fn_arities: HashSet<usize>,
+    /// Errors found so far
+    errors: Vec<Error>,
+}
+
+impl Loader {
+    pub fn new(search_paths: Vec<PathBuf>) -> Loader {
+        Loader {
+            search_paths,
+            loaded: HashSet::new(),
+            toplevel_items: vec![],
+            fn_arities: HashSet::new(),
+            errors: vec![],
+        }
+    }
+
+    /// Load the file at `path` (and the files it requires)
+    pub fn load(&mut self, path: &Path) -> Result<(), Error> {
+        let canonical = path
+            .canonicalize()
+            .map_err(|e| error::runner_error(format!("failed to load {}", path.display()), e))?;
+        if !self.loaded.insert(canonical) {
+            return Ok(());
+        }
+        let src = fs::read_to_string(path)
+            .map_err(|e| error::runner_error(format!("{} is not utf8", path.display()), e))?;
+        self.add_file(Rc::new(SourceFile::new(path, src)));
+        Ok(())
+    }
+
+    /// Parse the file and load the files it requires.
+    /// The required files come before the requiring file in the program
+    pub fn add_file(&mut self, file: Rc<SourceFile>) {
+        let program = match Parser::parse_file(&file) {
+            Ok(program) => program,
+            Err(errs) => {
+                self.errors.extend(errs.0);
+                return;
+            }
+        };
+        for req in &program.requires {
+            let result = match self.resolve(&file.path, &req.path) {
+                Some(path) => self.load(&path),
+                None => Err(error::program_error(&format!(
+                    "cannot find `{}' to require",
+                    req.path
+                ))),
+            };
+            if let Err(e) = result {
+                self.errors.push(e.with_location(&req.locs));
+            }
+        }
+        self.toplevel_items.extend(program.toplevel_items);
+        self.fn_arities.extend(program.fn_arities);
+    }
+
+    /// Return the program consists of the loaded files
+    pub fn finish(self) -> Result<ast::Program, Errors> {
+        if !self.errors.is_empty() {
+            return Err(Errors(self.errors));
+        }
+        Ok(ast::Program {
+            toplevel_items: self.toplevel_items,
+            requires: vec![],
+            fn_arities: self.fn_arities,
+        })
+    }
+
+    /// Find the file for `require "name"` in `from`
+    fn resolve(&self, from: &Path, name: &str) -> Option<PathBuf> {
+        let filename = if name.ends_with(".sk") {
+            name.to_string()
+        } else {
+            format!("{}.sk", name)
+        };
+        let base_dir = from.parent().unwrap_or_else(|| Path::new(""));
+        std::iter::once(base_dir)
+            .chain(self.search_paths.iter().map(|p| p.as_path()))
+            .map(|dir| dir.join(&filename))
+            .find(|path| path.is_file())
+    }
+}
@@ -0,0 +1,72 @@
+use crate::parser::lexer::Cursor;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+/// A source file given to the compiler
+#[derive(Debug, PartialEq)]
+pub struct SourceFile {
+    pub path: PathBuf,
+    pub content: String,
+}
+
+impl SourceFile {
+    pub fn new(path: impl Into<PathBuf>, content: String) -> SourceFile {
+        SourceFile {
+            path: path.into(),
+            content,
+        }
+    }
+}
+
+/// Range of a source program (`end` is exclusive)
+#[derive(Clone, Default, PartialEq)]
+pub struct LocationSpan {
+    pub file: Option<Rc<SourceFile>>,
+    pub begin: Cursor,
+    pub end: Cursor,
+}
+
+impl LocationSpan {
+    pub fn new(file: &Option<Rc<SourceFile>>, begin: Cursor, end: Cursor) -> LocationSpan {
+        LocationSpan {
+            file: file.clone(),
+            begin,
+            end,
+        }
+    }
+
+    /// Returns the path of the source file (or "(unknown)")
+    pub fn path(&self) -> String {
+        match &self.file {
+            Some(f) => f.path.to_string_lossy().to_string(),
+            None => "(unknown)".to_string(),
+        }
+    }
+}
+
+// Print only the positions because printing the whole source is too verbose
+impl std::fmt::Debug for LocationSpan {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "LocationSpan({}:{}-{}:{})",
+            self.begin.line(),
+            self.begin.col(),
+            self.end.line(),
+            self.end.col()
+        )
+    }
+}
+
+/// Prints `path:line:col` (1-origin)
+impl std::fmt::Display for LocationSpan {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}",
+            self.path(),
+            self.begin.line() + 1,
+            self.begin.col() + 1
+        )
+    }
+}
@@ -1,21 +1,46 @@
 use shiika::runner;
+use std::path::Path;
 #[macro_use]
 extern crate clap;
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
+fn main() {
+    if let Err(err) = main_() {
+        report_error(err);
+        std::process::exit(1);
+    }
+}
+
+fn main_() -> Result<(), Box<dyn std::error::Error>> {
     let yaml = load_yaml!("cli.yml");
     let matches = clap::App::from(yaml).get_matches();
 
     if let Some(ref matches) = matches.subcommand_matches("compile") {
         let filepath = matches.value_of("INPUT").unwrap();
-        runner::compile(filepath)?;
+        let builtin_dir = matches.value_of("builtin-dir").map(Path::new);
+        runner::compile_with_builtin_dir(filepath, builtin_dir)?;
     }
 
     if let Some(ref matches) = matches.subcommand_matches("run") {
         let filepath = matches.value_of("INPUT").unwrap();
-        runner::compile(filepath)?;
-        runner::run(filepath)?;
+        let builtin_dir = matches.value_of("builtin-dir").map(Path::new);
+        runner::compile_with_builtin_dir(filepath, builtin_dir)?;
+        runner::run_with_builtin_dir(filepath, builtin_dir)?;
+    }
+
+    if matches.subcommand_matches("build-corelib").is_some() {
+        runner::build_corelib()?;
     }
 
     Ok(())
 }
+
+/// Print the error in a human-readable form
+fn report_error(err: Box<dyn std::error::Error>) {
+    if let Some(errs) = err.downcast_ref::<shiika::error::Errors>() {
+        eprint!("{}", errs.render());
+    } else if let Some(e) = err.downcast_ref::<shiika::error::Error>() {
+        eprint!("{}", e.render());
+    } else {
+        eprintln!("Error: {}", err);
+    }
+}
@@ -1,5 +1,6 @@
 use crate::ty;
 use crate::ty::*;
+use serde::{Deserialize, Serialize};
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct ClassFirstname(pub String);
@@ -18,7 +19,7 @@ pub fn class_firstname(s: &str) -> ClassFirstname {
     ClassFirstname(s.to_string())
 }
 
-#[derive(Debug, PartialEq, Clone, Eq, Hash)]
+#[derive(Debug, PartialEq, Clone, Eq, Hash, Serialize, Deserialize)]
 pub struct ClassFullname(pub String);
 
 impl std::fmt::Display for ClassFullname {
@@ -35,6 +36,21 @@ pub fn metaclass_fullname(base: &str) -> ClassFullname {
     ClassFullname("Meta:".to_string() + base)
 }
 
+/// Name of the class of the functions which take `arity` parameters (eg. `Fn2`)
+pub fn fn_class_fullname(arity: usize) -> ClassFullname {
+    ClassFullname(format!("Fn{}", arity))
+}
+
+/// Return `n` if `name` is `Fn<n>`
+pub fn fn_class_arity(name: &str) -> Option<usize> {
+    let digits = name.strip_prefix("Fn")?;
+    if !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit()) {
+        digits.parse().ok()
+    } else {
+        None
+    }
+}
+
 impl ClassFullname {
     pub fn instance_ty(&self) -> TermTy {
         ty::raw(&self.0)
@@ -63,7 +79,7 @@ impl ClassFullname {
     }
 }
 
-#[derive(Debug, PartialEq, Clone, Eq, Hash)]
+#[derive(Debug, PartialEq, Clone, Eq, Hash, Serialize, Deserialize)]
 pub struct MethodFirstname(pub String);
 
 impl std::fmt::Display for MethodFirstname {
@@ -82,7 +98,7 @@ impl MethodFirstname {
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct MethodFullname {
     pub full_name: String,
     pub first_name: MethodFirstname,
@@ -95,6 +111,14 @@ pub fn method_fullname(class_name: &ClassFullname, first_name: &str) -> MethodFu
     }
 }
 
+impl MethodFullname {
+    /// Returns the name of the class which has this method
+    pub fn class_name(&self) -> ClassFullname {
+        let len = self.full_name.len() - self.first_name.0.len() - 1;
+        ClassFullname(self.full_name[..len].to_string())
+    }
+}
+
 impl std::fmt::Display for MethodFullname {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         write!(f, "{}", self.full_name)
@@ -114,7 +138,7 @@ pub fn const_firstname(s: &str) -> ConstFirstname {
     ConstFirstname(s.to_string())
 }
 
-#[derive(Debug, PartialEq, Clone, Eq, Hash)]
+#[derive(Debug, PartialEq, Clone, Eq, Hash, Serialize, Deserialize)]
 pub struct ConstFullname(pub String);
 
 impl std::fmt::Display for ConstFullname {
@@ -2,6 +2,7 @@
 pub use crate::ast;
 pub use crate::ast::*;
 pub use crate::error::*;
+pub use crate::location::LocationSpan;
 pub use crate::parser::lexer;
 pub use crate::parser::lexer::*;
 pub use crate::parser::token::Token;
@@ -56,7 +57,11 @@ impl<'a> Parser<'a> {
     pub(super) fn consume_token(&mut self) -> Token {
         let tok = self.current_token();
         self.debug_log(&format!("consume_token {:?}", &tok));
-        self.lexer.consume_token()
+        let tok = self.lexer.consume_token();
+        if tok != Token::Space && tok != Token::Separator {
+            self.last_token_end = self.lexer.cur.clone();
+        }
+        tok
     }
 
     /// Consume the current token if it equals to `token`.
@@ -106,6 +111,11 @@ impl<'a> Parser<'a> {
         self.lexer.set_position(cur);
     }
 
+    /// Returns the span from `begin` to the end of the last token
+    pub(super) fn span_from(&self, begin: &Cursor) -> LocationSpan {
+        LocationSpan::new(&self.file, begin.clone(), self.last_token_end.clone())
+    }
+
     pub(super) fn set_lexer_state(&mut self, state: LexerState) {
         self.lexer.set_state(state);
     }
@@ -118,7 +128,36 @@ impl<'a> Parser<'a> {
                 location: self.lexer.cur.clone(),
             },
             source: None,
+            location: Some(LocationSpan::new(
+                &self.file,
+                self.lexer.cur.clone(),
+                self.lexer.cur.clone(),
+            )),
+            notes: vec![],
+        }
+    }
+
+    /// Record `err` and skip to the next line (or `end`) to continue parsing.
+    /// Returns `err` if cannot recover (on EOF or too many errors)
+    pub(super) fn recover(&mut self, err: Error) -> Result<(), Error> {
+        if self.errors.len() + 1 >= MAX_ERRORS {
+            return Err(err);
         }
+        loop {
+            match self.current_token() {
+                Token::Eof => return Err(err),
+                Token::Separator => {
+                    self.consume_token();
+                    break;
+                }
+                Token::KwEnd => break,
+                _ => {
+                    self.consume_token();
+                }
+            }
+        }
+        self.errors.push(err);
+        Ok(())
     }
 
     /// Print parser debug log (uncomment to enable)
@@ -16,6 +16,9 @@ impl<'a> Parser<'a> {
     fn parse_definition(&mut self) -> Result<Option<ast::Definition>, Error> {
         match self.current_token() {
             Token::KwClass => Ok(Some(self.parse_class_definition()?)),
+            Token::KwModule => Ok(Some(self.parse_module_definition()?)),
+            Token::KwEnum => Ok(Some(self.parse_enum_definition()?)),
+            Token::KwInclude | Token::KwExtend => Ok(Some(self.parse_mixin_definition()?)),
             Token::KwDef => Ok(Some(self.parse_method_definition()?)),
             Token::UpperWord(_) => Ok(Some(self.parse_const_definition()?)),
             _ => Ok(None),
@@ -25,6 +28,7 @@ impl<'a> Parser<'a> {
     pub fn parse_class_definition(&mut self) -> Result<ast::Definition, Error> {
         self.debug_log("parse_class_definition");
         self.lv += 1;
+        let begin = self.current_position();
         let name;
         let mut typarams = vec![];
         let defs;
@@ -50,32 +54,7 @@ impl<'a> Parser<'a> {
 
         // Type parameters (optional)
         if self.current_token_is(Token::LessThan) {
-            self.consume_token();
-            self.skip_wsn();
-            loop {
-                match self.current_token() {
-                    Token::GreaterThan => {
-                        self.consume_token();
-                        break;
-                    }
-                    Token::UpperWord(s) => {
-                        typarams.push(s.to_string());
-                        self.consume_token();
-                        self.skip_wsn();
-                    }
-                    Token::Comma => {
-                        self.consume_token();
-                        self.skip_wsn();
-                    }
-                    token => {
-                        return Err(parse_error!(
-                            self,
-                            "unexpected token `{:?}' in type parameter definition",
-                            token
-                        ))
-                    }
-                }
-            }
+            typarams = self.parse_typarams()?;
         }
 
         // Superclass name (optional)
@@ -86,8 +65,21 @@ impl<'a> Parser<'a> {
             self.skip_wsn();
             match self.current_token() {
                 Token::UpperWord(s) => {
-                    super_name = class_fullname(s);
+                    let mut names = vec![s.to_string()];
                     self.consume_token();
+                    // eg. `Foo::Bar`
+                    while self.consume(Token::ColonColon) {
+                        match self.current_token() {
+                            Token::UpperWord(s) => {
+                                names.push(s.to_string());
+                                self.consume_token();
+                            }
+                            token => {
+                                return Err(parse_error!(self, "unexpected token: {:?}", token))
+                            }
+                        }
+                    }
+                    super_name = class_fullname(&names.join("::"));
                 }
                 token => {
                     return Err(parse_error!(
@@ -125,12 +117,216 @@ impl<'a> Parser<'a> {
             typarams,
             super_name,
             defs,
+            locs: self.span_from(&begin),
+        })
+    }
+
+    pub fn parse_module_definition(&mut self) -> Result<ast::Definition, Error> {
+        self.debug_log("parse_module_definition");
+        self.lv += 1;
+        let begin = self.current_position();
+        let name;
+
+        // `module'
+        assert!(self.consume(Token::KwModule));
+        self.skip_ws();
+
+        // Module name
+        match self.current_token() {
+            Token::UpperWord(s) => {
+                name = class_firstname(s);
+                self.consume_token();
+            }
+            token => {
+                return Err(parse_error!(
+                    self,
+                    "module name must start with A-Z but got {:?}",
+                    token
+                ))
+            }
+        }
+        self.expect_sep()?;
+
+        // Internal definitions
+        let defs = self.parse_definitions()?;
+
+        // `end'
+        match self.current_token() {
+            Token::KwEnd => {
+                self.consume_token();
+            }
+            token => {
+                return Err(parse_error!(
+                    self,
+                    "missing `end' for module {:?}; got {:?}",
+                    name,
+                    token
+                ))
+            }
+        }
+
+        self.lv -= 1;
+        Ok(ast::Definition::ModuleDefinition {
+            name,
+            defs,
+            locs: self.span_from(&begin),
+        })
+    }
+
+    pub fn parse_enum_definition(&mut self) -> Result<ast::Definition, Error> {
+        self.debug_log("parse_enum_definition");
+        self.lv += 1;
+        let begin = self.current_position();
+        let name;
+
+        // `enum'
+        assert!(self.consume(Token::KwEnum));
+        self.skip_ws();
+
+        // Enum name
+        match self.current_token() {
+            Token::UpperWord(s) => {
+                name = class_firstname(s);
+                self.consume_token();
+            }
+            token => {
+                return Err(parse_error!(
+                    self,
+                    "enum name must start with A-Z but got {:?}",
+                    token
+                ))
+            }
+        }
+        self.expect_sep()?;
+
+        // Cases and internal definitions
+        let mut cases = vec![];
+        let mut defs = vec![];
+        loop {
+            self.skip_wsn();
+            if self.current_token_is(Token::KwCase) {
+                cases.push(self.parse_enum_case()?);
+            } else if let Some(def) = self.parse_definition()? {
+                defs.push(def);
+            } else {
+                break;
+            }
+        }
+
+        // `end'
+        match self.current_token() {
+            Token::KwEnd => {
+                self.consume_token();
+            }
+            token => {
+                return Err(parse_error!(
+                    self,
+                    "missing `end' for enum {:?}; got {:?}",
+                    name,
+                    token
+                ))
+            }
+        }
+
+        self.lv -= 1;
+        Ok(ast::Definition::EnumDefinition {
+            name,
+            cases,
+            defs,
+            locs: self.span_from(&begin),
+        })
+    }
+
+    /// Parse `case Foo(a: Int)` of an enum
+    fn parse_enum_case(&mut self) -> Result<ast::EnumCase, Error> {
+        let begin = self.current_position();
+        assert!(self.consume(Token::KwCase));
+        self.skip_ws();
+        let name = match self.current_token() {
+            Token::UpperWord(s) => class_firstname(s),
+            token => {
+                return Err(parse_error!(
+                    self,
+                    "enum case name must start with A-Z but got {:?}",
+                    token
+                ))
+            }
+        };
+        self.consume_token();
+        let params = if self.consume(Token::LParen) {
+            self.skip_wsn();
+            self.parse_params()?
+        } else {
+            vec![]
+        };
+        Ok(ast::EnumCase {
+            name,
+            params,
+            locs: self.span_from(&begin),
         })
     }
 
+    /// Parse `include Foo` or `extend Foo`
+    fn parse_mixin_definition(&mut self) -> Result<ast::Definition, Error> {
+        let begin = self.current_position();
+        let is_include = self.current_token_is(Token::KwInclude);
+        self.consume_token();
+        self.skip_ws();
+        let module_name = match self.current_token() {
+            Token::UpperWord(s) => class_fullname(s),
+            token => {
+                return Err(parse_error!(
+                    self,
+                    "module name must start with A-Z but got {:?}",
+                    token
+                ))
+            }
+        };
+        self.consume_token();
+        let locs = self.span_from(&begin);
+        if is_include {
+            Ok(ast::Definition::IncludeDefinition { module_name, locs })
+        } else {
+            Ok(ast::Definition::ExtendDefinition { module_name, locs })
+        }
+    }
+
+    /// Parse `<S, T>` of a class or method definition
+    fn parse_typarams(&mut self) -> Result<Vec<String>, Error> {
+        let mut typarams = vec![];
+        assert!(self.consume(Token::LessThan));
+        self.skip_wsn();
+        loop {
+            match self.current_token() {
+                Token::GreaterThan => {
+                    self.consume_token();
+                    break;
+                }
+                Token::UpperWord(s) => {
+                    typarams.push(s.to_string());
+                    self.consume_token();
+                    self.skip_wsn();
+                }
+                Token::Comma => {
+                    self.consume_token();
+                    self.skip_wsn();
+                }
+                token => {
+                    return Err(parse_error!(
+                        self,
+                        "unexpected token `{:?}' in type parameter definition",
+                        token
+                    ))
+                }
+            }
+        }
+        Ok(typarams)
+    }
+
     pub fn parse_method_definition(&mut self) -> Result<ast::Definition, Error> {
         self.debug_log("parse_method_definition");
         self.lv += 1;
+        let begin = self.current_position();
         // `def'
         self.set_lexer_state(LexerState::MethodName);
         assert!(self.consume(Token::KwDef));
@@ -160,15 +356,25 @@ impl<'a> Parser<'a> {
         }
 
         self.lv -= 1;
+        let locs = self.span_from(&begin);
         if is_class_method {
-            Ok(ast::Definition::ClassMethodDefinition { sig, body_exprs })
+            Ok(ast::Definition::ClassMethodDefinition {
+                sig,
+                body_exprs,
+                locs,
+            })
         } else {
-            Ok(ast::Definition::InstanceMethodDefinition { sig, body_exprs })
+            Ok(ast::Definition::InstanceMethodDefinition {
+                sig,
+                body_exprs,
+                locs,
+            })
         }
     }
 
     pub fn parse_method_signature(&mut self) -> Result<(ast::AstMethodSignature, bool), Error> {
         let mut name = None;
+        let mut typarams = vec![];
         let params;
         let ret_typ;
         let mut is_class_method = false;
@@ -190,6 +396,11 @@ impl<'a> Parser<'a> {
             name = Some(method_firstname(self.get_method_name()?));
             self.consume_token();
         }
+
+        // Type parameters (optional)
+        if self.current_token_is(Token::LessThan) {
+            typarams = self.parse_typarams()?;
+        }
         self.skip_ws();
 
         // Params (optional)
@@ -224,6 +435,7 @@ impl<'a> Parser<'a> {
 
         let sig = ast::AstMethodSignature {
             name: name.unwrap(),
+            typarams,
             params,
             ret_typ,
         };
@@ -233,8 +445,12 @@ impl<'a> Parser<'a> {
     fn get_method_name(&mut self) -> Result<&str, Error> {
         let name = match self.current_token() {
             Token::LowerWord(s) => s,
+            // eg. `Object#Some`
+            Token::UpperWord(s) => s,
             Token::UPlusMethod => "+@",
             Token::UMinusMethod => "-@",
+            Token::GetMethod => "[]",
+            Token::SetMethod => "[]=",
             Token::BinaryPlus => "+",
             Token::BinaryMinus => "-",
             Token::Mul => "*",
@@ -327,7 +543,49 @@ impl<'a> Parser<'a> {
         Ok(ast::Param { name, typ })
     }
 
-    fn parse_typ(&mut self) -> Result<ast::Typ, Error> {
+    pub(super) fn parse_typ(&mut self) -> Result<ast::Typ, Error> {
+        let typ = if self.current_token_is(Token::LParen) {
+            self.parse_fn_typ()?
+        } else {
+            self.parse_named_typ()?
+        };
+        if let Some(arity) = fn_class_arity(&typ.name) {
+            self.fn_arities.insert(arity);
+        }
+        Ok(typ)
+    }
+
+    /// Parse `(Int, String) -> Bool`, which is the same as `Fn2<Int, String, Bool>`
+    fn parse_fn_typ(&mut self) -> Result<ast::Typ, Error> {
+        assert!(self.consume(Token::LParen));
+        let mut typ_args = vec![];
+        self.skip_wsn();
+        if !self.consume(Token::RParen) {
+            loop {
+                typ_args.push(self.parse_typ()?);
+                self.skip_wsn();
+                match self.consume_token() {
+                    Token::Comma => self.skip_wsn(),
+                    Token::RParen => break,
+                    token => {
+                        return Err(parse_error!(
+                            self,
+                            "invalid token in param types: {:?}",
+                            token
+                        ))
+                    }
+                }
+            }
+        }
+        self.skip_ws();
+        self.expect(Token::RightArrow)?;
+        self.skip_ws();
+        let name = fn_class_fullname(typ_args.len()).0;
+        typ_args.push(self.parse_typ()?);
+        Ok(ast::Typ { name, typ_args })
+    }
+
+    fn parse_named_typ(&mut self) -> Result<ast::Typ, Error> {
         let mut name = String::new();
         loop {
             match self.current_token() {
@@ -358,7 +616,7 @@ impl<'a> Parser<'a> {
         }
     }
 
-    fn parse_typ_args(&mut self) -> Result<Vec<ast::Typ>, Error> {
+    pub(super) fn parse_typ_args(&mut self) -> Result<Vec<ast::Typ>, Error> {
         let mut typ_args = vec![];
         loop {
             self.skip_wsn();
@@ -386,6 +644,7 @@ impl<'a> Parser<'a> {
     fn parse_const_definition(&mut self) -> Result<ast::Definition, Error> {
         self.debug_log("parse_const_definition");
         self.lv += 1;
+        let begin = self.current_position();
         let name;
         match self.current_token() {
             Token::UpperWord(s) => {
@@ -402,6 +661,10 @@ impl<'a> Parser<'a> {
         let expr = self.parse_expr()?;
 
         self.lv -= 1;
-        Ok(ast::Definition::ConstDefinition { name, expr })
+        Ok(ast::Definition::ConstDefinition {
+            name,
+            expr,
+            locs: self.span_from(&begin),
+        })
     }
 }
@@ -15,11 +15,27 @@ impl<'a> Parser<'a> {
                 self.consume_token();
                 expr_seen = false;
             } else {
-                if expr_seen {
-                    self.expect_sep()?; // Missing separator between exprs
+                let result = if expr_seen {
+                    // Missing separator between exprs
+                    self.expect_sep().and_then(|_| self.parse_expr())
+                } else {
+                    self.parse_expr()
+                };
+                match result {
+                    Ok(expr) => {
+                        ret.push(expr);
+                        expr_seen = true;
+                    }
+                    Err(e) => {
+                        self.recover(e)?;
+                        // Skip stray `end` (eg. `fn(){ end }`)
+                        let end = Token::KwEnd;
+                        if self.current_token_is(end.clone()) && !stop_toks.contains(&end) {
+                            self.consume_token();
+                        }
+                        expr_seen = false;
+                    }
                 }
-                ret.push(self.parse_expr()?);
-                expr_seen = true;
             }
         }
     }
@@ -31,6 +47,7 @@ impl<'a> Parser<'a> {
     pub fn parse_var_decl(&mut self) -> Result<AstExpression, Error> {
         self.lv += 1;
         self.debug_log("parse_var_decl");
+        let begin = self.current_position();
         let expr;
         if self.current_token_is(Token::KwVar) {
             self.consume_token();
@@ -39,23 +56,37 @@ impl<'a> Parser<'a> {
                 Token::LowerWord(s) => {
                     let name = s.to_string();
                     self.consume_token();
+                    let typ = self.parse_opt_lvar_typ()?;
                     self.skip_ws();
-                    self.expect(Token::Equal)?; // TODO: `+=` etc.
+                    self.expect(Token::Equal)?;
                     self.skip_wsn();
                     let rhs = self.parse_operator_expr()?;
-                    expr = ast::lvar_decl(name, rhs);
+                    expr = ast::typed_lvar_decl(name, typ, rhs, true)
+                        .with_locs(self.span_from(&begin));
                 }
                 Token::IVar(s) => {
                     let name = s.to_string();
                     self.consume_token();
                     self.skip_ws();
-                    self.expect(Token::Equal)?; // TODO: `+=` etc.
+                    self.expect(Token::Equal)?;
                     self.skip_wsn();
                     let rhs = self.parse_operator_expr()?;
-                    expr = ast::ivar_decl(name, rhs);
+                    expr = ast::ivar_decl(name, rhs).with_locs(self.span_from(&begin));
                 }
                 token => return Err(parse_error!(self, "invalid var name: {:?}", token)),
             }
+        } else if self.lvar_typ_follows() {
+            // `x: Int = 1`
+            let name = match self.consume_token() {
+                Token::LowerWord(s) => s,
+                _ => unreachable!(),
+            };
+            let typ = self.parse_opt_lvar_typ()?;
+            self.skip_ws();
+            self.expect(Token::Equal)?;
+            self.skip_wsn();
+            let rhs = self.parse_operator_expr()?;
+            expr = ast::typed_lvar_decl(name, typ, rhs, false).with_locs(self.span_from(&begin));
         } else {
             expr = self.parse_and_or_expr()?;
         }
@@ -63,9 +94,30 @@ impl<'a> Parser<'a> {
         Ok(expr)
     }
 
+    /// Return true if the current token is a local variable name followed by
+    /// a type annotation (`x: Int = 1`)
+    fn lvar_typ_follows(&mut self) -> bool {
+        match self.current_token() {
+            Token::LowerWord(_) => self.peek_next_token() == Token::Colon,
+            _ => false,
+        }
+    }
+
+    /// Parse `: Int` of `x: Int = 1`, if any
+    fn parse_opt_lvar_typ(&mut self) -> Result<Option<ast::Typ>, Error> {
+        if self.next_nonspace_token() != Token::Colon {
+            return Ok(None);
+        }
+        self.skip_ws();
+        assert!(self.consume(Token::Colon));
+        self.skip_ws();
+        Ok(Some(self.parse_typ()?))
+    }
+
     pub fn parse_and_or_expr(&mut self) -> Result<AstExpression, Error> {
         self.lv += 1;
         self.debug_log("parse_and_or_expr");
+        let begin = self.current_position();
         let mut expr = self.parse_not_expr()?;
         self.skip_ws();
         loop {
@@ -73,12 +125,14 @@ impl<'a> Parser<'a> {
                 Token::KwAnd => {
                     self.consume_token();
                     self.skip_wsn();
-                    expr = ast::logical_and(expr, self.parse_not_expr()?);
+                    expr = ast::logical_and(expr, self.parse_not_expr()?)
+                        .with_locs(self.span_from(&begin));
                 }
                 Token::KwOr => {
                     self.consume_token();
                     self.skip_wsn();
-                    expr = ast::logical_or(expr, self.parse_not_expr()?);
+                    expr = ast::logical_or(expr, self.parse_not_expr()?)
+                        .with_locs(self.span_from(&begin));
                 }
                 _ => break,
             }
@@ -91,18 +145,19 @@ impl<'a> Parser<'a> {
     fn parse_not_expr(&mut self) -> Result<AstExpression, Error> {
         self.lv += 1;
         self.debug_log("parse_not_expr");
+        let begin = self.current_position();
         let expr = match self.current_token() {
             Token::KwNot => {
                 self.consume_token();
                 self.skip_ws();
                 let inner = self.parse_not_expr()?;
-                ast::logical_not(inner)
+                ast::logical_not(inner).with_locs(self.span_from(&begin))
             }
             Token::Bang => {
                 self.consume_token();
                 self.skip_ws();
                 let inner = self.parse_call_wo_paren()?;
-                ast::logical_not(inner)
+                ast::logical_not(inner).with_locs(self.span_from(&begin))
             }
             _ => self.parse_call_wo_paren()?,
         };
@@ -126,11 +181,13 @@ impl<'a> Parser<'a> {
                 self.consume_token();
                 self.set_lexer_state(LexerState::ExprArg);
                 assert!(self.consume(Token::Space));
-                let args = self.parse_operator_exprs()?;
+                let args = self.parse_command_args()?;
                 self.debug_log(&format!("tried/args: {:?}", args));
                 if !args.is_empty() {
+                    let expr = ast::method_call(None, &s, args, false, false);
+                    let expr = self.parse_opt_do_block(expr, &cur)?;
                     self.lv -= 1;
-                    return Ok(ast::method_call(None, &s, args, false, false));
+                    return Ok(expr);
                 }
                 self.rewind_to(cur)
             }
@@ -138,15 +195,45 @@ impl<'a> Parser<'a> {
         let mut expr = self.parse_operator_expr()?;
         if expr.may_have_paren_wo_args() {
             // foo bar, baz
-            let args = self.parse_operator_exprs()?;
+            let args = self.parse_command_args()?;
             if !args.is_empty() {
-                expr = ast::set_method_call_args(expr, args);
+                let begin = expr.locs.begin.clone();
+                expr = ast::set_method_call_args(expr, args).with_locs(self.span_from(&begin));
+                expr = self.parse_opt_do_block(expr, &begin)?;
             }
         }
         self.lv -= 1;
         Ok(expr)
     }
 
+    /// Parse the arguments of a method call without parentheses
+    /// (`bar, baz` of `foo bar, baz`)
+    fn parse_command_args(&mut self) -> Result<Vec<AstExpression>, Error> {
+        if self.next_nonspace_token().value_starts() {
+            self.skip_ws();
+        }
+        let orig = self.no_do_block;
+        self.no_do_block = true;
+        let result = self.parse_operator_exprs();
+        self.no_do_block = orig;
+        result
+    }
+
+    /// Parse `do ... end` (if any) and pass it to the method call `expr`
+    fn parse_opt_do_block(
+        &mut self,
+        expr: AstExpression,
+        begin: &Cursor,
+    ) -> Result<AstExpression, Error> {
+        if self.next_nonspace_token() == Token::KwDo {
+            self.skip_ws();
+            let block = self.parse_block()?;
+            Ok(ast::add_block(expr, block).with_locs(self.span_from(begin)))
+        } else {
+            Ok(expr.with_locs(self.span_from(begin)))
+        }
+    }
+
     fn parse_args(&mut self) -> Result<Vec<AstExpression>, Error> {
         self.lv += 1;
         self.debug_log("parse_args");
@@ -200,6 +287,7 @@ impl<'a> Parser<'a> {
     fn parse_assignment_expr(&mut self, lhs: AstExpression) -> Result<AstExpression, Error> {
         self.lv += 1;
         self.debug_log("parse_assignment_expr");
+        let begin = lhs.locs.begin.clone();
 
         self.skip_ws();
         let op = self.next_nonspace_token();
@@ -209,17 +297,19 @@ impl<'a> Parser<'a> {
 
         self.lv -= 1;
 
-        Ok(match op {
-            Token::Equal => ast::assignment(lhs, rhs),
-            Token::PlusEq => ast::assignment(lhs.clone(), ast::bin_op_expr(lhs, "+", rhs)),
-            _unexpected => unimplemented!(),
-        })
+        let locs = self.span_from(&begin);
+        let expr = match op.compound_assignment_op() {
+            Some(op) => ast::op_assignment(lhs, op, rhs),
+            None => ast::assignment(lhs, rhs),
+        };
+        Ok(expr.with_locs(locs))
     }
 
     /// `a ? b : c`
     fn parse_conditional_expr(&mut self) -> Result<AstExpression, Error> {
         self.lv += 1;
         self.debug_log("parse_conditional_expr");
+        let begin = self.current_position();
         let expr = self.parse_range_expr()?;
         if self.next_nonspace_token() == Token::Question {
             self.skip_ws();
@@ -231,7 +321,8 @@ impl<'a> Parser<'a> {
             self.skip_wsn();
             let else_expr = self.parse_operator_expr()?;
             self.lv -= 1;
-            Ok(ast::if_expr(expr, vec![then_expr], Some(vec![else_expr])))
+            Ok(ast::if_expr(expr, vec![then_expr], Some(vec![else_expr]))
+                .with_locs(self.span_from(&begin)))
         } else {
             self.lv -= 1;
             Ok(expr)
@@ -261,6 +352,7 @@ impl<'a> Parser<'a> {
     fn parse_operator_or(&mut self) -> Result<AstExpression, Error> {
         self.lv += 1;
         self.debug_log("parse_operator_or");
+        let begin = self.current_position();
         let mut expr = self.parse_operator_and()?;
         let mut token = &self.next_nonspace_token();
         loop {
@@ -268,7 +360,8 @@ impl<'a> Parser<'a> {
                 self.skip_ws();
                 assert!(self.consume(Token::OrOr));
                 self.skip_wsn();
-                expr = ast::logical_or(expr, self.parse_operator_and()?);
+                expr = ast::logical_or(expr, self.parse_operator_and()?)
+                    .with_locs(self.span_from(&begin));
                 self.skip_ws();
                 token = self.current_token();
             } else {
@@ -283,6 +376,7 @@ impl<'a> Parser<'a> {
     fn parse_operator_and(&mut self) -> Result<AstExpression, Error> {
         self.lv += 1;
         self.debug_log("parse_operator_and");
+        let begin = self.current_position();
         let mut expr = self.parse_equality_expr()?;
         let mut token = &self.next_nonspace_token();
         loop {
@@ -290,7 +384,8 @@ impl<'a> Parser<'a> {
                 self.skip_ws();
                 assert!(self.consume(Token::AndAnd));
                 self.skip_wsn();
-                expr = ast::logical_and(expr, self.parse_equality_expr()?);
+                expr = ast::logical_and(expr, self.parse_equality_expr()?)
+                    .with_locs(self.span_from(&begin));
                 self.skip_ws();
                 token = self.current_token();
             } else {
@@ -305,6 +400,7 @@ impl<'a> Parser<'a> {
     fn parse_equality_expr(&mut self) -> Result<AstExpression, Error> {
         self.lv += 1;
         self.debug_log("parse_equality_expr");
+        let begin = self.current_position();
         let left = self.parse_relational_expr()?;
         let op = match self.next_nonspace_token() {
             // TODO: <=> === =~ !~
@@ -320,9 +416,11 @@ impl<'a> Parser<'a> {
         self.consume_token();
         self.skip_wsn();
         let right = self.parse_relational_expr()?;
-        let call_eq = ast::method_call(Some(left), "==", vec![right], false, false);
+        let locs = self.span_from(&begin);
+        let call_eq =
+            ast::method_call(Some(left), "==", vec![right], false, false).with_locs(locs.clone());
         let expr = if op == "!=" {
-            ast::logical_not(call_eq)
+            ast::logical_not(call_eq).with_locs(locs)
         } else {
             call_eq
         };
@@ -333,6 +431,7 @@ impl<'a> Parser<'a> {
     fn parse_relational_expr(&mut self) -> Result<AstExpression, Error> {
         self.lv += 1;
         self.debug_log("parse_relational_expr");
+        let begin = self.current_position();
         let mut expr = self.parse_bitwise_or()?; // additive (> >= < <=) additive
         let mut nesting = false;
         loop {
@@ -351,11 +450,14 @@ impl<'a> Parser<'a> {
             if nesting {
                 if let AstExpressionBody::MethodCall { arg_exprs, .. } = &expr.body {
                     let mid = arg_exprs[0].clone();
-                    let compare = ast::method_call(Some(mid), op, vec![right], false, false);
-                    expr = ast::logical_and(expr, compare);
+                    let mid_begin = mid.locs.begin.clone();
+                    let compare = ast::method_call(Some(mid), op, vec![right], false, false)
+                        .with_locs(self.span_from(&mid_begin));
+                    expr = ast::logical_and(expr, compare).with_locs(self.span_from(&begin));
                 }
             } else {
-                expr = ast::method_call(Some(expr), op, vec![right], false, false);
+                expr = ast::method_call(Some(expr), op, vec![right], false, false)
+                    .with_locs(self.span_from(&begin));
                 nesting = true;
             }
         }
@@ -414,9 +516,10 @@ impl<'a> Parser<'a> {
         //  parse_power_expr
         //  parse_unary_expr
         //  parse_secondary_expr
+        let begin = self.current_position();
         let expr = if self.consume(Token::UnaryMinus) {
             let target = self.parse_secondary_expr()?;
-            ast::unary_expr(target, "-@")
+            ast::unary_expr(target, "-@").with_locs(self.span_from(&begin))
         } else {
             self.parse_secondary_expr()?
         };
@@ -433,22 +536,75 @@ impl<'a> Parser<'a> {
     fn parse_secondary_expr(&mut self) -> Result<AstExpression, Error> {
         self.lv += 1;
         self.debug_log("parse_secondary_expr");
+        let begin = self.current_position();
         let expr = match self.current_token() {
             Token::KwBreak => self.parse_break_expr(),
+            Token::KwNext => self.parse_next_expr(),
+            Token::KwReturn => self.parse_return_expr(),
             Token::KwIf => self.parse_if_expr(),
             Token::KwUnless => self.parse_unless_expr(),
             Token::KwWhile => self.parse_while_expr(),
+            Token::KwUntil => self.parse_until_expr(),
+            Token::KwLoop => self.parse_loop_expr(),
+            Token::Label(_) => self.parse_labeled_loop(),
+            Token::KwBegin => self.parse_begin_expr(),
+            Token::KwMatch => self.parse_match_expr(),
             _ => self.parse_primary_expr(),
         }?;
         self.lv -= 1;
-        Ok(expr)
+        Ok(expr.with_locs(self.span_from(&begin)))
     }
 
     fn parse_break_expr(&mut self) -> Result<AstExpression, Error> {
         self.lv += 1;
         self.debug_log("parse_break_expr");
         assert!(self.consume(Token::KwBreak));
-        Ok(ast::break_expr())
+        self.skip_ws();
+        let label = self.parse_opt_label();
+        self.skip_ws();
+        let arg = if self.current_token().value_starts() {
+            Some(self.parse_operator_expr()?)
+        } else {
+            None
+        };
+        self.lv -= 1;
+        Ok(ast::break_expr(label, arg))
+    }
+
+    fn parse_next_expr(&mut self) -> Result<AstExpression, Error> {
+        self.lv += 1;
+        self.debug_log("parse_next_expr");
+        assert!(self.consume(Token::KwNext));
+        self.skip_ws();
+        let label = self.parse_opt_label();
+        self.lv -= 1;
+        Ok(ast::next_expr(label))
+    }
+
+    /// Parse the label of `break` or `next`, if any
+    fn parse_opt_label(&mut self) -> Option<String> {
+        match self.current_token() {
+            Token::Label(s) => {
+                let name = s.to_string();
+                self.consume_token();
+                Some(name)
+            }
+            _ => None,
+        }
+    }
+
+    fn parse_return_expr(&mut self) -> Result<AstExpression, Error> {
+        self.lv += 1;
+        self.debug_log("parse_return_expr");
+        assert!(self.consume(Token::KwReturn));
+        self.skip_ws();
+        let arg = if self.current_token().value_starts() {
+            Some(self.parse_operator_expr()?)
+        } else {
+            None
+        };
+        self.lv -= 1;
+        Ok(ast::return_expr(arg))
     }
 
     fn parse_if_expr(&mut self) -> Result<AstExpression, Error> {
@@ -456,6 +612,21 @@ impl<'a> Parser<'a> {
         self.debug_log("parse_if_expr");
         assert!(self.consume(Token::KwIf));
         self.skip_ws();
+        // `if let x = maybe`
+        let let_name = if self.consume(Token::KwLet) {
+            self.skip_ws();
+            let name = match self.current_token() {
+                Token::LowerWord(s) => s.to_string(),
+                token => return Err(parse_error!(self, "invalid variable name: {:?}", token)),
+            };
+            self.consume_token();
+            self.skip_ws();
+            self.expect(Token::Equal)?;
+            self.skip_ws();
+            Some(name)
+        } else {
+            None
+        };
         let cond_expr = self.parse_expr()?;
         self.skip_ws();
         if self.consume(Token::KwThen) {
@@ -465,17 +636,19 @@ impl<'a> Parser<'a> {
         }
         let then_exprs = self.parse_exprs(vec![Token::KwEnd, Token::KwElse])?;
         self.skip_wsn();
-        if self.consume(Token::KwElse) {
+        let else_exprs = if self.consume(Token::KwElse) {
             self.skip_wsn();
             let else_exprs = self.parse_exprs(vec![Token::KwEnd])?;
             self.skip_wsn();
-            self.expect(Token::KwEnd)?;
-            self.lv -= 1;
-            Ok(ast::if_expr(cond_expr, then_exprs, Some(else_exprs)))
+            Some(else_exprs)
         } else {
-            self.expect(Token::KwEnd)?;
-            self.lv -= 1;
-            Ok(ast::if_expr(cond_expr, then_exprs, None))
+            None
+        };
+        self.expect(Token::KwEnd)?;
+        self.lv -= 1;
+        match let_name {
+            Some(name) => Ok(ast::if_let_expr(&name, cond_expr, then_exprs, else_exprs)),
+            None => Ok(ast::if_expr(cond_expr, then_exprs, else_exprs)),
         }
     }
 
@@ -517,6 +690,197 @@ impl<'a> Parser<'a> {
         Ok(ast::while_expr(cond_expr, body_exprs))
     }
 
+    fn parse_until_expr(&mut self) -> Result<AstExpression, Error> {
+        self.lv += 1;
+        self.debug_log("parse_until_expr");
+        assert!(self.consume(Token::KwUntil));
+        self.skip_ws();
+        let cond_expr = self.parse_expr()?;
+        self.skip_ws();
+        self.expect(Token::Separator)?;
+        let body_exprs = self.parse_exprs(vec![Token::KwEnd])?;
+        self.skip_wsn();
+        self.expect(Token::KwEnd)?;
+        self.lv -= 1;
+        Ok(ast::while_expr(ast::logical_not(cond_expr), body_exprs))
+    }
+
+    fn parse_loop_expr(&mut self) -> Result<AstExpression, Error> {
+        self.lv += 1;
+        self.debug_log("parse_loop_expr");
+        assert!(self.consume(Token::KwLoop));
+        self.skip_ws();
+        self.expect(Token::KwDo)?;
+        let body_exprs = self.parse_exprs(vec![Token::KwEnd])?;
+        self.skip_wsn();
+        self.expect(Token::KwEnd)?;
+        self.lv -= 1;
+        Ok(ast::loop_expr(body_exprs))
+    }
+
+    /// `'foo: while ...`, `'foo: loop do ...`, etc.
+    fn parse_labeled_loop(&mut self) -> Result<AstExpression, Error> {
+        self.lv += 1;
+        self.debug_log("parse_labeled_loop");
+        let label = self.parse_opt_label().unwrap();
+        self.expect(Token::Colon)?;
+        self.skip_ws();
+        let expr = match self.current_token() {
+            Token::KwWhile => self.parse_while_expr()?,
+            Token::KwUntil => self.parse_until_expr()?,
+            Token::KwLoop => self.parse_loop_expr()?,
+            token => {
+                return Err(parse_error!(
+                    self,
+                    "a label must be followed by a loop but got {:?}",
+                    token
+                ))
+            }
+        };
+        self.lv -= 1;
+        Ok(ast::labeled_loop(expr, label))
+    }
+
+    fn parse_begin_expr(&mut self) -> Result<AstExpression, Error> {
+        self.lv += 1;
+        self.debug_log("parse_begin_expr");
+        assert!(self.consume(Token::KwBegin));
+        let body_exprs = self.parse_exprs(vec![Token::KwRescue, Token::KwEnsure, Token::KwEnd])?;
+        let mut rescue_var = None;
+        let mut rescue_exprs = None;
+        if self.consume(Token::KwRescue) {
+            self.skip_ws();
+            if self.consume(Token::FatArrow) {
+                self.skip_ws();
+                match self.current_token() {
+                    Token::LowerWord(s) => {
+                        rescue_var = Some(s.to_string());
+                        self.consume_token();
+                    }
+                    token => return Err(parse_error!(self, "invalid variable name: {:?}", token)),
+                }
+            }
+            rescue_exprs = Some(self.parse_exprs(vec![Token::KwEnsure, Token::KwEnd])?);
+        }
+        let ensure_exprs = if self.consume(Token::KwEnsure) {
+            Some(self.parse_exprs(vec![Token::KwEnd])?)
+        } else {
+            None
+        };
+        self.expect(Token::KwEnd)?;
+        self.lv -= 1;
+        Ok(ast::begin_expr(
+            body_exprs,
+            rescue_var,
+            rescue_exprs,
+            ensure_exprs,
+        ))
+    }
+
+    fn parse_match_expr(&mut self) -> Result<AstExpression, Error> {
+        self.lv += 1;
+        self.debug_log("parse_match_expr");
+        assert!(self.consume(Token::KwMatch));
+        self.skip_ws();
+        let cond_expr = self.parse_expr()?;
+        self.skip_wsn();
+        let mut clauses = vec![];
+        while self.consume(Token::KwWhen) {
+            self.skip_ws();
+            let pattern = self.parse_pattern()?;
+            self.skip_ws();
+            let guard = if self.consume(Token::KwIf) {
+                self.skip_ws();
+                let expr = self.parse_expr()?;
+                self.skip_ws();
+                Some(expr)
+            } else {
+                None
+            };
+            if self.consume(Token::KwThen) {
+                self.skip_wsn();
+            } else {
+                self.expect(Token::Separator)?;
+            }
+            let body_exprs = self.parse_exprs(vec![Token::KwWhen, Token::KwElse, Token::KwEnd])?;
+            clauses.push(ast::AstMatchClause {
+                pattern,
+                guard,
+                body_exprs,
+            });
+        }
+        if clauses.is_empty() {
+            return Err(parse_error!(
+                self,
+                "expected `when' but got {:?}",
+                self.current_token()
+            ));
+        }
+        if self.consume(Token::KwElse) {
+            let body_exprs = self.parse_exprs(vec![Token::KwEnd])?;
+            clauses.push(ast::AstMatchClause {
+                pattern: ast::AstPattern::WildcardPattern,
+                guard: None,
+                body_exprs,
+            });
+        }
+        self.expect(Token::KwEnd)?;
+        self.lv -= 1;
+        Ok(ast::match_expr(cond_expr, clauses))
+    }
+
+    /// Parse a pattern of `match`
+    fn parse_pattern(&mut self) -> Result<ast::AstPattern, Error> {
+        match self.current_token() {
+            Token::LowerWord(s) => {
+                let name = s.to_string();
+                self.consume_token();
+                if name == "_" {
+                    Ok(ast::AstPattern::WildcardPattern)
+                } else {
+                    Ok(ast::AstPattern::VariablePattern(name))
+                }
+            }
+            Token::UpperWord(_) => self.parse_extractor_pattern(),
+            Token::Number(_) | Token::Str(_) | Token::KwTrue | Token::KwFalse => {
+                let expr = self.parse_atomic()?;
+                Ok(ast::AstPattern::LiteralPattern(Box::new(expr)))
+            }
+            token => Err(parse_error!(self, "invalid pattern: {:?}", token)),
+        }
+    }
+
+    /// Parse `Foo::Bar(a, b)`
+    fn parse_extractor_pattern(&mut self) -> Result<ast::AstPattern, Error> {
+        let mut names = vec![];
+        loop {
+            match self.current_token() {
+                Token::UpperWord(s) => {
+                    names.push(s.to_string());
+                    self.consume_token();
+                }
+                token => return Err(parse_error!(self, "invalid pattern: {:?}", token)),
+            }
+            if !self.consume(Token::ColonColon) {
+                break;
+            }
+        }
+        let mut params = vec![];
+        if self.consume(Token::LParen) {
+            self.skip_wsn();
+            loop {
+                params.push(self.parse_pattern()?);
+                self.skip_wsn();
+                if self.consume(Token::RParen) {
+                    break;
+                }
+                self.expect(Token::Comma)?;
+                self.skip_wsn();
+            }
+        }
+        Ok(ast::AstPattern::ExtractorPattern { names, params })
+    }
+
     // prim . methodName argumentWithParentheses? block?
     // prim [ indexingArgumentList? ] not(EQUAL)
     fn parse_primary_expr(&mut self) -> Result<AstExpression, Error> {
@@ -524,10 +888,22 @@ impl<'a> Parser<'a> {
         self.debug_log("parse_primary_expr");
         let mut expr = self.parse_atomic()?;
         loop {
-            if self.next_nonspace_token() == Token::Dot {
+            let next_token = self.next_nonspace_token();
+            if next_token == Token::Dot || next_token == Token::QuestionDot {
                 // TODO: Newline should also be allowed here (but Semicolon is not)
                 self.skip_ws();
                 expr = self.parse_method_chain(expr)?;
+            } else if self.current_token_is(Token::LSqBracket) {
+                // `a[b]` (`a [b]` is a method call with an array)
+                expr = self.parse_index_expr(expr)?;
+            } else if expr.can_take_block()
+                && (next_token == Token::LBrace || (next_token == Token::KwDo && !self.no_do_block))
+            {
+                // foo.bar { |x| ... }, foo.bar do |x| ... end
+                let begin = expr.locs.begin.clone();
+                self.skip_ws();
+                let block = self.parse_block()?;
+                expr = ast::add_block(expr, block).with_locs(self.span_from(&begin));
             } else {
                 break;
             }
@@ -536,12 +912,15 @@ impl<'a> Parser<'a> {
         Ok(expr)
     }
 
-    /// Parse `.foo(args)`
+    /// Parse `.foo(args)` (or `?.foo(args)`)
     fn parse_method_chain(&mut self, expr: AstExpression) -> Result<AstExpression, Error> {
         self.lv += 1;
         self.debug_log("parse_method_chain");
-        // .
-        assert!(self.consume(Token::Dot));
+        // . or ?.
+        let is_safe = self.consume(Token::QuestionDot);
+        if !is_safe {
+            assert!(self.consume(Token::Dot));
+        }
         self.skip_wsn();
 
         // Method name
@@ -551,6 +930,9 @@ impl<'a> Parser<'a> {
         };
         self.consume_token();
 
+        // Type args (optional)
+        let type_args = self.parse_method_type_args();
+
         // Args
         let (args, may_have_paren_wo_args) = match self.current_token() {
             // .foo(args)
@@ -560,13 +942,55 @@ impl<'a> Parser<'a> {
         };
 
         self.lv -= 1;
-        Ok(ast::method_call(
+        let begin = expr.locs.begin.clone();
+        if is_safe {
+            return Ok(ast::safe_method_call(expr, &method_name, type_args, args)
+                .with_locs(self.span_from(&begin)));
+        }
+        Ok(ast::generic_method_call(
             Some(expr),
             &method_name,
+            type_args,
             args,
             true,
             may_have_paren_wo_args,
-        ))
+        )
+        .with_locs(self.span_from(&begin)))
+    }
+
+    /// Parse `[args]` of `a[args]`, which is a call of the method `[]`
+    fn parse_index_expr(&mut self, expr: AstExpression) -> Result<AstExpression, Error> {
+        self.lv += 1;
+        self.debug_log("parse_index_expr");
+        assert!(self.consume(Token::LSqBracket));
+        self.skip_wsn();
+        let args = self.parse_args()?;
+        self.skip_wsn();
+        self.expect(Token::RSqBracket)?;
+        self.lv -= 1;
+        let begin = expr.locs.begin.clone();
+        Ok(ast::method_call(Some(expr), "[]", args, true, false).with_locs(self.span_from(&begin)))
+    }
+
+    /// Parse `<Int>` of `foo<Int>(x)`, if any.
+    /// Returns an empty Vec (and backtracks) unless the `<` is immediately after
+    /// the method name and followed by type names, `>` and `(`; eg. `a<b` is
+    /// parsed as a comparison
+    fn parse_method_type_args(&mut self) -> Vec<ast::Typ> {
+        if !self.current_token_is(Token::LessThan) {
+            return vec![];
+        }
+        let cur = self.current_position();
+        let last_token_end = self.last_token_end.clone();
+        self.consume_token();
+        match self.parse_typ_args() {
+            Ok(typ_args) if self.current_token_is(Token::LParen) => typ_args,
+            _ => {
+                self.rewind_to(cur);
+                self.last_token_end = last_token_end;
+                vec![]
+            }
+        }
     }
 
     fn parse_paren_and_args(&mut self) -> Result<Vec<AstExpression>, Error> {
@@ -574,6 +998,8 @@ impl<'a> Parser<'a> {
         self.debug_log("parse_paren_and_args");
         assert!(self.consume(Token::LParen));
         self.skip_wsn();
+        let orig = self.no_do_block;
+        self.no_do_block = false;
         let args;
         if self.consume(Token::RParen) {
             args = vec![]
@@ -582,6 +1008,7 @@ impl<'a> Parser<'a> {
             self.skip_wsn();
             self.expect(Token::RParen)?;
         }
+        self.no_do_block = orig;
         self.lv -= 1;
         Ok(args)
     }
@@ -589,6 +1016,7 @@ impl<'a> Parser<'a> {
     fn parse_atomic(&mut self) -> Result<AstExpression, Error> {
         self.lv += 1;
         self.debug_log("parse_atomic");
+        let begin = self.current_position();
         let token = self.current_token();
         let expr = match token {
             Token::LowerWord(s) => {
@@ -601,6 +1029,7 @@ impl<'a> Parser<'a> {
                 self.parse_const_ref(name)
             }
             Token::KwFn => self.parse_lambda(),
+            Token::KwSuper => self.parse_super(),
             Token::KwSelf | Token::KwTrue | Token::KwFalse => {
                 let t = token.clone();
                 self.consume_token();
@@ -613,24 +1042,37 @@ impl<'a> Parser<'a> {
             }
             Token::LSqBracket => self.parse_array_literal(),
             Token::Number(_) => self.parse_decimal_literal(),
-            Token::Str(_) => self.parse_string(),
+            Token::Str(_) | Token::StrWithInterpolation(_) => self.parse_string(),
             Token::LParen => self.parse_parenthesized_expr(),
+            Token::LexError(msg) => Err(parse_error!(self, "{}", msg)),
             token => Err(parse_error!(self, "unexpected token: {:?}", token)),
         }?;
         self.lv -= 1;
-        Ok(expr)
+        Ok(expr.with_locs(self.span_from(&begin)))
+    }
+
+    /// Parse `super` or `super(args)`
+    fn parse_super(&mut self) -> Result<AstExpression, Error> {
+        assert!(self.consume(Token::KwSuper));
+        let arg_exprs = match self.current_token() {
+            Token::LParen => Some(self.parse_paren_and_args()?),
+            _ => None,
+        };
+        Ok(ast::super_call(arg_exprs))
     }
 
     // Method call with explicit parenthesis (eg. `foo(bar)`)
     fn parse_primary_method_call(&mut self, bare_name_str: &str) -> Result<AstExpression, Error> {
         self.lv += 1;
         self.debug_log("parse_primary_method_call");
+        let type_args = self.parse_method_type_args();
         let expr = match self.current_token() {
             Token::LParen => {
                 let arg_exprs = self.parse_paren_and_args()?;
-                ast::method_call(
+                ast::generic_method_call(
                     None, // receiver_expr
                     bare_name_str,
+                    type_args,
                     arg_exprs,
                     true,  // primary
                     false, // may_have_paren_wo_args
@@ -643,8 +1085,13 @@ impl<'a> Parser<'a> {
     }
 
     fn parse_const_ref(&mut self, s: String) -> Result<AstExpression, Error> {
-        let mut names = vec![s];
         self.consume_token();
+        // `Foo(args)` is a call of the method `Foo` (eg. `Some(x)`)
+        if self.current_token_is(Token::LParen) {
+            let arg_exprs = self.parse_paren_and_args()?;
+            return Ok(ast::method_call(None, &s, arg_exprs, true, false));
+        }
+        let mut names = vec![s];
         // Parse `A::B`
         while self.current_token_is(Token::ColonColon) {
             self.consume_token();
@@ -665,14 +1112,83 @@ impl<'a> Parser<'a> {
         self.debug_log("parse_lambda");
         assert!(self.consume(Token::KwFn));
         self.expect(Token::LParen)?;
-        let params = self.parse_params()?;
+        let params = self.parse_block_params(Token::RParen)?;
+        self.fn_arities.insert(params.len());
         self.skip_ws();
         self.expect(Token::LBrace)?;
         self.consume_token();
         let exprs = self.parse_exprs(vec![Token::RBrace])?;
         assert!(self.consume(Token::RBrace));
         self.lv -= 1;
-        Ok(ast::lambda_expr(params, exprs))
+        Ok(ast::lambda_expr(params, exprs, true))
+    }
+
+    /// Parse a block (`do |x| ... end` or `{ |x| ... }`) into a lambda
+    fn parse_block(&mut self) -> Result<AstExpression, Error> {
+        self.lv += 1;
+        self.debug_log("parse_block");
+        let begin = self.current_position();
+        let end_token = match self.consume_token() {
+            Token::KwDo => Token::KwEnd,
+            Token::LBrace => Token::RBrace,
+            token => panic!("[BUG] unexpected token for a block: {:?}", token),
+        };
+        self.skip_ws();
+        let params = match self.current_token() {
+            Token::Or => {
+                self.consume_token();
+                self.parse_block_params(Token::Or)?
+            }
+            // `{ || ... }`
+            Token::OrOr => {
+                self.consume_token();
+                vec![]
+            }
+            _ => vec![],
+        };
+        self.fn_arities.insert(params.len());
+        let exprs = self.parse_exprs(vec![end_token.clone()])?;
+        self.expect(end_token)?;
+        self.lv -= 1;
+        Ok(ast::lambda_expr(params, exprs, false).with_locs(self.span_from(&begin)))
+    }
+
+    /// Parse `x, y: Int|` of a block or `x, y: Int)` of a lambda. The types
+    /// may be omitted
+    fn parse_block_params(&mut self, end_token: Token) -> Result<Vec<ast::BlockParam>, Error> {
+        let mut params = vec![];
+        self.skip_wsn();
+        if self.consume(end_token.clone()) {
+            return Ok(params);
+        }
+        loop {
+            let name = match self.current_token() {
+                Token::LowerWord(s) => s.to_string(),
+                token => return Err(parse_error!(self, "invalid token as param: {:?}", token)),
+            };
+            self.consume_token();
+            self.skip_ws();
+            let opt_typ = if self.consume(Token::Colon) {
+                self.skip_ws();
+                Some(self.parse_typ()?)
+            } else {
+                None
+            };
+            params.push(ast::BlockParam { name, opt_typ });
+            self.skip_wsn();
+            match self.consume_token() {
+                Token::Comma => self.skip_wsn(),
+                token if token == end_token => break,
+                token => {
+                    return Err(parse_error!(
+                        self,
+                        "unexpected token in params: {:?}",
+                        token
+                    ))
+                }
+            }
+        }
+        Ok(params)
     }
 
     fn parse_parenthesized_expr(&mut self) -> Result<AstExpression, Error> {
@@ -731,15 +1247,13 @@ impl<'a> Parser<'a> {
         self.lv += 1;
         self.debug_log("parse_decimal_literal");
         let expr = match self.consume_token() {
-            Token::Number(s) => {
-                if s.contains('.') {
-                    let value = s.parse().unwrap();
-                    ast::float_literal(value)
-                } else {
-                    let value = s.parse().unwrap();
-                    ast::decimal_literal(value)
+            Token::Number(s) => match number_literal(&s) {
+                Ok(expr) => expr,
+                Err(msg) => {
+                    self.lv -= 1;
+                    return Err(self.parseerror(&msg));
                 }
-            }
+            },
             _ => {
                 self.lv -= 1;
                 return Err(self.parseerror("expected decimal literal"));
@@ -754,12 +1268,52 @@ impl<'a> Parser<'a> {
         self.debug_log("parse_string");
         let expr = match self.consume_token() {
             Token::Str(content) => ast::string_literal(content),
+            Token::StrWithInterpolation(head) => self.parse_string_with_interpolation(head)?,
             _ => panic!("parse_string called on non-string token"),
         };
         self.lv -= 1;
         Ok(expr)
     }
 
+    /// Parse the rest of `"...#{expr}..."` (`head` is the part before `#{`)
+    fn parse_string_with_interpolation(&mut self, head: String) -> Result<AstExpression, Error> {
+        let mut parts = vec![];
+        if !head.is_empty() {
+            parts.push(ast::string_literal(head));
+        }
+        loop {
+            self.skip_wsn();
+            parts.push(self.parse_expr()?);
+            self.skip_wsn();
+            if !self.current_token_is(Token::RBrace) {
+                return Err(parse_error!(
+                    self,
+                    "expected `}}' after the embedded expression but got {:?}",
+                    self.current_token()
+                ));
+            }
+            // Read the rest of the string literal from the `}`
+            self.set_lexer_state(LexerState::StrLiteral);
+            self.rewind_to(self.current_position());
+            match self.consume_token() {
+                Token::Str(content) => {
+                    if !content.is_empty() {
+                        parts.push(ast::string_literal(content));
+                    }
+                    break;
+                }
+                Token::StrWithInterpolation(content) => {
+                    if !content.is_empty() {
+                        parts.push(ast::string_literal(content));
+                    }
+                }
+                Token::LexError(msg) => return Err(parse_error!(self, "{}", msg)),
+                token => panic!("unexpected token in a string literal: {:?}", token),
+            }
+        }
+        Ok(ast::string_with_interpolation(parts))
+    }
+
     // func: parse_xx
     // Parse `xx op xx op ... xx`
     fn parse_binary_operator<F: Fn(&mut Self) -> Result<AstExpression, Error>>(
@@ -770,6 +1324,7 @@ impl<'a> Parser<'a> {
     ) -> Result<AstExpression, Error> {
         self.lv += 1;
         self.debug_log(name);
+        let begin = self.current_position();
         let mut left = func(self)?;
         loop {
             let t = self.next_nonspace_token();
@@ -784,7 +1339,32 @@ impl<'a> Parser<'a> {
             self.consume_token(); // Consume t
             self.skip_wsn(); // TODO: should ban ';' here
             let right = func(self)?;
-            left = ast::bin_op_expr(left, op, right)
+            left = ast::bin_op_expr(left, op, right).with_locs(self.span_from(&begin))
+        }
+    }
+}
+
+/// Convert the text of a number literal (already checked by the lexer)
+/// into an AST node
+fn number_literal(s: &str) -> Result<AstExpression, String> {
+    let digits = s.replace('_', "");
+    let (radix, body) = if let Some(body) = digits.strip_prefix("0x") {
+        (16, body)
+    } else if let Some(body) = digits.strip_prefix("0o") {
+        (8, body)
+    } else if let Some(body) = digits.strip_prefix("0b") {
+        (2, body)
+    } else if digits.contains(&['.', 'e', 'E'][..]) {
+        let value: f64 = digits.parse().unwrap();
+        if value.is_infinite() {
+            return Err(format!("float literal `{}' is out of range", s));
         }
+        return Ok(ast::float_literal(value));
+    } else {
+        (10, &digits[..])
+    };
+    match u64::from_str_radix(body, radix) {
+        Ok(value) => Ok(ast::decimal_literal(value)),
+        Err(_) => Err(format!("integer literal `{}' is out of range", s)),
     }
 }
@@ -35,6 +35,9 @@ pub enum LexerState {
     /// Expects a method name
     /// eg. `+@`, `-@` is allowed only in this state
     MethodName,
+    /// Reading the rest of a string literal after `#{...}`
+    /// (the current char is the closing `}`)
+    StrLiteral,
 }
 
 #[derive(Debug, PartialEq, Clone, Default)]
@@ -53,6 +56,21 @@ impl Cursor {
         }
     }
 
+    /// Line number (0-origin)
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Column number (0-origin)
+    pub fn col(&self) -> usize {
+        self.col
+    }
+
+    /// Number of bytes from the beginning of the file
+    pub fn pos(&self) -> usize {
+        self.pos
+    }
+
     /// Return the current char (None if eof)
     pub fn peek(&self, src: &str) -> Option<char> {
         src[self.pos..].chars().next()
@@ -91,6 +109,7 @@ enum CharType {
     UpperWord, // identifier which starts with upper-case letter
     LowerWord, // Keyword or identifier which starts with lower-case letter
     IVar,      // Instance variable (eg. "foo" for @foo)
+    Label,     // Loop label (eg. "foo" for 'foo)
     Symbol,    // '+', '(', etc.
     Number,    // '0'~'9'
     Str,       // '"'
@@ -207,9 +226,10 @@ impl<'a> Lexer<'a> {
             ),
             CharType::LowerWord => self.read_lower_word(&mut next_next_cur, Some(&next_cur)),
             CharType::IVar => (self.read_ivar(&mut next_next_cur, Some(&next_cur)), None),
+            CharType::Label => (self.read_label(&mut next_next_cur, Some(&next_cur)), None),
             CharType::Symbol => self.read_symbol(&mut next_next_cur),
             CharType::Number => (self.read_number(&mut next_next_cur, Some(&next_cur)), None),
-            CharType::Str => (self.read_str(&mut next_next_cur, Some(&next_cur)), None),
+            CharType::Str => self.read_str(&mut next_next_cur),
             CharType::Eof => (self.read_eof(), None),
         };
         token
@@ -219,7 +239,12 @@ impl<'a> Lexer<'a> {
     fn read_token(&mut self) {
         let c = self.cur.peek(self.src);
         let mut next_cur = self.cur.clone();
-        let (token, new_state) = match self.char_type(c) {
+        let char_type = if self.state == LexerState::StrLiteral {
+            CharType::Str
+        } else {
+            self.char_type(c)
+        };
+        let (token, new_state) = match char_type {
             CharType::Space => (self.read_space(&mut next_cur), None),
             CharType::Separator => (self.read_separator(&mut next_cur), None),
             CharType::Comment => (self.read_comment(&mut next_cur), None),
@@ -232,15 +257,16 @@ impl<'a> Lexer<'a> {
                 self.read_ivar(&mut next_cur, None),
                 Some(LexerState::ExprEnd),
             ),
+            CharType::Label => (
+                self.read_label(&mut next_cur, None),
+                Some(LexerState::ExprBegin),
+            ),
             CharType::Symbol => self.read_symbol(&mut next_cur),
             CharType::Number => (
                 self.read_number(&mut next_cur, None),
                 Some(LexerState::ExprEnd),
             ),
-            CharType::Str => (
-                self.read_str(&mut next_cur, None),
-                Some(LexerState::ExprEnd),
-            ),
+            CharType::Str => self.read_str(&mut next_cur),
             CharType::Eof => (self.read_eof(), None),
         };
         self.set_current_token(token);
@@ -305,6 +331,13 @@ impl<'a> Lexer<'a> {
                     }
                     break;
                 }
+                // `foo?` and `foo!`
+                CharType::Symbol if (c == Some('?') || c == Some('!')) => {
+                    if self.is_name_suffix(c, next_cur.peek2(self.src)) {
+                        next_cur.proceed(self.src);
+                    }
+                    break;
+                }
                 _ => break,
             }
         }
@@ -324,13 +357,31 @@ impl<'a> Lexer<'a> {
             "if" => (Token::KwIf, LexerState::ExprBegin),
             "unless" => (Token::KwUnless, LexerState::ExprBegin),
             "while" => (Token::KwWhile, LexerState::ExprBegin),
-            "break" => (Token::KwBreak, LexerState::ExprEnd),
+            "break" => (Token::KwBreak, LexerState::ExprBegin),
+            "next" => (Token::KwNext, LexerState::ExprEnd),
+            "loop" => (Token::KwLoop, LexerState::ExprBegin),
+            "do" => (Token::KwDo, LexerState::ExprBegin),
+            "until" => (Token::KwUntil, LexerState::ExprBegin),
+            "begin" => (Token::KwBegin, LexerState::ExprBegin),
+            "rescue" => (Token::KwRescue, LexerState::ExprBegin),
+            "ensure" => (Token::KwEnsure, LexerState::ExprBegin),
+            "enum" => (Token::KwEnum, LexerState::ExprBegin),
+            "case" => (Token::KwCase, LexerState::ExprBegin),
+            "match" => (Token::KwMatch, LexerState::ExprBegin),
+            "when" => (Token::KwWhen, LexerState::ExprBegin),
+            "let" => (Token::KwLet, LexerState::ExprBegin),
             "then" => (Token::KwThen, LexerState::ExprBegin),
             "else" => (Token::KwElse, LexerState::ExprBegin),
             "fn" => (Token::KwFn, LexerState::ExprBegin),
             "self" => (Token::KwSelf, LexerState::ExprEnd),
             "true" => (Token::KwTrue, LexerState::ExprEnd),
             "false" => (Token::KwFalse, LexerState::ExprEnd),
+            "require" => (Token::KwRequire, LexerState::ExprBegin),
+            "module" => (Token::KwModule, LexerState::ExprBegin),
+            "include" => (Token::KwInclude, LexerState::ExprBegin),
+            "extend" => (Token::KwExtend, LexerState::ExprBegin),
+            "super" => (Token::KwSuper, LexerState::ExprEnd),
+            "return" => (Token::KwReturn, LexerState::ExprBegin),
             _ => (Token::LowerWord(s.to_string()), LexerState::ExprEnd),
         };
         (token, Some(state))
@@ -353,13 +404,40 @@ impl<'a> Lexer<'a> {
         Token::IVar(s.to_string())
     }
 
+    fn read_label(&mut self, next_cur: &mut Cursor, cur: Option<&Cursor>) -> Token {
+        next_cur.proceed(self.src); // Skip '\''
+        while let CharType::UpperWord | CharType::LowerWord | CharType::Number =
+            self.char_type(next_cur.peek(self.src))
+        {
+            next_cur.proceed(self.src);
+        }
+        let begin = match cur {
+            Some(c) => c.pos,
+            None => self.cur.pos,
+        };
+        let s = &self.src[(begin + 1)..next_cur.pos];
+        Token::Label(s.to_string())
+    }
+
     fn read_symbol(&mut self, next_cur: &mut Cursor) -> (Token, Option<LexerState>) {
         let c1 = next_cur.proceed(self.src);
         let c2 = next_cur.peek(self.src);
         let (token, state) = match c1 {
             '(' => (Token::LParen, LexerState::ExprBegin),
             ')' => (Token::RParen, LexerState::ExprEnd),
-            '[' => (Token::LSqBracket, LexerState::ExprBegin),
+            '[' => {
+                if self.state == LexerState::MethodName && c2 == Some(']') {
+                    next_cur.proceed(self.src);
+                    if next_cur.peek(self.src) == Some('=') {
+                        next_cur.proceed(self.src);
+                        (Token::SetMethod, LexerState::ExprBegin)
+                    } else {
+                        (Token::GetMethod, LexerState::ExprBegin)
+                    }
+                } else {
+                    (Token::LSqBracket, LexerState::ExprBegin)
+                }
+            }
             ']' => (Token::RSqBracket, LexerState::ExprEnd),
             '{' => (Token::LBrace, LexerState::ExprBegin),
             '}' => (Token::RBrace, LexerState::ExprEnd),
@@ -383,19 +461,25 @@ impl<'a> Lexer<'a> {
                 } else if c2 == Some('>') {
                     next_cur.proceed(self.src);
                     (Token::RightArrow, LexerState::ExprBegin)
+                } else if c2 == Some('=') {
+                    next_cur.proceed(self.src);
+                    (Token::MinusEq, LexerState::ExprBegin)
                 } else if self.is_unary(c2) {
                     (Token::UnaryMinus, LexerState::ExprBegin)
                 } else {
                     (Token::BinaryMinus, LexerState::ExprBegin)
                 }
             }
-            '*' => (Token::Mul, LexerState::ExprBegin),
-            '/' => (Token::Div, LexerState::ExprBegin),
-            '%' => (Token::Mod, LexerState::ExprBegin),
+            '*' => self.with_eq(next_cur, Token::Mul, Token::MulEq),
+            '/' => self.with_eq(next_cur, Token::Div, Token::DivEq),
+            '%' => self.with_eq(next_cur, Token::Mod, Token::ModEq),
             '=' => {
                 if c2 == Some('=') {
                     next_cur.proceed(self.src);
                     (Token::EqEq, LexerState::ExprBegin)
+                } else if c2 == Some('>') {
+                    next_cur.proceed(self.src);
+                    (Token::FatArrow, LexerState::ExprBegin)
                 } else {
                     (Token::Equal, LexerState::ExprBegin)
                 }
@@ -414,7 +498,7 @@ impl<'a> Lexer<'a> {
                     (Token::LessEq, LexerState::ExprBegin)
                 } else if c2 == Some('<') {
                     next_cur.proceed(self.src);
-                    (Token::LShift, LexerState::ExprBegin)
+                    self.with_eq(next_cur, Token::LShift, Token::LShiftEq)
                 } else {
                     (Token::LessThan, LexerState::ExprBegin)
                 }
@@ -425,7 +509,7 @@ impl<'a> Lexer<'a> {
                     (Token::GreaterEq, LexerState::ExprBegin)
                 } else if c2 == Some('>') {
                     next_cur.proceed(self.src);
-                    (Token::RShift, LexerState::ExprBegin)
+                    self.with_eq(next_cur, Token::RShift, Token::RShiftEq)
                 } else {
                     (Token::GreaterThan, LexerState::ExprBegin)
                 }
@@ -433,7 +517,14 @@ impl<'a> Lexer<'a> {
             '.' => (Token::Dot, LexerState::ExprBegin),
             '@' => (Token::At, LexerState::ExprBegin),
             '~' => (Token::Tilde, LexerState::ExprBegin),
-            '?' => (Token::Question, LexerState::ExprBegin),
+            '?' => {
+                if c2 == Some('.') {
+                    next_cur.proceed(self.src);
+                    (Token::QuestionDot, LexerState::ExprBegin)
+                } else {
+                    (Token::Question, LexerState::ExprBegin)
+                }
+            }
             ',' => (Token::Comma, LexerState::ExprBegin),
             ':' => {
                 if c2 == Some(':') {
@@ -446,20 +537,20 @@ impl<'a> Lexer<'a> {
             '&' => {
                 if c2 == Some('&') {
                     next_cur.proceed(self.src);
-                    (Token::AndAnd, LexerState::ExprBegin)
+                    self.with_eq(next_cur, Token::AndAnd, Token::AndAndEq)
                 } else {
-                    (Token::And, LexerState::ExprBegin)
+                    self.with_eq(next_cur, Token::And, Token::AndEq)
                 }
             }
             '|' => {
                 if c2 == Some('|') {
                     next_cur.proceed(self.src);
-                    (Token::OrOr, LexerState::ExprBegin)
+                    self.with_eq(next_cur, Token::OrOr, Token::OrOrEq)
                 } else {
-                    (Token::Or, LexerState::ExprBegin)
+                    self.with_eq(next_cur, Token::Or, Token::OrEq)
                 }
             }
-            '^' => (Token::Xor, LexerState::ExprBegin),
+            '^' => self.with_eq(next_cur, Token::Xor, Token::XorEq),
             c => {
                 // TODO: this should be lexing error
                 panic!("unknown symbol: {}", c)
@@ -468,69 +559,178 @@ impl<'a> Lexer<'a> {
         (token, Some(state))
     }
 
+    /// Return `with_eq` if the next char is `=` (eg. `*=`), or `token` otherwise
+    fn with_eq(&self, next_cur: &mut Cursor, token: Token, with_eq: Token) -> (Token, LexerState) {
+        if self.state != LexerState::MethodName && next_cur.peek(self.src) == Some('=') {
+            next_cur.proceed(self.src);
+            (with_eq, LexerState::ExprBegin)
+        } else {
+            (token, LexerState::ExprBegin)
+        }
+    }
+
+    /// Whether the `?` or `!` (`c`) after a name is a part of the name
+    fn is_name_suffix(&self, c: Option<char>, next_char: Option<char>) -> bool {
+        match next_char {
+            // `x!=y`
+            Some('=') => false,
+            // `x?.foo` is a safe call but `a.x?.foo` is a call of `x?`
+            Some('.') if c == Some('?') => {
+                self.state == LexerState::MethodName
+                    || self.current_token == Token::Dot
+                    || self.current_token == Token::QuestionDot
+            }
+            // `x?1:2` is a conditional expression
+            Some(c2) if c == Some('?') => {
+                !(c2.is_alphanumeric() || c2 == '_' || c2 == '"' || c2 == '@')
+            }
+            _ => true,
+        }
+    }
+
     fn is_unary(&self, next_char: Option<char>) -> bool {
         match self.state {
             LexerState::ExprBegin => true,
             LexerState::ExprEnd => false,
             LexerState::ExprArg => self.current_token == Token::Space && next_char != Some(' '),
             LexerState::MethodName => false,
+            LexerState::StrLiteral => false,
         }
     }
 
+    /// Read a number literal. (eg. `123`, `1_000`, `0xff`, `0o755`, `0b1010`,
+    /// `1.5`, `1e-3`.) The value is calculated by the parser
     fn read_number(&mut self, next_cur: &mut Cursor, cur: Option<&Cursor>) -> Token {
+        let begin = match cur {
+            Some(c) => c.pos,
+            None => self.cur.pos,
+        };
+        self.skip_number_chars(next_cur);
+        let is_decimal = !self.src[begin..next_cur.pos].starts_with("0x")
+            && !self.src[begin..next_cur.pos].starts_with("0o")
+            && !self.src[begin..next_cur.pos].starts_with("0b");
+        if is_decimal {
+            // Fraction (`1.foo` is a method call)
+            if next_cur.peek(self.src) == Some('.')
+                && self.char_type(next_cur.peek2(self.src)) == CharType::Number
+            {
+                next_cur.proceed(self.src);
+                self.skip_number_chars(next_cur);
+            }
+            // Sign of the exponent
+            if self.src[begin..next_cur.pos].ends_with(&['e', 'E'][..])
+                && (next_cur.peek(self.src) == Some('+') || next_cur.peek(self.src) == Some('-'))
+                && self.char_type(next_cur.peek2(self.src)) == CharType::Number
+            {
+                next_cur.proceed(self.src);
+                self.skip_number_chars(next_cur);
+            }
+        }
+        let s = &self.src[begin..next_cur.pos];
+        if is_valid_number(s) {
+            Token::Number(s.to_string())
+        } else {
+            Token::LexError(format!("invalid number literal `{}'", s))
+        }
+    }
+
+    /// Skip digits, letters and `_` (letters are checked by `is_valid_number`)
+    fn skip_number_chars(&self, next_cur: &mut Cursor) {
+        while let CharType::UpperWord | CharType::LowerWord | CharType::Number =
+            self.char_type(next_cur.peek(self.src))
+        {
+            next_cur.proceed(self.src);
+        }
+    }
+
+    /// Read a string literal until the closing `"` or `#{`.
+    /// Escape sequences are expanded here
+    fn read_str(&mut self, next_cur: &mut Cursor) -> (Token, Option<LexerState>) {
+        next_cur.proceed(self.src); // Skip '"' (or '}' of `#{...}`)
+        let mut content = String::new();
+        let mut error = None;
         loop {
-            match self.char_type(next_cur.peek(self.src)) {
-                CharType::Number => {
+            match next_cur.peek(self.src) {
+                None => {
+                    let msg = "unterminated string literal".to_string();
+                    return (Token::LexError(msg), None);
+                }
+                Some('"') => {
                     next_cur.proceed(self.src);
+                    break;
                 }
-                CharType::UpperWord | CharType::LowerWord => {
-                    // TODO: this should be lexing error
-                    panic!("need space after a number")
+                Some('#') if next_cur.peek2(self.src) == Some('{') => {
+                    next_cur.proceed(self.src);
+                    next_cur.proceed(self.src);
+                    if let Some(msg) = error {
+                        return (Token::LexError(msg), None);
+                    }
+                    return (
+                        Token::StrWithInterpolation(content),
+                        Some(LexerState::ExprBegin),
+                    );
                 }
-                CharType::Symbol => {
-                    if next_cur.peek(self.src) == Some('.') {
-                        if self.char_type(next_cur.peek2(self.src)) == CharType::Number {
-                            next_cur.proceed(self.src);
-                            next_cur.proceed(self.src);
-                        } else {
-                            break;
-                        }
-                    } else {
-                        break;
+                Some('\\') => {
+                    next_cur.proceed(self.src);
+                    match self.read_escape_sequence(next_cur) {
+                        Ok(c) => content.push(c),
+                        // Keep reading to find the end of the string
+                        Err(msg) => error = error.or(Some(msg)),
                     }
                 }
-                _ => break,
+                _ => {
+                    content.push(next_cur.proceed(self.src));
+                }
             }
         }
-        let begin = match cur {
-            Some(c) => c.pos,
-            None => self.cur.pos,
+        match error {
+            Some(msg) => (Token::LexError(msg), None),
+            None => (Token::Str(content), Some(LexerState::ExprEnd)),
+        }
+    }
+
+    /// Read the escape sequence after a `\` and return the char it represents
+    fn read_escape_sequence(&self, next_cur: &mut Cursor) -> Result<char, String> {
+        let c = match next_cur.peek(self.src) {
+            Some(c) => c,
+            None => return Err("unterminated string literal".to_string()),
         };
-        Token::Number(self.src[begin..next_cur.pos].to_string())
+        next_cur.proceed(self.src);
+        match c {
+            'n' => Ok('\n'),
+            't' => Ok('\t'),
+            'r' => Ok('\r'),
+            'e' => Ok('\x1b'),
+            '0' => Ok('\0'),
+            '\\' | '"' | '#' => Ok(c),
+            'u' => self.read_unicode_escape(next_cur),
+            _ => Err(format!("invalid escape sequence `\\{}'", c)),
+        }
     }
 
-    fn read_str(&mut self, next_cur: &mut Cursor, cur: Option<&Cursor>) -> Token {
+    /// Read `{3042}` of `\u{3042}`
+    fn read_unicode_escape(&self, next_cur: &mut Cursor) -> Result<char, String> {
+        if next_cur.peek(self.src) != Some('{') {
+            return Err("`\\u' must be followed by `{'".to_string());
+        }
         next_cur.proceed(self.src);
+        let mut digits = String::new();
         loop {
             match next_cur.peek(self.src) {
-                None => {
-                    // TODO: should be a LexError
-                    panic!("found unterminated string");
-                }
-                Some('"') => {
+                Some('}') => {
                     next_cur.proceed(self.src);
                     break;
                 }
-                _ => {
-                    next_cur.proceed(self.src);
+                Some(c) if c.is_ascii_hexdigit() && digits.len() < 6 => {
+                    digits.push(next_cur.proceed(self.src));
                 }
+                _ => return Err("invalid unicode escape (expected `\\u{XXXX}')".to_string()),
             }
         }
-        let begin = match cur {
-            Some(c) => c.pos,
-            None => self.cur.pos,
-        };
-        Token::Str(self.src[(begin + 1)..(next_cur.pos - 1)].to_string())
+        u32::from_str_radix(&digits, 16)
+            .ok()
+            .and_then(std::char::from_u32)
+            .ok_or_else(|| format!("invalid unicode escape `\\u{{{}}}'", digits))
     }
 
     fn read_eof(&mut self) -> Token {
@@ -548,6 +748,7 @@ impl<'a> Lexer<'a> {
             '"' => CharType::Str,
             '0'..='9' => CharType::Number,
             '@' => CharType::IVar,
+            '\'' => CharType::Label,
             '(' | ')' | '[' | ']' | '<' | '>' | '{' | '}' | '+' | '-' | '*' | '/' | '%' | '='
             | '!' | '^' | '.' | '~' | '?' | ',' | ':' | '|' | '&' => CharType::Symbol,
             'A'..='Z' => CharType::UpperWord,
@@ -555,3 +756,36 @@ impl<'a> Lexer<'a> {
         }
     }
 }
+
+/// Return true if `s` is a valid number literal
+fn is_valid_number(s: &str) -> bool {
+    if let Some(digits) = s.strip_prefix("0x") {
+        return is_valid_digits(digits, 16);
+    } else if let Some(digits) = s.strip_prefix("0o") {
+        return is_valid_digits(digits, 8);
+    } else if let Some(digits) = s.strip_prefix("0b") {
+        return is_valid_digits(digits, 2);
+    }
+    let (mantissa, exponent) = match s.find(&['e', 'E'][..]) {
+        Some(i) => (&s[..i], Some(&s[i + 1..])),
+        None => (s, None),
+    };
+    let mantissa_ok = match mantissa.find('.') {
+        Some(i) => is_valid_digits(&mantissa[..i], 10) && is_valid_digits(&mantissa[i + 1..], 10),
+        None => is_valid_digits(mantissa, 10),
+    };
+    let exponent_ok = match exponent {
+        Some(e) => is_valid_digits(e.strip_prefix(&['+', '-'][..]).unwrap_or(e), 10),
+        None => true,
+    };
+    mantissa_ok && exponent_ok
+}
+
+/// Return true if `s` consists of digits of `radix` and `_` between them
+fn is_valid_digits(s: &str, radix: u32) -> bool {
+    !s.is_empty()
+        && !s.starts_with('_')
+        && !s.ends_with('_')
+        && !s.contains("__")
+        && s.chars().all(|c| c == '_' || c.is_digit(radix))
+}
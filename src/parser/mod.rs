@@ -16,42 +16,76 @@ mod expression_parser;
 pub mod lexer;
 pub mod token;
 use crate::ast;
-use crate::error::Error;
+use crate::error::{Error, Errors};
+use crate::location::SourceFile;
+use crate::parser::lexer::Cursor;
 use crate::parser::lexer::Lexer;
 use crate::parser::lexer::LexerState;
 pub use crate::parser::token::Token;
+use std::collections::HashSet;
+use std::rc::Rc;
 
 pub struct Parser<'a> {
     pub lexer: Lexer<'a>,
+    /// The file being parsed (None if parsing a string)
+    file: Option<Rc<SourceFile>>,
+    /// End position of the last non-space token
+    last_token_end: Cursor,
+    /// Errors recovered from so far
+    errors: Vec<Error>,
+    /// True while parsing the arguments of a method call without
+    /// parentheses (`do ... end` belongs to the outer call there)
+    no_do_block: bool,
+    /// Arities of the `Fn` classes appeared in the source
+    fn_arities: HashSet<usize>,
     /// For debug print
     pub lv: usize,
 }
 
 impl<'a> Parser<'a> {
     pub fn new(src: &str) -> Parser {
-        Parser {
-            lexer: Lexer::new(src),
-            lv: 0,
-        }
+        Parser::new_with_state(src, LexerState::ExprBegin)
     }
 
     pub fn new_with_state(src: &str, state: LexerState) -> Parser {
         Parser {
             lexer: Lexer::new_with_state(src, state),
+            file: None,
+            last_token_end: Cursor::new(),
+            errors: vec![],
+            no_do_block: false,
+            fn_arities: HashSet::new(),
             lv: 0,
         }
     }
 
-    pub fn parse(src: &str) -> Result<ast::Program, Error> {
+    pub fn parse(src: &str) -> Result<ast::Program, Errors> {
         let mut parser = Parser::new(src);
         parser.parse_program()
     }
 
-    fn parse_program(&mut self) -> Result<ast::Program, Error> {
+    /// Parse a source file. Locations in the AST refer to `file`
+    pub fn parse_file(file: &Rc<SourceFile>) -> Result<ast::Program, Errors> {
+        let mut parser = Parser::new(&file.content);
+        parser.file = Some(file.clone());
+        parser.parse_program()
+    }
+
+    fn parse_program(&mut self) -> Result<ast::Program, Errors> {
+        let result = self.parse_program_();
+        Errors::check(result, std::mem::take(&mut self.errors))
+    }
+
+    fn parse_program_(&mut self) -> Result<ast::Program, Error> {
         self.skip_wsn();
-        let toplevel_items = self.parse_toplevel_items()?;
+        let mut requires = vec![];
+        let toplevel_items = self.parse_toplevel_items(&mut requires)?;
         self.expect_eof()?;
-        Ok(ast::Program { toplevel_items })
+        Ok(ast::Program {
+            toplevel_items,
+            requires,
+            fn_arities: std::mem::take(&mut self.fn_arities),
+        })
     }
 
     pub fn expect_eof(&self) -> Result<(), Error> {
@@ -65,23 +99,56 @@ impl<'a> Parser<'a> {
         Ok(())
     }
 
-    fn parse_toplevel_items(&mut self) -> Result<Vec<ast::TopLevelItem>, Error> {
+    fn parse_toplevel_items(
+        &mut self,
+        requires: &mut Vec<ast::Require>,
+    ) -> Result<Vec<ast::TopLevelItem>, Error> {
         let mut items = vec![];
         loop {
-            match self.current_token() {
-                Token::KwClass => {
-                    items.push(ast::TopLevelItem::Def(self.parse_class_definition()?));
-                }
-                Token::KwDef => {
-                    items.push(ast::TopLevelItem::Def(self.parse_method_definition()?));
+            if self.current_token_is(Token::KwRequire) {
+                match self.parse_require() {
+                    Ok(req) => requires.push(req),
+                    Err(e) => self.recover(e)?,
                 }
+                self.skip_wsn();
+                continue;
+            }
+            let item = match self.current_token() {
+                Token::KwClass => self.parse_class_definition().map(ast::TopLevelItem::Def),
+                Token::KwModule => self.parse_module_definition().map(ast::TopLevelItem::Def),
+                Token::KwEnum => self.parse_enum_definition().map(ast::TopLevelItem::Def),
+                Token::KwDef => self.parse_method_definition().map(ast::TopLevelItem::Def),
                 Token::Eof | Token::KwEnd => break,
-                _ => {
-                    items.push(ast::TopLevelItem::Expr(self.parse_expr()?));
-                }
+                _ => self.parse_expr().map(ast::TopLevelItem::Expr),
+            };
+            match item {
+                Ok(item) => items.push(item),
+                Err(e) => self.recover(e)?,
             }
             self.skip_wsn();
         }
         Ok(items)
     }
+
+    /// Parse `require "foo"`
+    fn parse_require(&mut self) -> Result<ast::Require, Error> {
+        let begin = self.current_position();
+        assert!(self.consume(Token::KwRequire));
+        self.skip_ws();
+        match self.current_token() {
+            Token::Str(s) => {
+                let path = s.to_string();
+                self.consume_token();
+                Ok(ast::Require {
+                    path,
+                    locs: self.span_from(&begin),
+                })
+            }
+            token => Err(parse_error!(
+                self,
+                "expected a string after `require' but got {:?}",
+                token
+            )),
+        }
+    }
 }
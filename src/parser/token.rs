@@ -7,8 +7,13 @@ pub enum Token {
     UpperWord(String),
     LowerWord(String),
     IVar(String),
+    Label(String), // 'foo
     Number(String),
     Str(String),
+    /// Beginning (or middle) of a string literal which ends with `#{`
+    StrWithInterpolation(String),
+    /// Invalid token (eg. a string with an invalid escape sequence)
+    LexError(String),
     // Symbols
     LParen,       //  (
     RParen,       //  )
@@ -31,11 +36,13 @@ pub enum Token {
     LessEq,       //  <=
     GreaterEq,    //  >=
     Equal,        //  =
+    FatArrow,     //  =>
     Bang,         //  !
     Dot,          //  .
     At,           //  @
     Tilde,        //  ~
     Question,     //  ?
+    QuestionDot,  //  ?.
     Comma,        //  ,
     Colon,        //  :
     ColonColon,   //  ::
@@ -48,7 +55,20 @@ pub enum Token {
     RShift,       //  >>
     UPlusMethod,  //  +@
     UMinusMethod, //  -@
+    GetMethod,    //  []
+    SetMethod,    //  []=
     PlusEq,       //  +=
+    MinusEq,      //  -=
+    MulEq,        //  *=
+    DivEq,        //  /=
+    ModEq,        //  %=
+    LShiftEq,     //  <<=
+    RShiftEq,     //  >>=
+    AndEq,        //  &=
+    OrEq,         //  |=
+    XorEq,        //  ^=
+    AndAndEq,     //  &&=
+    OrOrEq,       //  ||=
     // Keywords
     KwClass,
     KwEnd,
@@ -67,6 +87,24 @@ pub enum Token {
     KwSelf,
     KwTrue,
     KwFalse,
+    KwRequire,
+    KwModule,
+    KwInclude,
+    KwExtend,
+    KwSuper,
+    KwReturn,
+    KwNext,
+    KwLoop,
+    KwDo,
+    KwUntil,
+    KwBegin,
+    KwRescue,
+    KwEnsure,
+    KwEnum,
+    KwCase,
+    KwMatch,
+    KwWhen,
+    KwLet,
 }
 
 impl Token {
@@ -81,10 +119,27 @@ impl Token {
     }
 
     pub fn is_assignment_token(&self) -> bool {
-        match self {
-            Token::Equal | Token::PlusEq => true,
-            _ => false,
-        }
+        *self == Token::Equal || self.compound_assignment_op().is_some()
+    }
+
+    /// Return the operator of a compound assignment (eg. `"+"` for `+=`)
+    pub fn compound_assignment_op(&self) -> Option<&'static str> {
+        let op = match self {
+            Token::PlusEq => "+",
+            Token::MinusEq => "-",
+            Token::MulEq => "*",
+            Token::DivEq => "/",
+            Token::ModEq => "%",
+            Token::LShiftEq => "<<",
+            Token::RShiftEq => ">>",
+            Token::AndEq => "&",
+            Token::OrEq => "|",
+            Token::XorEq => "^",
+            Token::AndAndEq => "&&",
+            Token::OrOrEq => "||",
+            _ => return None,
+        };
+        Some(op)
     }
 
     /// Return true if a value may start with this token
@@ -99,8 +154,11 @@ impl Token {
             Token::UpperWord(_) => true,
             Token::LowerWord(_) => true,
             Token::IVar(_) => true,
+            Token::Label(_) => false,
             Token::Number(_) => true,
             Token::Str(_) => true,
+            Token::StrWithInterpolation(_) => true,
+            Token::LexError(_) => true,
             // Symbols
             Token::LParen => true,        //  (
             Token::RParen => false,       //  )
@@ -123,11 +181,13 @@ impl Token {
             Token::LessEq => false,       //  <=
             Token::GreaterEq => false,    //  >=
             Token::Equal => false,        //  =
+            Token::FatArrow => false,     //  =>
             Token::Bang => true,          //  !
             Token::Dot => false,          //  .
             Token::At => true,            //  @
             Token::Tilde => true,         //  ~
             Token::Question => false,     //  ?
+            Token::QuestionDot => false,  //  ?.
             Token::Comma => false,        //  ,
             Token::Colon => true,         //  :
             Token::ColonColon => true,    //  ::
@@ -140,7 +200,20 @@ impl Token {
             Token::RShift => false,       //  >>
             Token::UPlusMethod => false,  //  +@
             Token::UMinusMethod => false, //  -@
+            Token::GetMethod => false,    //  []
+            Token::SetMethod => false,    //  []=
             Token::PlusEq => false,       //  +=
+            Token::MinusEq => false,      //  -=
+            Token::MulEq => false,        //  *=
+            Token::DivEq => false,        //  /=
+            Token::ModEq => false,        //  %=
+            Token::LShiftEq => false,     //  <<=
+            Token::RShiftEq => false,     //  >>=
+            Token::AndEq => false,        //  &=
+            Token::OrEq => false,         //  |=
+            Token::XorEq => false,        //  ^=
+            Token::AndAndEq => false,     //  &&=
+            Token::OrOrEq => false,       //  ||=
             // Keywords
             Token::KwClass => false,
             Token::KwEnd => false,
@@ -159,6 +232,24 @@ impl Token {
             Token::KwSelf => true,
             Token::KwTrue => true,
             Token::KwFalse => true,
+            Token::KwRequire => false,
+            Token::KwModule => false,
+            Token::KwInclude => false,
+            Token::KwExtend => false,
+            Token::KwSuper => true,
+            Token::KwReturn => false,
+            Token::KwNext => false,
+            Token::KwLoop => true,
+            Token::KwDo => false,
+            Token::KwUntil => true,
+            Token::KwBegin => true,
+            Token::KwRescue => false,
+            Token::KwEnsure => false,
+            Token::KwEnum => false,
+            Token::KwCase => false,
+            Token::KwMatch => true,
+            Token::KwWhen => false,
+            Token::KwLet => false,
         }
     }
 }
@@ -1,46 +1,188 @@
 use crate::error::*;
+use crate::library::LibraryExports;
+use crate::loader::Loader;
+use crate::location::SourceFile;
 use std::env;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::rc::Rc;
 
 /// Generate .ll from .sk
 pub fn compile<P: AsRef<Path>>(filepath: P) -> Result<(), Box<dyn std::error::Error>> {
+    compile_with_builtin_dir(filepath, None)
+}
+
+/// Generate .ll from .sk, loading builtin/*.sk from `builtin_dir` if given
+/// (or `SHIIKA_BUILTIN_DIR` if set). Otherwise the precompiled corelib is used
+/// if it is up to date, or the embedded builtin/*.sk are compiled
+pub fn compile_with_builtin_dir<P: AsRef<Path>>(
+    filepath: P,
+    builtin_dir: Option<&Path>,
+) -> Result<(), Box<dyn std::error::Error>> {
     let path = filepath
         .as_ref()
         .to_str()
         .expect("failed to unwrap filepath")
         .to_string();
-    let builtin = load_builtin()?;
-    let str = builtin
-        + &fs::read_to_string(filepath)
-            .map_err(|e| runner_error(format!("{} is not utf8", path), e))?;
-    let ast = crate::parser::Parser::parse(&str)?;
-    let corelib = crate::corelib::Corelib::create();
-    let hir = crate::hir::build(ast, corelib)?;
+    let builtin_dir = builtin_dir_override(builtin_dir);
+    let imports = match builtin_dir {
+        Some(_) => None,
+        None => load_corelib_exports(),
+    };
+    let mut loader = Loader::new(search_paths());
+    if imports.is_none() {
+        for file in load_builtin(builtin_dir.as_deref())? {
+            loader.add_file(file);
+        }
+    }
+    loader.load(Path::new(&path))?;
+    let ast = loader.finish()?;
+    let hir = match imports {
+        Some(imports) => crate::hir::build_with_library(ast, imports)?,
+        None => crate::hir::build(ast, crate::corelib::Corelib::create())?,
+    };
     crate::code_gen::run(&hir, &(path + ".ll"))?;
     Ok(())
 }
 
-fn load_builtin() -> Result<String, Box<dyn std::error::Error>> {
-    let mut s = String::new();
-    let dir = fs::read_dir("builtin").map_err(|e| runner_error("./builtin not found", e))?;
-    for item in dir {
+/// Compile builtin/*.sk and corelib into the precompiled corelib
+/// (builtin.o and exports.json in `corelib_dir()`)
+pub fn build_corelib() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = corelib_dir()
+        .ok_or_else(|| plain_runner_error("neither SHIIKA_CORELIB_DIR nor HOME is set"))?;
+    fs::create_dir_all(&dir)
+        .map_err(|e| runner_error(format!("failed to create {}", dir.display()), e))?;
+
+    let mut loader = Loader::new(vec![]);
+    for file in load_builtin(None)? {
+        loader.add_file(file);
+    }
+    let ast = loader.finish()?;
+    let hir = crate::hir::build(ast, crate::corelib::Corelib::create())?;
+    let bc_path = dir.join("builtin.bc");
+    crate::code_gen::run_library(&hir, &bc_path)?;
+
+    let mut cmd = Command::new(env::var("LLC").unwrap_or_else(|_| "llc".to_string()));
+    cmd.arg("-filetype=obj");
+    cmd.arg("-o");
+    cmd.arg(dir.join("builtin.o"));
+    cmd.arg(&bc_path);
+    let status = cmd
+        .status()
+        .map_err(|e| runner_error("failed to run llc", e))?;
+    if !status.success() {
+        return Err(Box::new(plain_runner_error("llc failed")));
+    }
+
+    // Written last so that a half-built corelib is never used
+    let exports = LibraryExports::new(&hir, embedded::CORELIB_FINGERPRINT);
+    exports.save(&dir.join("exports.json"))?;
+    Ok(())
+}
+
+/// Directory of the precompiled corelib
+/// (`SHIIKA_CORELIB_DIR` or ~/.shiika/corelib)
+fn corelib_dir() -> Option<PathBuf> {
+    match env::var_os("SHIIKA_CORELIB_DIR") {
+        Some(dir) => Some(PathBuf::from(dir)),
+        None => env::var_os("HOME").map(|home| PathBuf::from(home).join(".shiika/corelib")),
+    }
+}
+
+/// Return the exports of the precompiled corelib, if it is built by
+/// this version of the compiler
+fn load_corelib_exports() -> Option<LibraryExports> {
+    let path = corelib_dir()?.join("exports.json");
+    if !path.exists() {
+        return None;
+    }
+    // Index of an older compiler may not be loadable; just ignore it
+    let exports = LibraryExports::load(&path).ok()?;
+    if exports.fingerprint == embedded::CORELIB_FINGERPRINT {
+        Some(exports)
+    } else {
+        None
+    }
+}
+
+/// Return the directory to load builtin/*.sk from instead of the embedded
+/// ones (or the precompiled corelib), if specified
+fn builtin_dir_override(builtin_dir: Option<&Path>) -> Option<PathBuf> {
+    builtin_dir
+        .map(|dir| dir.to_path_buf())
+        .or_else(|| env::var_os("SHIIKA_BUILTIN_DIR").map(PathBuf::from))
+}
+
+/// Return the path of builtin.o if the program is compiled with the
+/// precompiled corelib
+fn corelib_object(builtin_dir: Option<&Path>) -> Option<PathBuf> {
+    if builtin_dir_override(builtin_dir).is_some() {
+        return None;
+    }
+    load_corelib_exports()?;
+    Some(corelib_dir()?.join("builtin.o"))
+}
+
+/// Directories to search for `require`d files (taken from `SHIIKA_PATH`)
+fn search_paths() -> Vec<PathBuf> {
+    match env::var_os("SHIIKA_PATH") {
+        Some(paths) => env::split_paths(&paths).collect(),
+        None => vec![],
+    }
+}
+
+/// builtin/*.sk embedded at build time and the fingerprint of the compiler
+/// (generated by build.rs)
+mod embedded {
+    include!(concat!(env!("OUT_DIR"), "/builtin.rs"));
+}
+
+/// Return builtin/*.sk in the order to load
+fn load_builtin(dir: Option<&Path>) -> Result<Vec<Rc<SourceFile>>, Box<dyn std::error::Error>> {
+    let dir = match dir {
+        Some(d) => d,
+        None => {
+            return Ok(embedded::BUILTIN_FILES
+                .iter()
+                .map(|(path, src)| Rc::new(SourceFile::new(*path, src.to_string())))
+                .collect())
+        }
+    };
+    let mut paths = vec![];
+    let entries =
+        fs::read_dir(dir).map_err(|e| runner_error(format!("{} not found", dir.display()), e))?;
+    for item in entries {
         let pathbuf = item?.path();
         let path = pathbuf
             .to_str()
             .ok_or_else(|| plain_runner_error("Filename not utf8"))?;
         if path.ends_with(".sk") {
-            s += &fs::read_to_string(path)
-                .map_err(|e| runner_error(format!("failed to load {}", path), e))?;
+            paths.push(path.to_string());
         }
     }
-    Ok(s)
+    // Sort to make the load order deterministic
+    paths.sort();
+    let mut files = vec![];
+    for path in paths {
+        let src = fs::read_to_string(&path)
+            .map_err(|e| runner_error(format!("failed to load {}", path), e))?;
+        files.push(Rc::new(SourceFile::new(path, src)));
+    }
+    Ok(files)
 }
 
 /// Execute compiled .ll
 pub fn run<P: AsRef<Path>>(sk_path: P) -> Result<(), Box<dyn std::error::Error>> {
-    run_(sk_path, false)?;
+    run_with_builtin_dir(sk_path, None)
+}
+
+/// Execute .ll compiled by `compile_with_builtin_dir`
+pub fn run_with_builtin_dir<P: AsRef<Path>>(
+    sk_path: P,
+    builtin_dir: Option<&Path>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    run_(sk_path, builtin_dir, false)?;
     Ok(())
 }
 
@@ -48,11 +190,12 @@ pub fn run<P: AsRef<Path>>(sk_path: P) -> Result<(), Box<dyn std::error::Error>>
 pub fn run_and_capture<P: AsRef<Path>>(
     sk_path: P,
 ) -> Result<(String, String), Box<dyn std::error::Error>> {
-    run_(sk_path, true)
+    run_(sk_path, None, true)
 }
 
 fn run_<P: AsRef<Path>>(
     sk_path: P,
+    builtin_dir: Option<&Path>,
     capture_out: bool,
 ) -> Result<(String, String), Box<dyn std::error::Error>> {
     let s = sk_path.as_ref().to_str().expect("failed to unwrap sk_path");
@@ -96,6 +239,9 @@ fn run_<P: AsRef<Path>>(
     cmd.arg("-o");
     cmd.arg(out_path.clone());
     cmd.arg(asm_path.clone());
+    if let Some(obj_path) = corelib_object(builtin_dir) {
+        cmd.arg(obj_path);
+    }
     if !cmd.status()?.success() {
         return Err(Box::new(plain_runner_error("clang failed")));
     }
@@ -16,9 +16,10 @@ use crate::hir::class_dict::ClassDict;
 ///
 use crate::names::*;
 use crate::ty;
+use serde::{Deserialize, Serialize};
 
 // Types for a term (types of Shiika values)
-#[derive(PartialEq, Clone)]
+#[derive(PartialEq, Clone, Serialize, Deserialize)]
 pub struct TermTy {
     pub fullname: ClassFullname,
     pub body: TyBody,
@@ -36,7 +37,7 @@ impl std::fmt::Debug for TermTy {
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub enum TyBody {
     // Types corresponds to non-generic class
     // eg. "Int", "String", "Object"
@@ -65,11 +66,20 @@ pub enum TyBody {
     },
     // Type parameter reference eg. `T`
     TyParamRef {
+        kind: TyParamKind,
         name: String,
         idx: usize,
     },
 }
 
+/// Whether a type parameter is of a class (`class A<T>`) or
+/// of a method (`def foo<T>`)
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub enum TyParamKind {
+    Class,
+    Method,
+}
+
 use TyBody::*;
 
 impl TermTy {
@@ -90,12 +100,46 @@ impl TermTy {
         }
     }
 
-    pub fn conforms_to(&self, other: &TermTy) -> bool {
+    /// Return true if `self` is `other` or a subtype of it
+    pub fn conforms_to(&self, other: &TermTy, class_dict: &ClassDict) -> bool {
+        if self.equals_to(other) {
+            return true;
+        }
+        if self.is_never_type() {
+            return true;
+        }
         if let TyParamRef { .. } = other.body {
             return self == &ty::raw("Object"); // The upper bound
         }
-        // TODO: Should respect class hierarchy
-        self.equals_to(other)
+        // Maybe is covariant because it is immutable
+        // (eg. `None`, a `Maybe<Never>`, conforms to `Maybe<Int>`)
+        if let (
+            TySpe {
+                base_name: name1,
+                type_args: args1,
+            },
+            TySpe {
+                base_name: name2,
+                type_args: args2,
+            },
+        ) = (&self.body, &other.body)
+        {
+            if name1 == "Maybe" && name2 == "Maybe" {
+                return args1[0].conforms_to(&args2[0], class_dict);
+            }
+        }
+        class_dict
+            .ancestor_types(self)
+            .iter()
+            .any(|t| t.equals_to(other))
+    }
+
+    /// Returns true when this is the Never type
+    pub fn is_never_type(&self) -> bool {
+        match self.body {
+            TyRaw => self.fullname.0 == "Never",
+            _ => false,
+        }
     }
 
     /// Return true if two types are identical
@@ -116,18 +160,66 @@ impl TermTy {
                 }
             }
             TyClass => Some(ty::raw("Object")),
-            _ => panic!("TODO"),
+            TyGenMeta { base_name, .. } | TySpeMeta { base_name, .. } => {
+                match class_dict.get_superclass(&class_fullname(base_name)) {
+                    Some(scls) => Some(ty::meta(&scls.fullname.0)),
+                    None => Some(ty::class()),
+                }
+            }
+            // TODO #115: superclass may be a specialized class
+            TySpe { base_name, .. } => class_dict
+                .get_superclass(&class_fullname(base_name))
+                .map(|scls| ty::raw(&scls.fullname.0)),
+            TyParamRef { .. } => Some(ty::raw("Object")), // The upper bound
         }
     }
 
-    /// Apply type argments into type parameters
-    pub fn substitute(&self, type_args: &[TermTy]) -> TermTy {
+    /// Apply type argments into type parameters.
+    /// Type parameters are left as is if the corresponding type argument is not given
+    pub fn substitute(&self, class_tyargs: &[TermTy], method_tyargs: &[TermTy]) -> TermTy {
         match &self.body {
-            TyParamRef { idx, .. } => type_args[*idx].clone(),
+            TyParamRef { kind, idx, .. } => {
+                let tyargs = match kind {
+                    TyParamKind::Class => class_tyargs,
+                    TyParamKind::Method => method_tyargs,
+                };
+                tyargs.get(*idx).unwrap_or(self).clone()
+            }
+            TySpe {
+                base_name,
+                type_args,
+            } => ty::spe(
+                base_name,
+                type_args
+                    .iter()
+                    .map(|t| t.substitute(class_tyargs, method_tyargs))
+                    .collect(),
+            ),
+            TySpeMeta {
+                base_name,
+                type_args,
+            } => ty::spe_meta(
+                base_name,
+                type_args
+                    .iter()
+                    .map(|t| t.substitute(class_tyargs, method_tyargs))
+                    .collect(),
+            ),
             _ => self.clone(),
         }
     }
 
+    /// Returns true if this is or contains a type parameter reference
+    pub fn has_typaram_ref(&self) -> bool {
+        match &self.body {
+            TyParamRef { .. } => true,
+            TySpe { type_args, .. } | TySpeMeta { type_args, .. } => {
+                type_args.iter().any(|t| t.has_typaram_ref())
+            }
+            _ => false,
+        }
+    }
+
     pub fn is_specialized(&self) -> bool {
         match self.body {
             TySpe { .. } | TySpeMeta { .. } => true,
@@ -201,7 +293,7 @@ pub fn spe_meta(base_name: &str, type_args: Vec<TermTy>) -> TermTy {
         .collect::<Vec<_>>();
     TermTy {
         fullname: class_fullname(&format!("Meta:{}<{}>", &base_name, &tyarg_names.join(","))),
-        body: TySpe {
+        body: TySpeMeta {
             base_name: base_name.to_string(),
             type_args,
         },
@@ -213,27 +305,29 @@ pub fn ary(type_arg: TermTy) -> TermTy {
     spe("Array", vec![type_arg])
 }
 
-pub fn typaram(name: impl Into<String>, idx: usize) -> TermTy {
+pub fn typaram(name: impl Into<String>, kind: TyParamKind, idx: usize) -> TermTy {
     let s = name.into();
     TermTy {
         // TODO: s is not a class name. `fullname` should be just a String
         fullname: class_fullname(format!("TyParamRef({})", &s)),
-        body: TyParamRef { name: s, idx },
+        body: TyParamRef { kind, name: s, idx },
     }
 }
 
 /// A type parameter
 /// In the future, may have something like +T/-T or in/out
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct TyParam {
     pub name: String,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct MethodSignature {
     pub fullname: MethodFullname,
     pub ret_ty: TermTy,
     pub params: Vec<MethodParam>,
+    /// Type parameters of the method (eg. `U` of `def map<U>`)
+    pub typarams: Vec<TyParam>,
 }
 
 impl MethodSignature {
@@ -249,31 +343,47 @@ impl MethodSignature {
         &self.fullname.first_name
     }
 
+    /// Return a copy of this signature as a method of another class
+    /// (used for the methods of a module included into a class)
+    pub fn with_owner(&self, classname: &ClassFullname) -> MethodSignature {
+        MethodSignature {
+            fullname: method_fullname(classname, &self.fullname.first_name.0),
+            ..self.clone()
+        }
+    }
+
     /// Substitute type parameters with type arguments
-    pub fn specialize(&self, type_args: &[TermTy]) -> MethodSignature {
+    /// (the method is no longer generic if `method_tyargs` is given)
+    pub fn specialize(&self, class_tyargs: &[TermTy], method_tyargs: &[TermTy]) -> MethodSignature {
+        let typarams = if method_tyargs.is_empty() {
+            self.typarams.clone()
+        } else {
+            vec![]
+        };
         MethodSignature {
             fullname: self.fullname.clone(),
-            ret_ty: self.ret_ty.substitute(&type_args),
+            ret_ty: self.ret_ty.substitute(class_tyargs, method_tyargs),
             params: self
                 .params
                 .iter()
-                .map(|param| param.substitute(&type_args))
+                .map(|param| param.substitute(class_tyargs, method_tyargs))
                 .collect(),
+            typarams,
         }
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct MethodParam {
     pub name: String,
     pub ty: TermTy,
 }
 
 impl MethodParam {
-    pub fn substitute(&self, type_args: &[TermTy]) -> MethodParam {
+    pub fn substitute(&self, class_tyargs: &[TermTy], method_tyargs: &[TermTy]) -> MethodParam {
         MethodParam {
             name: self.name.clone(),
-            ty: self.ty.substitute(&type_args),
+            ty: self.ty.substitute(class_tyargs, method_tyargs),
         }
     }
 }
@@ -1,5 +1,5 @@
 use crate::error::Error;
-use crate::hir;
+use crate::hir::class_dict::ClassDict;
 use crate::ty;
 use crate::ty::*;
 
@@ -9,8 +9,12 @@ macro_rules! type_error {
     })
 }
 
-pub fn check_return_value(sig: &MethodSignature, ty: &TermTy) -> Result<(), Error> {
-    if ty.conforms_to(&sig.ret_ty) || sig.ret_ty.is_void_type() {
+pub fn check_return_value(
+    class_dict: &ClassDict,
+    sig: &MethodSignature,
+    ty: &TermTy,
+) -> Result<(), Error> {
+    if ty.conforms_to(&sig.ret_ty, class_dict) || sig.ret_ty.is_void_type() {
         Ok(())
     } else {
         Err(type_error!(
@@ -22,6 +26,60 @@ pub fn check_return_value(sig: &MethodSignature, ty: &TermTy) -> Result<(), Erro
     }
 }
 
+/// Check the type of the value given to `return` in a lambda
+pub fn check_lambda_return_value(
+    class_dict: &ClassDict,
+    ret_ty: &TermTy,
+    ty: &TermTy,
+) -> Result<(), Error> {
+    if ty.conforms_to(ret_ty, class_dict) || ret_ty.is_void_type() {
+        Ok(())
+    } else {
+        Err(type_error!(
+            "the lambda should return {} but returns {}",
+            ret_ty.fullname,
+            ty.fullname
+        ))
+    }
+}
+
+/// Check the value of a local variable declared with a type (`x: Int = 1`)
+pub fn check_lvar_annotation(
+    class_dict: &ClassDict,
+    declared_ty: &TermTy,
+    ty: &TermTy,
+    name: &str,
+) -> Result<(), Error> {
+    if ty.conforms_to(declared_ty, class_dict) {
+        Ok(())
+    } else {
+        Err(type_error!(
+            "`{}' is declared as {} but the value is {}",
+            name,
+            declared_ty.fullname,
+            ty.fullname
+        ))
+    }
+}
+
+/// Check the value of an embedded expression of a string (`"#{x}"`) is
+/// converted to a String by `to_s`
+pub fn check_to_s_ty(
+    class_dict: &ClassDict,
+    receiver_ty: &TermTy,
+    ty: &TermTy,
+) -> Result<(), Error> {
+    if ty.conforms_to(&ty::raw("String"), class_dict) {
+        Ok(())
+    } else {
+        Err(type_error!(
+            "`to_s' of {} should return String but returns {}",
+            receiver_ty.fullname,
+            ty.fullname
+        ))
+    }
+}
+
 pub fn check_logical_operator_ty(ty: &TermTy, on: &str) -> Result<(), Error> {
     if *ty == ty::raw("Bool") {
         Ok(())
@@ -42,8 +100,13 @@ pub fn check_condition_ty(ty: &TermTy, on: &str) -> Result<(), Error> {
     }
 }
 
-pub fn check_reassign_var(orig_ty: &TermTy, new_ty: &TermTy, name: &str) -> Result<(), Error> {
-    if orig_ty.equals_to(new_ty) {
+pub fn check_reassign_var(
+    class_dict: &ClassDict,
+    orig_ty: &TermTy,
+    new_ty: &TermTy,
+    name: &str,
+) -> Result<(), Error> {
+    if new_ty.conforms_to(orig_ty, class_dict) {
         Ok(())
     } else {
         Err(type_error!(
@@ -56,20 +119,18 @@ pub fn check_reassign_var(orig_ty: &TermTy, new_ty: &TermTy, name: &str) -> Resu
 }
 
 pub fn check_method_args(
+    class_dict: &ClassDict,
     sig: &MethodSignature,
     arg_tys: &[&TermTy],
-    receiver_hir: &hir::HirExpression,
-    arg_hirs: &[hir::HirExpression],
 ) -> Result<(), Error> {
     if sig.params.len() != arg_tys.len() {
         return Err(type_error!(
-            "{} takes {} args but got {} (receiver: {:?}, args: {:?})",
+            "{} takes {} args but got {}",
             sig.fullname,
             sig.params.len(),
-            arg_tys.len(),
-            receiver_hir,
-            arg_hirs
-        ));
+            arg_tys.len()
+        )
+        .with_note(params_note(sig)));
     }
 
     sig.params
@@ -78,19 +139,132 @@ pub fn check_method_args(
         .try_for_each(|(param, arg_ty)| {
             let a = arg_ty.upper_bound();
             let p = param.ty.upper_bound();
-            if a.conforms_to(&p) {
+            if a.conforms_to(&p, class_dict) {
                 Ok(())
             } else {
                 Err(type_error!(
-                    "{} takes {} but got {} (receiver: {:?}, args: {:?})",
+                    "{} takes {} but got {}",
                     sig.fullname,
                     param.ty.fullname,
-                    arg_ty.fullname,
-                    receiver_hir,
-                    arg_hirs
-                ))
+                    arg_ty.fullname
+                )
+                .with_note(params_note(sig)))
             }
         })?;
 
     Ok(())
 }
+
+/// eg. "note: parameters of Foo#bar are (a: Int, b: String)"
+fn params_note(sig: &MethodSignature) -> String {
+    let params = sig
+        .params
+        .iter()
+        .map(|p| format!("{}: {}", p.name, p.ty))
+        .collect::<Vec<_>>();
+    format!(
+        "note: parameters of {} are ({})",
+        sig.fullname,
+        params.join(", ")
+    )
+}
+
+/// Check the number of explicit type arguments for a method
+pub fn check_method_tyargs(sig: &MethodSignature, tyargs: &[TermTy]) -> Result<(), Error> {
+    if tyargs.is_empty() || sig.typarams.len() == tyargs.len() {
+        Ok(())
+    } else {
+        Err(type_error!(
+            "{} takes {} type arguments but got {}",
+            sig.fullname,
+            sig.typarams.len(),
+            tyargs.len()
+        ))
+    }
+}
+
+/// Infer the type arguments of a generic method from the argument types
+pub fn infer_method_tyargs(
+    sig: &MethodSignature,
+    arg_tys: &[&TermTy],
+) -> Result<Vec<TermTy>, Error> {
+    let mut tyargs = vec![None; sig.typarams.len()];
+    for (param, arg_ty) in sig.params.iter().zip(arg_tys.iter()) {
+        match_typarams(&param.ty, arg_ty, &mut tyargs);
+    }
+    tyargs
+        .into_iter()
+        .zip(sig.typarams.iter())
+        .map(|(tyarg, typaram)| {
+            tyarg.ok_or_else(|| {
+                type_error!(
+                    "could not infer type parameter `{}' of {}",
+                    typaram.name,
+                    sig.fullname
+                )
+                .with_note(format!(
+                    "note: give it explicitly like `{}<{}>(...)'",
+                    sig.fullname.first_name, typaram.name
+                ))
+            })
+        })
+        .collect()
+}
+
+/// Infer the type arguments of a generic method from the types of the
+/// arguments known so far (None for the others.) Type parameters which cannot
+/// be inferred yet are left as is
+pub fn infer_method_tyargs_partially(
+    sig: &MethodSignature,
+    arg_tys: &[Option<&TermTy>],
+) -> Vec<TermTy> {
+    let mut tyargs = vec![None; sig.typarams.len()];
+    for (param, arg_ty) in sig.params.iter().zip(arg_tys.iter()) {
+        if let Some(ty) = arg_ty {
+            match_typarams(&param.ty, ty, &mut tyargs);
+        }
+    }
+    tyargs
+        .into_iter()
+        .zip(sig.typarams.iter())
+        .enumerate()
+        .map(|(i, (tyarg, typaram))| {
+            tyarg.unwrap_or_else(|| ty::typaram(&typaram.name, TyParamKind::Method, i))
+        })
+        .collect()
+}
+
+/// Find the types for the method type parameters in `param_ty` by matching it
+/// with `arg_ty` (the first one found wins; conflicts are reported by
+/// `check_method_args` later)
+fn match_typarams(param_ty: &TermTy, arg_ty: &TermTy, tyargs: &mut [Option<TermTy>]) {
+    match (&param_ty.body, &arg_ty.body) {
+        (
+            TyBody::TyParamRef {
+                kind: TyParamKind::Method,
+                idx,
+                ..
+            },
+            _,
+        ) => {
+            if tyargs[*idx].is_none() {
+                tyargs[*idx] = Some(arg_ty.clone());
+            }
+        }
+        (
+            TyBody::TySpe {
+                base_name,
+                type_args,
+            },
+            TyBody::TySpe {
+                base_name: arg_base_name,
+                type_args: arg_type_args,
+            },
+        ) if base_name == arg_base_name => {
+            for (p, a) in type_args.iter().zip(arg_type_args.iter()) {
+                match_typarams(p, a, tyargs);
+            }
+        }
+        _ => (),
+    }
+}
@@ -168,6 +168,24 @@ fn test_decimal_literal() {
     assert_eq!(result.unwrap(), ast::decimal_literal(123),)
 }
 
+#[test]
+fn test_number_literal_syntax() {
+    assert_eq!(
+        parse_expr("1_000_000").unwrap(),
+        ast::decimal_literal(1000000)
+    );
+    assert_eq!(parse_expr("0xff").unwrap(), ast::decimal_literal(255));
+    assert_eq!(parse_expr("0o755").unwrap(), ast::decimal_literal(493));
+    assert_eq!(parse_expr("0b1010").unwrap(), ast::decimal_literal(10));
+    // The range is checked in HIR
+    assert_eq!(
+        parse_expr("0xffff_ffff_ffff_ffff").unwrap(),
+        ast::decimal_literal(u64::MAX)
+    );
+    assert_eq!(parse_expr("1e3").unwrap(), ast::float_literal(1000.0));
+    assert_eq!(parse_expr("1.5e-3").unwrap(), ast::float_literal(0.0015));
+}
+
 //
 // Method call (0 args)
 //
@@ -251,3 +269,399 @@ fn test_call_with_space_2() {
         )
     )
 }
+
+//
+// Method call with type arguments
+//
+
+#[test]
+fn test_call_with_type_args() {
+    let result = parse_expr("foo<Int>(1)");
+    assert_eq!(
+        result.unwrap(),
+        ast::generic_method_call(
+            None,
+            "foo",
+            vec![ast::Typ {
+                name: "Int".to_string(),
+                typ_args: vec![]
+            }],
+            vec![ast::decimal_literal(1)],
+            true,
+            false
+        )
+    )
+}
+
+#[test]
+fn test_less_than_is_not_type_args() {
+    let result = parse_expr("a<b");
+    assert_eq!(
+        result.unwrap(),
+        ast::method_call(
+            Some(ast::bare_name("a")),
+            "<",
+            vec![ast::bare_name("b")],
+            false,
+            false
+        )
+    )
+}
+
+#[test]
+fn test_super() {
+    let result = parse_expr("super");
+    assert_eq!(result.unwrap(), ast::super_call(None))
+}
+
+#[test]
+fn test_super_with_args() {
+    let result = parse_expr("super(1)");
+    assert_eq!(
+        result.unwrap(),
+        ast::super_call(Some(vec![ast::decimal_literal(1)]))
+    )
+}
+
+#[test]
+fn test_return() {
+    let result = parse_expr("return");
+    assert_eq!(result.unwrap(), ast::return_expr(None))
+}
+
+#[test]
+fn test_return_with_value() {
+    let result = parse_expr("return 1 + 2");
+    assert_eq!(
+        result.unwrap(),
+        ast::return_expr(Some(ast::method_call(
+            Some(ast::decimal_literal(1)),
+            "+",
+            vec![ast::decimal_literal(2)],
+            false,
+            false
+        )))
+    )
+}
+
+#[test]
+fn test_labeled_loop() {
+    let result = parse_expr("'a: loop do break 'a 1 end");
+    assert_eq!(
+        result.unwrap(),
+        ast::labeled_loop(
+            ast::loop_expr(vec![ast::break_expr(
+                Some("a".to_string()),
+                Some(ast::decimal_literal(1))
+            )]),
+            "a".to_string()
+        )
+    )
+}
+
+#[test]
+fn test_begin_rescue_ensure() {
+    let result = parse_expr("begin foo rescue => e bar ensure baz end");
+    assert_eq!(
+        result.unwrap(),
+        ast::begin_expr(
+            vec![ast::bare_name("foo")],
+            Some("e".to_string()),
+            Some(vec![ast::bare_name("bar")]),
+            Some(vec![ast::bare_name("baz")])
+        )
+    )
+}
+
+#[test]
+fn test_match() {
+    let result = parse_expr("match x when A::B(y, _) if y then 1 else 2 end");
+    assert_eq!(
+        result.unwrap(),
+        ast::match_expr(
+            ast::bare_name("x"),
+            vec![
+                ast::AstMatchClause {
+                    pattern: ast::AstPattern::ExtractorPattern {
+                        names: vec!["A".to_string(), "B".to_string()],
+                        params: vec![
+                            ast::AstPattern::VariablePattern("y".to_string()),
+                            ast::AstPattern::WildcardPattern,
+                        ],
+                    },
+                    guard: Some(ast::bare_name("y")),
+                    body_exprs: vec![ast::decimal_literal(1)],
+                },
+                ast::AstMatchClause {
+                    pattern: ast::AstPattern::WildcardPattern,
+                    guard: None,
+                    body_exprs: vec![ast::decimal_literal(2)],
+                },
+            ]
+        )
+    )
+}
+
+#[test]
+fn test_if_let() {
+    let result = parse_expr("if let x = foo?.bar(1) then x else None end");
+    assert_eq!(
+        result.unwrap(),
+        ast::if_let_expr(
+            "x",
+            ast::safe_method_call(
+                ast::bare_name("foo"),
+                "bar",
+                vec![],
+                vec![ast::decimal_literal(1)]
+            ),
+            vec![ast::bare_name("x")],
+            Some(vec![ast::const_ref(vec!["None".to_string()])])
+        )
+    )
+}
+
+#[test]
+fn test_capitalized_method_call() {
+    let result = parse_expr("Some(1)");
+    assert_eq!(
+        result.unwrap(),
+        ast::method_call(None, "Some", vec![ast::decimal_literal(1)], true, false)
+    )
+}
+
+#[test]
+fn test_do_block() {
+    let result = parse_expr("foo.bar(1) do |x| x end");
+    assert_eq!(
+        result.unwrap(),
+        ast::method_call(
+            Some(ast::bare_name("foo")),
+            "bar",
+            vec![
+                ast::decimal_literal(1),
+                ast::lambda_expr(
+                    vec![ast::BlockParam {
+                        name: "x".to_string(),
+                        opt_typ: None
+                    }],
+                    vec![ast::bare_name("x")],
+                    false
+                )
+            ],
+            true,
+            false
+        )
+    )
+}
+
+#[test]
+fn test_brace_block() {
+    let result = parse_expr("foo { 1 }");
+    assert_eq!(
+        result.unwrap(),
+        ast::method_call(
+            None,
+            "foo",
+            vec![ast::lambda_expr(
+                vec![],
+                vec![ast::decimal_literal(1)],
+                false
+            )],
+            true,
+            false
+        )
+    )
+}
+
+#[test]
+fn test_lambda_without_param_types() {
+    let result = parse_expr("fn(x){ x }");
+    assert_eq!(
+        result.unwrap(),
+        ast::lambda_expr(
+            vec![ast::BlockParam {
+                name: "x".to_string(),
+                opt_typ: None
+            }],
+            vec![ast::bare_name("x")],
+            true
+        )
+    )
+}
+
+#[test]
+fn test_typed_lvar_decl() {
+    let result = parse_expr("x: Int = 1");
+    assert_eq!(
+        result.unwrap(),
+        ast::typed_lvar_decl(
+            "x".to_string(),
+            Some(ast::Typ {
+                name: "Int".to_string(),
+                typ_args: vec![]
+            }),
+            ast::decimal_literal(1),
+            false
+        )
+    )
+}
+
+#[test]
+fn test_fn_typ() {
+    let result = parse_expr("f: (Int, String) -> Bool = g");
+    let typ = |name: &str| ast::Typ {
+        name: name.to_string(),
+        typ_args: vec![],
+    };
+    assert_eq!(
+        result.unwrap(),
+        ast::typed_lvar_decl(
+            "f".to_string(),
+            Some(ast::Typ {
+                name: "Fn2".to_string(),
+                typ_args: vec![typ("Int"), typ("String"), typ("Bool")]
+            }),
+            ast::bare_name("g"),
+            false
+        )
+    )
+}
+
+#[test]
+fn test_string_with_interpolation() {
+    let result = parse_expr("\"a#{b}\\n\"");
+    assert_eq!(
+        result.unwrap(),
+        ast::string_with_interpolation(vec![
+            ast::string_literal("a".to_string()),
+            ast::bare_name("b"),
+            ast::string_literal("\n".to_string()),
+        ])
+    )
+}
+
+#[test]
+fn test_index() {
+    let result = parse_expr("a[1, 2]");
+    assert_eq!(
+        result.unwrap(),
+        ast::method_call(
+            Some(ast::bare_name("a")),
+            "[]",
+            vec![ast::decimal_literal(1), ast::decimal_literal(2)],
+            true,
+            false
+        )
+    )
+}
+
+#[test]
+fn test_index_assign() {
+    let result = parse_expr("a[1] = 2");
+    assert_eq!(
+        result.unwrap(),
+        ast::method_call(
+            Some(ast::bare_name("a")),
+            "[]=",
+            vec![ast::decimal_literal(1), ast::decimal_literal(2)],
+            false,
+            false
+        )
+    )
+}
+
+#[test]
+fn test_compound_assignment() {
+    let result = parse_expr("a -= 1");
+    assert_eq!(
+        result.unwrap(),
+        ast::assignment(
+            ast::bare_name("a"),
+            ast::bin_op_expr(ast::bare_name("a"), "-", ast::decimal_literal(1))
+        )
+    )
+}
+
+#[test]
+fn test_logical_compound_assignment() {
+    let result = parse_expr("a ||= b");
+    assert_eq!(
+        result.unwrap(),
+        ast::logical_or(
+            ast::bare_name("a"),
+            ast::assignment(ast::bare_name("a"), ast::bare_name("b"))
+        )
+    )
+}
+
+#[test]
+fn test_compound_assignment_to_index() {
+    let result = parse_expr("a[0] ||= b");
+    let index = ast::method_call(
+        Some(ast::bare_name("a")),
+        "[]",
+        vec![ast::decimal_literal(0)],
+        true,
+        false,
+    );
+    assert_eq!(
+        result.unwrap(),
+        ast::op_assignment(index, "||", ast::bare_name("b"))
+    )
+}
+
+#[test]
+fn test_method_name_with_suffix() {
+    let result = parse_expr("a.empty? ? b.fill!(1) : c");
+    assert_eq!(
+        result.unwrap(),
+        ast::if_expr(
+            ast::method_call(Some(ast::bare_name("a")), "empty?", vec![], true, true),
+            vec![ast::method_call(
+                Some(ast::bare_name("b")),
+                "fill!",
+                vec![ast::decimal_literal(1)],
+                true,
+                false
+            )],
+            Some(vec![ast::bare_name("c")])
+        )
+    )
+}
+
+#[test]
+fn test_conditional_expr_without_spaces() {
+    let result = parse_expr("x?1:2");
+    assert_eq!(
+        result.unwrap(),
+        ast::if_expr(
+            ast::bare_name("x"),
+            vec![ast::decimal_literal(1)],
+            Some(vec![ast::decimal_literal(2)])
+        )
+    )
+}
+
+#[test]
+fn test_method_call_on_predicate() {
+    let result = parse_expr("a.empty?.to_s");
+    let empty = ast::method_call(Some(ast::bare_name("a")), "empty?", vec![], true, true);
+    assert_eq!(
+        result.unwrap(),
+        ast::method_call(Some(empty), "to_s", vec![], true, true)
+    )
+}
+
+#[test]
+fn test_not_eq_after_name() {
+    let result = parse_expr("a!=b");
+    assert_eq!(
+        result.unwrap(),
+        ast::logical_not(ast::bin_op_expr(
+            ast::bare_name("a"),
+            "==",
+            ast::bare_name("b")
+        ))
+    )
+}
@@ -0,0 +1,452 @@
+use shiika::corelib::Corelib;
+use shiika::error::Error;
+use shiika::location::SourceFile;
+use shiika::parser::Parser;
+use std::rc::Rc;
+
+/// Compile `src` into HIR and return the errors
+fn build_errors(src: &str) -> Vec<Error> {
+    let file = Rc::new(SourceFile::new("test.sk", src.to_string()));
+    let ast = Parser::parse_file(&file).unwrap();
+    match shiika::hir::build(ast, Corelib::create()) {
+        Ok(_) => panic!("expected an error"),
+        Err(errs) => errs.0,
+    }
+}
+
+/// Compile `src` into HIR and return the first error
+fn build_error(src: &str) -> Error {
+    build_errors(src).remove(0)
+}
+
+/// Compile `src` into HIR and return the error message
+fn hir_error(src: &str) -> String {
+    build_error(src).msg
+}
+
+#[test]
+fn test_did_you_mean_variable() {
+    let msg = hir_error("count = 1; cuont");
+    assert_eq!(msg, "variable `cuont' was not found; did you mean `count'?");
+}
+
+#[test]
+fn test_did_you_mean_method() {
+    let src = "
+      class A
+        def length -> Int; 1; end
+      end
+      class B : A; end
+      B.new.lenght
+    ";
+    let msg = hir_error(src);
+    assert_eq!(
+        msg,
+        "method `lenght' not found on B; did you mean `length'?"
+    );
+}
+
+#[test]
+fn test_did_you_mean_ivar() {
+    let src = "
+      class A
+        def initialize
+          @count = 1
+        end
+        def foo -> Int; @cuont; end
+      end
+    ";
+    let msg = hir_error(src);
+    assert_eq!(msg, "ivar `cuont' was not found; did you mean `count'?");
+}
+
+#[test]
+fn test_did_you_mean_constant() {
+    let msg = hir_error("FOO = 1; FOOO");
+    assert_eq!(msg, "constant `FOOO' was not found; did you mean `FOO'?");
+}
+
+#[test]
+fn test_no_suggestion() {
+    let msg = hir_error("count = 1; xyz");
+    assert_eq!(msg, "variable `xyz' was not found");
+}
+
+#[test]
+fn test_error_location() {
+    let err = build_error("x = 1\nx + 1 + foo");
+    assert_eq!(err.to_string(), "test.sk:2:9: variable `foo' was not found");
+}
+
+#[test]
+fn test_error_location_in_method() {
+    let src = "class A\n  def foo -> Int\n    1.bar\n  end\nend";
+    let err = build_error(src);
+    let locs = err.location.unwrap();
+    assert_eq!((locs.begin.line(), locs.begin.col()), (2, 4));
+    assert_eq!((locs.end.line(), locs.end.col()), (2, 9));
+}
+
+#[test]
+fn test_render() {
+    let err = build_error("x = 1\nx + 1 + foo");
+    assert_eq!(
+        err.render(),
+        "ProgramError: variable `foo' was not found
+ --> test.sk:2:9
+  |
+2 | x + 1 + foo
+  |         ^^^
+"
+    );
+}
+
+#[test]
+fn test_render_note() {
+    let src = "class A\n  def foo(a: Int, b: Int); end\nend\nA.new.foo(1, true)";
+    let err = build_error(src);
+    assert_eq!(
+        err.render(),
+        "TypeError: A#foo takes Int but got Bool
+ --> test.sk:4:1
+  |
+4 | A.new.foo(1, true)
+  | ^^^^^^^^^^^^^^^^^^
+  = note: parameters of A#foo are (a: Int, b: Int)
+"
+    );
+}
+
+#[test]
+fn test_multiple_errors() {
+    let src = "
+      class A
+        def foo -> Int
+          1.bar
+          2
+        end
+        def baz -> Int
+          qux
+        end
+      end
+      A.new.foo(1)
+    ";
+    let msgs = build_errors(src)
+        .into_iter()
+        .map(|e| e.msg)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        msgs,
+        vec![
+            "method `bar' not found on Int",
+            "variable `qux' was not found",
+            "A#foo takes 0 args but got 1",
+        ]
+    );
+}
+
+#[test]
+fn test_no_cascading_errors() {
+    let msgs = build_errors("x = foo; x.bar; x + 1")
+        .into_iter()
+        .map(|e| e.msg)
+        .collect::<Vec<_>>();
+    assert_eq!(msgs, vec!["variable `foo' was not found"]);
+}
+
+#[test]
+fn test_uninferable_method_typaram() {
+    let src = "
+      class A
+        def self.make<T> -> Int; 1; end
+      end
+      A.make
+    ";
+    let err = build_error(src);
+    assert_eq!(err.msg, "could not infer type parameter `T' of Meta:A#make");
+}
+
+#[test]
+fn test_include_class() {
+    let src = "
+      class A; end
+      class B
+        include A
+      end
+    ";
+    let msg = hir_error(src);
+    assert_eq!(msg, "`A' is a class, not a module");
+}
+
+#[test]
+fn test_super_out_of_method() {
+    let msg = hir_error("super");
+    assert_eq!(msg, "`super' must be used directly in a method");
+}
+
+#[test]
+fn test_super_method_not_found() {
+    let src = "
+      class A
+        def foo -> Int
+          super
+        end
+      end
+    ";
+    let msg = hir_error(src);
+    assert_eq!(msg, "method `foo' not found in the superclasses of `A'");
+}
+
+#[test]
+fn test_return_out_of_method() {
+    let msg = hir_error("return 1");
+    assert_eq!(msg, "`return' must be used in a method or a lambda");
+}
+
+#[test]
+fn test_return_type_mismatch() {
+    let src = "
+      class A
+        def foo(x: Int) -> Int
+          if x == 0 then return true end
+          x
+        end
+      end
+    ";
+    let msg = hir_error(src);
+    assert_eq!(msg, "A#foo should return Int but returns Bool");
+}
+
+#[test]
+fn test_return_type_mismatch_in_lambda() {
+    let src = "
+      fn(x: Int){
+        if x == 0 then return true end
+        x
+      }
+    ";
+    let msg = hir_error(src);
+    assert_eq!(msg, "the lambda should return Int but returns Bool");
+}
+
+#[test]
+fn test_break_value_in_while() {
+    let msg = hir_error("while true; break 1; end");
+    assert_eq!(msg, "`break' with a value is only allowed in `loop'");
+}
+
+#[test]
+fn test_unknown_loop_label() {
+    let msg = hir_error("'a: while true; next 'b; end");
+    assert_eq!(msg, "loop labeled `'b' not found");
+}
+
+#[test]
+fn test_break_out_of_loop() {
+    let msg = hir_error("while true; fn(){ break }; end");
+    assert_eq!(msg, "`break' out of a loop");
+}
+
+#[test]
+fn test_rescue_var_already_exists() {
+    let msg = hir_error("e = 1; begin; 2; rescue => e; 3; end");
+    assert_eq!(msg, "variable `e' already exists");
+}
+
+#[test]
+fn test_match_not_exhaustive() {
+    let src = "
+      enum Shape
+        case Circle(radius: Int)
+        case Rect(width: Int, height: Int)
+      end
+      class A
+        def foo(s: Shape) -> Int
+          match s
+          when Shape::Circle(_) then 1
+          when Shape::Rect(_, 0) then 2
+          end
+        end
+      end
+    ";
+    let msg = hir_error(src);
+    assert_eq!(msg, "match is not exhaustive; missing `Shape::Rect(_, _)'");
+}
+
+#[test]
+fn test_pattern_var_not_visible_after_match() {
+    let src = "
+      enum Shape
+        case Circle(radius: Int)
+        case Rect(width: Int, height: Int)
+      end
+      class A
+        def foo(s: Shape) -> Int
+          match s
+          when Shape::Circle(r) then r
+          when Shape::Rect(w, h) then w * h
+          end
+          r
+        end
+      end
+    ";
+    let msg = hir_error(src);
+    assert_eq!(msg, "variable `r' was not found; did you mean `s'?");
+}
+
+#[test]
+fn test_pattern_var_reused_with_another_type() {
+    // `x` of the second clause is a different variable (no "already exists")
+    let src = "
+      enum Num
+        case I(value: Int)
+        case F(value: Float)
+      end
+      class A
+        def foo(n: Num) -> Float
+          match n
+          when Num::I(x) then x.to_f
+          when Num::F(x) then x
+          end
+          x
+        end
+      end
+    ";
+    let msg = hir_error(src);
+    assert_eq!(msg, "variable `x' was not found; did you mean `n'?");
+}
+
+#[test]
+fn test_inherit_enum_case() {
+    let src = "
+      enum E
+        case A
+      end
+      class B : E::A; end
+    ";
+    let msg = hir_error(src);
+    assert_eq!(msg, "cannot inherit enum case `E::A'");
+}
+
+#[test]
+fn test_int_literal_out_of_range() {
+    let msg = hir_error("9223372036854775808");
+    assert_eq!(
+        msg,
+        "integer literal 9223372036854775808 is out of the range of Int"
+    );
+    let msg = hir_error("-9223372036854775809");
+    assert_eq!(
+        msg,
+        "integer literal 9223372036854775809 is out of the range of Int"
+    );
+    let msg = hir_error("18446744073709551615.to_i8");
+    assert_eq!(
+        msg,
+        "integer literal 18446744073709551615 is out of the range of Int"
+    );
+}
+
+#[test]
+fn test_enum_new() {
+    let src = "
+      enum E
+        case A
+      end
+      E.new
+    ";
+    let msg = hir_error(src);
+    assert!(msg.starts_with("method `new' not found on Meta:E"));
+}
+
+#[test]
+fn test_if_let_not_maybe() {
+    let msg = hir_error("if let x = 1 then x end");
+    assert_eq!(msg, "`if let' needs a Maybe but got Int");
+}
+
+#[test]
+fn test_maybe_or_type_mismatch() {
+    let msg = hir_error("Some(1) || true");
+    assert_eq!(msg, "rhs of `||' must be Int but got Bool");
+}
+
+#[test]
+fn test_safe_call_on_non_maybe() {
+    let msg = hir_error("1?.abs");
+    assert_eq!(msg, "receiver of `?.' must be a Maybe but got Int");
+}
+
+#[test]
+fn test_block_too_many_params() {
+    let src = "
+      class A
+        def self.foo(f: Fn1<Int, Void>)
+        end
+      end
+      A.foo do |x, y| end
+    ";
+    let msg = hir_error(src);
+    assert_eq!(
+        msg,
+        "expected Fn1<Int,Void> but the block takes 2 parameter(s)"
+    );
+}
+
+#[test]
+fn test_block_param_not_inferred() {
+    let src = "
+      class A
+        def self.foo(f: Object)
+        end
+      end
+      A.foo { |x| x }
+    ";
+    let msg = hir_error(src);
+    assert_eq!(msg, "cannot infer the type of the block parameter `x'");
+}
+
+#[test]
+fn test_lambda_param_not_inferred() {
+    let msg = hir_error("f = fn(x){ x }");
+    assert_eq!(msg, "cannot infer the type of the lambda parameter `x'");
+}
+
+#[test]
+fn test_lambda_return_type_mismatch() {
+    let msg = hir_error("f: Fn1<Int, Int> = fn(x){ true }");
+    assert_eq!(msg, "the lambda should return Int but returns Bool");
+}
+
+#[test]
+fn test_lambda_param_type_mismatch() {
+    let msg = hir_error("f: Fn1<Int, Int> = fn(x: Bool){ 1 }");
+    assert_eq!(msg, "the lambda parameter `x' should be Int but is Bool");
+}
+
+#[test]
+fn test_lambda_arity_mismatch() {
+    let msg = hir_error("f: Fn1<Int, Int> = fn(){ 1 }");
+    assert_eq!(
+        msg,
+        "expected Fn1<Int,Int> but the lambda takes 0 parameter(s)"
+    );
+}
+
+#[test]
+fn test_lvar_annotation_mismatch() {
+    let msg = hir_error("x: Int = true");
+    assert_eq!(msg, "`x' is declared as Int but the value is Bool");
+}
+
+#[test]
+fn test_to_s_not_returning_string() {
+    let src = "
+      class A
+        def to_s -> Int; 1; end
+      end
+      \"a#{A.new}\"
+    ";
+    let msg = hir_error(src);
+    assert_eq!(msg, "`to_s' of A should return String but returns Int");
+}
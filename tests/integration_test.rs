@@ -1,8 +1,15 @@
 use shiika::error::*;
+use std::env;
 use std::fs;
 
 #[test]
 fn test_compile_and_run() -> Result<(), Box<dyn std::error::Error>> {
+    // Use the corelib built from this tree (not the one in ~/.shiika)
+    env::set_var(
+        "SHIIKA_CORELIB_DIR",
+        concat!(env!("CARGO_MANIFEST_DIR"), "/target/corelib"),
+    );
+    shiika::runner::build_corelib()?;
     let paths = fs::read_dir("tests/sk/")?;
     for item in paths {
         let pathbuf = item?.path();
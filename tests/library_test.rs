@@ -0,0 +1,124 @@
+use shiika::corelib::Corelib;
+use shiika::hir::Hir;
+use shiika::library::LibraryExports;
+use shiika::loader::Loader;
+use shiika::location::SourceFile;
+use shiika::names::*;
+use shiika::parser::Parser;
+use std::fs;
+use std::rc::Rc;
+
+/// Compile builtin/*.sk and corelib and return the exports
+fn builtin_exports() -> LibraryExports {
+    let mut paths = fs::read_dir("builtin")
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().map_or(false, |ext| ext == "sk"))
+        .collect::<Vec<_>>();
+    paths.sort();
+    let mut loader = Loader::new(vec![]);
+    for path in paths {
+        loader.load(&path).unwrap();
+    }
+    let hir = shiika::hir::build(loader.finish().unwrap(), Corelib::create()).unwrap();
+    LibraryExports::new(&hir, "test")
+}
+
+/// Compile `src` into HIR with the builtin library
+fn build_with_library(src: &str) -> Result<Hir, shiika::error::Errors> {
+    let file = Rc::new(SourceFile::new("test.sk", src.to_string()));
+    let ast = Parser::parse_file(&file).unwrap();
+    shiika::hir::build_with_library(ast, builtin_exports())
+}
+
+#[test]
+fn test_save_and_load() {
+    let exports = builtin_exports();
+    let path = std::env::temp_dir().join("shiika_library_test.json");
+    exports.save(&path).unwrap();
+    let loaded = LibraryExports::load(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(loaded.fingerprint, "test");
+    assert_eq!(loaded.sk_classes, exports.sk_classes);
+    assert_eq!(loaded.sk_methods, exports.sk_methods);
+    assert_eq!(loaded.constants, exports.constants);
+}
+
+#[test]
+fn test_only_the_program_is_compiled() {
+    let src = "
+      class A
+        def foo -> Int
+          1 + 2
+        end
+      end
+      A.new.foo
+    ";
+    let hir = build_with_library(src).unwrap();
+    let mut classes = hir
+        .sk_methods
+        .keys()
+        .map(|c| c.0.as_str())
+        .collect::<Vec<_>>();
+    classes.sort();
+    assert_eq!(classes, vec!["A", "Meta:A"]);
+    assert!(hir.constants.contains_key(&const_fullname("::A")));
+    assert!(!hir.constants.contains_key(&const_fullname("::Int")));
+    assert!(!hir.constants.contains_key(&const_fullname("::Void")));
+}
+
+#[test]
+fn test_type_error_with_library() {
+    let errs = build_with_library("1 + true").unwrap_err();
+    assert_eq!(errs.0.len(), 1);
+}
+
+#[test]
+fn test_if_let_var_not_visible_after_end() {
+    let errs = build_with_library("if let x = Some(1) then x end; x").unwrap_err();
+    assert_eq!(errs.0[0].msg, "variable `x' was not found");
+}
+
+#[test]
+fn test_if_let_var_reused_with_another_type() {
+    // `x` of the second `if let` is a different variable (no "already exists")
+    let src = "if let x = Some(1) then x end; if let x = Some(true) then x end; x";
+    let errs = build_with_library(src).unwrap_err();
+    assert_eq!(errs.0[0].msg, "variable `x' was not found");
+}
+
+#[test]
+fn test_maybe_new_is_hidden() {
+    let errs = build_with_library("Maybe.new(1, true)").unwrap_err();
+    assert_eq!(errs.0[0].msg, "method `new' not found on Meta:Maybe");
+}
+
+#[test]
+fn test_adding_methods_to_library_class() {
+    let src = "
+      class Int
+        def foo -> Int; 1; end
+      end
+    ";
+    let errs = build_with_library(src).unwrap_err();
+    assert_eq!(
+        errs.0[0].msg,
+        "cannot add methods to `Int' because it is in the precompiled library"
+    );
+}
+
+#[test]
+fn test_fn_class_generated_on_demand() {
+    let src = "
+      f: (Int, Int, Int, Int, Int, Int, Int, Int, Int, Int, Int, Int) -> Int =
+        fn(a, b, c, d, e, f, g, h, i, j, k, l){ a + l }
+      f.call(1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12)
+    ";
+    let hir = build_with_library(src).unwrap();
+    assert!(hir.sk_classes.contains_key(&class_fullname("Fn12")));
+    let methods = &hir.sk_methods[&class_fullname("Fn12")];
+    assert!(methods.iter().any(|m| m.signature.first_name().0 == "call"));
+    assert!(hir.sk_methods.contains_key(&class_fullname("Meta:Fn12")));
+    assert!(!hir.sk_methods.contains_key(&class_fullname("Fn1")));
+}
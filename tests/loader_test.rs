@@ -0,0 +1,54 @@
+use shiika::ast;
+use shiika::loader::Loader;
+use std::fs;
+use std::path::Path;
+
+/// Return the names of the toplevel classes
+fn class_names(program: &ast::Program) -> Vec<String> {
+    program
+        .toplevel_items
+        .iter()
+        .filter_map(|item| match item {
+            ast::TopLevelItem::Def(ast::Definition::ClassDefinition { name, .. }) => {
+                Some(name.0.clone())
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn test_required_files_come_first() {
+    let mut loader = Loader::new(vec![]);
+    loader.load(Path::new("tests/sk/require.sk")).unwrap();
+    let program = loader.finish().unwrap();
+    assert_eq!(class_names(&program), vec!["Farewell", "Greeter"]);
+}
+
+#[test]
+fn test_search_paths() {
+    let dir = std::env::temp_dir().join("shiika_loader_test");
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("main.sk");
+    fs::write(&path, "require \"farewell\"\n").unwrap();
+
+    let mut loader = Loader::new(vec!["tests/sk/lib".into()]);
+    loader.load(&path).unwrap();
+    let program = loader.finish().unwrap();
+    assert_eq!(class_names(&program), vec!["Farewell"]);
+}
+
+#[test]
+fn test_file_not_found() {
+    let dir = std::env::temp_dir().join("shiika_loader_test");
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("not_found.sk");
+    fs::write(&path, "1\nrequire \"no_such_file\"\n").unwrap();
+
+    let mut loader = Loader::new(vec![]);
+    loader.load(&path).unwrap();
+    let errs = loader.finish().unwrap_err();
+    assert_eq!(errs.0.len(), 1);
+    assert_eq!(errs.0[0].msg, "cannot find `no_such_file' to require");
+    assert_eq!(errs.0[0].location.as_ref().unwrap().begin.line(), 1);
+}
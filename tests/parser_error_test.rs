@@ -0,0 +1,100 @@
+use shiika::parser::Parser;
+
+/// Parse `src` and return the error messages
+fn parse_errors(src: &str) -> Vec<String> {
+    match Parser::parse(src) {
+        Ok(_) => panic!("expected an error"),
+        Err(errs) => errs.0.into_iter().map(|e| e.msg).collect(),
+    }
+}
+
+#[test]
+fn test_resync_at_newline() {
+    let src = "
+      class A
+        def foo
+          1 + * 2
+          p(1)
+          var 1 = 2
+        end
+      end
+      x = )
+      p(2)
+    ";
+    let msgs = parse_errors(src);
+    assert_eq!(
+        msgs,
+        vec![
+            "unexpected token: Mul",
+            "invalid var name: Number(\"1\")",
+            "unexpected token: RParen",
+        ]
+    );
+}
+
+#[test]
+fn test_resync_at_end() {
+    let src = "
+      def foo
+        1 + end
+      p(1)
+    ";
+    let msgs = parse_errors(src);
+    assert_eq!(msgs, vec!["unexpected token: KwEnd"]);
+}
+
+#[test]
+fn test_label_without_loop() {
+    let msgs = parse_errors("'a: 1");
+    assert_eq!(
+        msgs,
+        vec!["a label must be followed by a loop but got Number(\"1\")"]
+    );
+}
+
+#[test]
+fn test_invalid_escape_sequence() {
+    let msgs = parse_errors("p(\"a\\qb\")");
+    assert_eq!(msgs, vec!["invalid escape sequence `\\q'"]);
+}
+
+#[test]
+fn test_invalid_unicode_escape() {
+    let msgs = parse_errors("p(\"\\u{110000}\")");
+    assert_eq!(msgs, vec!["invalid unicode escape `\\u{110000}'"]);
+}
+
+#[test]
+fn test_unterminated_string() {
+    let msgs = parse_errors("p(\"abc");
+    assert_eq!(msgs, vec!["unterminated string literal"]);
+}
+
+#[test]
+fn test_invalid_number_literal() {
+    assert_eq!(parse_errors("p 0x"), vec!["invalid number literal `0x'"]);
+    assert_eq!(
+        parse_errors("p 0b102"),
+        vec!["invalid number literal `0b102'"]
+    );
+    assert_eq!(
+        parse_errors("p 1__000"),
+        vec!["invalid number literal `1__000'"]
+    );
+    assert_eq!(parse_errors("p 1e"), vec!["invalid number literal `1e'"]);
+    assert_eq!(
+        parse_errors("p 12abc"),
+        vec!["invalid number literal `12abc'"]
+    );
+}
+
+#[test]
+fn test_number_literal_out_of_range() {
+    let msgs = parse_errors("p 18446744073709551616");
+    assert_eq!(
+        msgs,
+        vec!["integer literal `18446744073709551616' is out of range"]
+    );
+    let msgs = parse_errors("p 1e400");
+    assert_eq!(msgs, vec!["float literal `1e400' is out of range"]);
+}